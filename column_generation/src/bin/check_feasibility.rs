@@ -1,4 +1,4 @@
-use shared::{Site, Segment, Vehicle, Battery};
+use shared::{Horizon, InstanceData, InstanceFile, InstanceManifest};
 
 
 
@@ -8,6 +8,7 @@ use colored::*;
 use rust_hawktracer::*;
 
 use column_generation::fixed_size::check_feasibility::CheckFeasibility;
+use column_generation::lp::Backend;
 use clap::{App, Arg};
 
 
@@ -23,6 +24,8 @@ pub fn main() {
         buffer_size: 4096,
     });
 
+    let default_solver = Backend::default().to_string();
+
     let matches = App::new("CheckFeasibility")
 
 
@@ -31,15 +34,17 @@ pub fn main() {
             .long("vehicles")
             .value_name("FILE")
             .help("Vehicles to load")
-            .required(true)
+            .required_unless("instance")
             .takes_value(true))
 
         .arg(Arg::with_name("battery")
             .short("b")
             .long("battery")
             .value_name("FILE")
-            .help("Battery Config to load")
-            .required(true)
+            .help("Battery Config to load, repeat or use one table per profile for several battery profiles")
+            .required_unless("instance")
+            .multiple(true)
+            .number_of_values(1)
             .takes_value(true))
 
         .arg( Arg::with_name("percent_infeasible_allowed")
@@ -51,28 +56,81 @@ pub fn main() {
             .long("trips")
             .value_name("FILE")
             .help("Trips to load")
-            .required(true)
+            .required_unless("instance")
             .takes_value(true))
         .arg(Arg::with_name("sites")
             .short("s")
             .long("sites")
             .value_name("FILE")
             .help("Sites to load")
-            .required(true)
+            .required_unless("instance")
+            .takes_value(true))
+        .arg(Arg::with_name("period_length")
+            .long("period_length")
+            .value_name("MINUTES")
+            .help("Length of a period in minutes, unless set by the instance manifest")
+            .default_value("5")
+            .takes_value(true))
+        .arg(Arg::with_name("horizon")
+            .long("horizon")
+            .value_name("MINUTES")
+            .help("Length of the planning horizon in minutes, later periods wrap around to its start, unless set by the instance manifest")
+            .default_value("1440")
+            .takes_value(true))
+        .arg(Arg::with_name("instance")
+            .short("i")
+            .long("instance")
+            .value_name("FILE")
+            .help("Instance manifest listing sites, battery profiles and scenarios")
+            .conflicts_with_all(&["vehicles", "trips", "battery"])
+            .takes_value(true))
+        .arg(Arg::with_name("scenario")
+            .long("scenario")
+            .value_name("NAME")
+            .help("Scenario of the instance manifest to load, defaults to the first")
+            .requires("instance")
+            .takes_value(true))
+        .arg(Arg::with_name("solver")
+            .long("solver")
+            .value_name("SOLVER")
+            .help("LP and MIP solver, gurobi or the licence-free simplex")
+            .possible_values(&["gurobi", "simplex"])
+            .default_value(&default_solver)
+            .takes_value(true))
+        .arg(Arg::with_name("seed")
+            .long("seed")
+            .value_name("SEED")
+            .help("Seed of all random decisions and of the Gurobi solver")
+            .default_value("12345")
             .takes_value(true))
         .get_matches();
 
 
-    let vehicles_path = matches.value_of("vehicles").unwrap();
-    let trips_path =  matches.value_of("trips").unwrap();
-    let sites_path =matches.value_of("sites").unwrap();
-    let battery_path =matches.value_of("battery").unwrap();
+    let mut manifest = match matches.value_of("instance") {
+        Some(instance_path) => InstanceManifest::load(instance_path).unwrap_or_else(|e| panic!("{}", e)),
+        None => InstanceManifest::from_paths(
+            matches.value_of("sites").unwrap(),
+            &matches.values_of("battery").unwrap().collect::<Vec<&str>>(),
+            &[(matches.value_of("vehicles").unwrap(), matches.value_of("trips").unwrap())],
+        ),
+    };
+    // a site solution given explicitly replaces the sites of the manifest
+    if let Some(sites_path) = matches.value_of("sites") {
+        manifest.sites = InstanceFile::new(sites_path);
+    }
+    let scenario = manifest.scenario(matches.value_of("scenario")).expect("Unknown scenario");
+    let vehicles_path = scenario.vehicles.path.as_str();
 
 
-    let sites = Site::load(sites_path);
-    let segments = Segment::load(&sites, trips_path);
-    let battery = Battery::load(battery_path);
-    let vehicles = Vehicle::load(&segments,vehicles_path,&battery);
+    let horizon = manifest.horizon.unwrap_or_else(|| Horizon::new(
+        matches.value_of("period_length").unwrap().parse().expect("Invalid period_length"),
+        matches.value_of("horizon").unwrap().parse().expect("Invalid horizon"),
+    ).unwrap_or_else(|e| panic!("{}", e)));
+    let seed : u64 = matches.value_of("seed").unwrap().parse().expect("Invalid seed");
+    let solver : Backend = matches.value_of("solver").unwrap().parse().unwrap_or_else(|e| panic!("{}", e));
+    let mut instance = InstanceData::new(&manifest).unwrap_or_else(|e| panic!("{}", e));
+    let vehicles = instance.add_scenario(scenario).unwrap_or_else(|e| panic!("{}", e)).vehicles.clone();
+    let (sites, segments) = (&instance.sites, &instance.segments);
 
     let num_infeasible_allowed = ((matches.value_of("percent_infeasible_allowed").unwrap().parse::<f64>().unwrap()) * vehicles.len() as f64).round() as usize;
 
@@ -80,7 +138,7 @@ pub fn main() {
 
 
 
-    match CheckFeasibility::has_feasibility_error(&sites, &segments, vehicles, num_infeasible_allowed)  {
+    match CheckFeasibility::has_feasibility_error(&sites, &segments, vehicles, num_infeasible_allowed, &horizon, seed, solver)  {
         None => {
 
             println!("{} IS FEASIBLE", vehicles_path);
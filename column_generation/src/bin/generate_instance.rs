@@ -0,0 +1,122 @@
+use shared::{generate_instance, GeneratorConfig, Horizon, Location, Shift};
+
+use std::path::Path;
+use clap::{App, Arg};
+
+
+/// Writes a synthetic instance with sites, trips, vehicles, a battery and an instance manifest
+/// to a directory. Everything is derived from the seed, so the same arguments give the same files.
+
+pub fn main() {
+
+    let matches = App::new("GenerateInstance")
+
+        .arg(Arg::with_name("output")
+            .short("o")
+            .long("output")
+            .value_name("DIR")
+            .help("Directory to write the instance to")
+            .required(true)
+            .takes_value(true))
+        .arg(Arg::with_name("center")
+            .long("center")
+            .value_name("LAT,LON")
+            .help("Center of the city")
+            .default_value("52.52,13.405")
+            .takes_value(true))
+        .arg(Arg::with_name("radius")
+            .long("radius")
+            .value_name("KM")
+            .help("Radius of the city")
+            .default_value("10")
+            .takes_value(true))
+        .arg(Arg::with_name("sites")
+            .long("sites")
+            .value_name("NUM")
+            .help("Number of candidate sites")
+            .default_value("20")
+            .takes_value(true))
+        .arg(Arg::with_name("vehicles")
+            .long("vehicles")
+            .value_name("NUM")
+            .help("Fleet size")
+            .default_value("10")
+            .takes_value(true))
+        .arg(Arg::with_name("shift")
+            .long("shift")
+            .value_name("START-END")
+            .help("Working hours as minutes since the start of the horizon, e.g. 360-960. Repeat for several shifts, vehicles are assigned in turn. Defaults to 360-960 and 840-1440")
+            .multiple(true)
+            .number_of_values(1)
+            .takes_value(true))
+        .arg(Arg::with_name("trip_km")
+            .long("trip_km")
+            .value_name("MIN-MAX")
+            .help("Range of customer trip lengths in km")
+            .default_value("2-15")
+            .takes_value(true))
+        .arg(Arg::with_name("idle_minutes")
+            .long("idle_minutes")
+            .value_name("MIN-MAX")
+            .help("Range of the free time between customer trips in minutes")
+            .default_value("10-60")
+            .takes_value(true))
+        .arg(Arg::with_name("period_length")
+            .long("period_length")
+            .value_name("MINUTES")
+            .help("Length of a period in minutes")
+            .default_value("5")
+            .takes_value(true))
+        .arg(Arg::with_name("horizon")
+            .long("horizon")
+            .value_name("MINUTES")
+            .help("Length of the planning horizon in minutes")
+            .default_value("1440")
+            .takes_value(true))
+        .arg(Arg::with_name("seed")
+            .long("seed")
+            .value_name("SEED")
+            .help("Seed of all random decisions")
+            .default_value("12345")
+            .takes_value(true))
+        .get_matches();
+
+
+    fn range<T: std::str::FromStr + PartialOrd>(name: &str, value: &str) -> (T, T) {
+        let mut bounds = value.splitn(2, '-').map(|v| v.trim().parse::<T>().ok());
+        match (bounds.next().flatten(), bounds.next().flatten()) {
+            (Some(min), Some(max)) if min <= max => (min, max),
+            (Some(_), Some(_)) => panic!("Invalid {} {}, MIN is larger than MAX", name, value),
+            _ => panic!("Invalid {} {}, expected MIN-MAX", name, value),
+        }
+    }
+
+    let center: Vec<f32> = matches.value_of("center").unwrap().split(',').map(|v| v.trim().parse().expect("Invalid center")).collect();
+    assert_eq!(center.len(), 2, "Invalid center, expected LAT,LON");
+
+    let config = GeneratorConfig {
+        center: Location::new(center[0], center[1]),
+        city_radius_km: matches.value_of("radius").unwrap().parse().expect("Invalid radius"),
+        num_sites: matches.value_of("sites").unwrap().parse().expect("Invalid number of sites"),
+        num_vehicles: matches.value_of("vehicles").unwrap().parse().expect("Invalid number of vehicles"),
+        shifts: match matches.values_of("shift") {
+            Some(shifts) => shifts.map(|shift| {
+                let (start, end): (u32, u32) = range("shift", shift);
+                assert!(start < end, "Invalid shift {}, ends before it starts", shift);
+                Shift { start_minute: start, length_minutes: end - start }
+            }).collect(),
+            None => GeneratorConfig::default().shifts,
+        },
+        trip_km: range("trip_km", matches.value_of("trip_km").unwrap()),
+        idle_minutes: range("idle_minutes", matches.value_of("idle_minutes").unwrap()),
+        horizon: Horizon::new(
+            matches.value_of("period_length").unwrap().parse().expect("Invalid period_length"),
+            matches.value_of("horizon").unwrap().parse().expect("Invalid horizon"),
+        ).unwrap_or_else(|e| panic!("{}", e)),
+        seed: matches.value_of("seed").unwrap().parse().expect("Invalid seed"),
+    };
+
+    let output = Path::new(matches.value_of("output").unwrap());
+    generate_instance(&config).unwrap_or_else(|e| panic!("{}", e)).write_to_dir(output).expect("Could not write instance");
+    eprintln!("Wrote instance to {}", output.join("instance.toml").display());
+}
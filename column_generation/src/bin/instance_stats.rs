@@ -0,0 +1,100 @@
+use shared::{instance_stats, Horizon, InstanceData, InstanceManifest};
+
+use serde_json::json;
+use clap::{App, Arg};
+
+
+/// Prints json statistics of every scenario of an instance: fleet size, driven distances
+/// and energy demand, free segments, their potential sites and the vehicles that do not need to charge.
+
+pub fn main() {
+
+    let matches = App::new("InstanceStats")
+
+
+        .arg(Arg::with_name("vehicles")
+            .short("v")
+            .long("vehicles")
+            .value_name("FILE")
+            .help("Vehicles to describe, one per scenario")
+            .required_unless("instance")
+            .multiple(true)
+            .min_values(1)
+            .takes_value(true))
+
+        .arg(Arg::with_name("battery")
+            .short("b")
+            .long("battery")
+            .value_name("FILE")
+            .help("Battery Config to load, repeat or use one table per profile for several battery profiles")
+            .required_unless("instance")
+            .multiple(true)
+            .number_of_values(1)
+            .takes_value(true))
+
+        .arg(Arg::with_name("trips")
+            .short("t")
+            .long("trips")
+            .value_name("FILE")
+            .help("Trips to describe, one per scenario")
+            .required_unless("instance")
+            .multiple(true)
+            .min_values(1)
+            .takes_value(true))
+        .arg(Arg::with_name("sites")
+            .short("s")
+            .long("sites")
+            .value_name("FILE")
+            .help("Sites to load")
+            .required_unless("instance")
+            .takes_value(true))
+        .arg(Arg::with_name("period_length")
+            .long("period_length")
+            .value_name("MINUTES")
+            .help("Length of a period in minutes, unless set by the instance manifest")
+            .default_value("5")
+            .takes_value(true))
+        .arg(Arg::with_name("horizon")
+            .long("horizon")
+            .value_name("MINUTES")
+            .help("Length of the planning horizon in minutes, later periods wrap around to its start, unless set by the instance manifest")
+            .default_value("1440")
+            .takes_value(true))
+        .arg(Arg::with_name("instance")
+            .short("i")
+            .long("instance")
+            .value_name("FILE")
+            .help("Instance manifest listing sites, battery profiles and scenarios")
+            .conflicts_with_all(&["vehicles", "trips", "battery", "sites"])
+            .takes_value(true))
+        .get_matches();
+
+
+    let manifest = match matches.value_of("instance") {
+        Some(instance_path) => InstanceManifest::load(instance_path).unwrap_or_else(|e| panic!("{}", e)),
+        None => InstanceManifest::from_paths(
+            matches.value_of("sites").unwrap(),
+            &matches.values_of("battery").unwrap().collect::<Vec<&str>>(),
+            &matches.values_of("vehicles").unwrap().zip(matches.values_of("trips").unwrap()).collect::<Vec<(&str,&str)>>(),
+        ),
+    };
+
+    let horizon = manifest.horizon.unwrap_or_else(|| Horizon::new(
+        matches.value_of("period_length").unwrap().parse().expect("Invalid period_length"),
+        matches.value_of("horizon").unwrap().parse().expect("Invalid horizon"),
+    ).unwrap_or_else(|e| panic!("{}", e)));
+    let base = InstanceData::new(&manifest).unwrap_or_else(|e| panic!("{}", e));
+
+    let scenarios: Vec<serde_json::Value> = manifest.scenarios.iter().map(|scenario| {
+        let mut instance = base.clone();
+        let vehicles = &instance.add_scenario(scenario).unwrap_or_else(|e| panic!("{}", e)).vehicles;
+        instance_stats(&scenario.name, vehicles, &horizon).to_json()
+    }).collect();
+
+    println!("{:#}", json!({
+        "minutes_per_period": horizon.minutes_per_period,
+        "periods": horizon.periods,
+        "sites": base.sites.len(),
+        "scenarios": scenarios,
+    }));
+}
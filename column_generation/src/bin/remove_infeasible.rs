@@ -1,4 +1,4 @@
-use shared::{Site, Segment, Vehicle, Battery};
+use shared::{Horizon, InstanceData, InstanceFile, InstanceManifest, Vehicle};
 
 
 use std::{io};
@@ -28,15 +28,17 @@ pub fn main() {
             .long("vehicles")
             .value_name("FILE")
             .help("Vehicles to load")
-            .required(true)
+            .required_unless("instance")
             .takes_value(true))
 
         .arg(Arg::with_name("battery")
             .short("b")
             .long("battery")
             .value_name("FILE")
-            .help("Battery Config to load")
-            .required(true)
+            .help("Battery Config to load, repeat or use one table per profile for several battery profiles")
+            .required_unless("instance")
+            .multiple(true)
+            .number_of_values(1)
             .takes_value(true))
 
         .arg(Arg::with_name("trips")
@@ -44,36 +46,73 @@ pub fn main() {
             .long("trips")
             .value_name("FILE")
             .help("Trips to load")
-            .required(true)
+            .required_unless("instance")
             .takes_value(true))
         .arg(Arg::with_name("sites")
             .short("s")
             .long("sites")
             .value_name("FILE")
             .help("Sites to load")
-            .required(true)
+            .required_unless("instance")
+            .takes_value(true))
+        .arg(Arg::with_name("period_length")
+            .long("period_length")
+            .value_name("MINUTES")
+            .help("Length of a period in minutes, unless set by the instance manifest")
+            .default_value("5")
+            .takes_value(true))
+        .arg(Arg::with_name("horizon")
+            .long("horizon")
+            .value_name("MINUTES")
+            .help("Length of the planning horizon in minutes, later periods wrap around to its start, unless set by the instance manifest")
+            .default_value("1440")
+            .takes_value(true))
+        .arg(Arg::with_name("instance")
+            .short("i")
+            .long("instance")
+            .value_name("FILE")
+            .help("Instance manifest listing sites, battery profiles and scenarios")
+            .conflicts_with_all(&["vehicles", "trips", "battery"])
+            .takes_value(true))
+        .arg(Arg::with_name("scenario")
+            .long("scenario")
+            .value_name("NAME")
+            .help("Scenario of the instance manifest to load, defaults to the first")
+            .requires("instance")
             .takes_value(true))
         .get_matches();
 
 
-    let vehicles_path = matches.value_of("vehicles").unwrap();
-    let trips_path =  matches.value_of("trips").unwrap();
-    let sites_path =matches.value_of("sites").unwrap();
-    let battery_path =matches.value_of("battery").unwrap();
+    let mut manifest = match matches.value_of("instance") {
+        Some(instance_path) => InstanceManifest::load(instance_path).unwrap_or_else(|e| panic!("{}", e)),
+        None => InstanceManifest::from_paths(
+            matches.value_of("sites").unwrap(),
+            &matches.values_of("battery").unwrap().collect::<Vec<&str>>(),
+            &[(matches.value_of("vehicles").unwrap(), matches.value_of("trips").unwrap())],
+        ),
+    };
+    // a site solution given explicitly replaces the sites of the manifest
+    if let Some(sites_path) = matches.value_of("sites") {
+        manifest.sites = InstanceFile::new(sites_path);
+    }
+    let scenario = manifest.scenario(matches.value_of("scenario")).expect("Unknown scenario");
 
 
 
-    let sites = Site::load(sites_path);
-    let segments = Segment::load(&sites, trips_path);
-    let battery = Battery::load(battery_path);
-    let vehicles = Vehicle::load(&segments,vehicles_path,&battery);
+    let horizon = manifest.horizon.unwrap_or_else(|| Horizon::new(
+        matches.value_of("period_length").unwrap().parse().expect("Invalid period_length"),
+        matches.value_of("horizon").unwrap().parse().expect("Invalid horizon"),
+    ).unwrap_or_else(|e| panic!("{}", e)));
+    let mut instance = InstanceData::new(&manifest).unwrap_or_else(|e| panic!("{}", e));
+    let vehicles = instance.add_scenario(scenario).unwrap_or_else(|e| panic!("{}", e)).vehicles.clone();
+    let (sites, segments) = (&instance.sites, &instance.segments);
 
 
     eprintln!("{}","♞ Loading Data Completed".on_green().bold());
 
 
 
-    let feasible_vehicles =  CheckFeasibility::get_potentially_feasible(&sites, &segments, &vehicles);
+    let feasible_vehicles =  CheckFeasibility::get_potentially_feasible(&sites, &segments, &vehicles, &horizon);
 
     Vehicle::output(&feasible_vehicles.into_iter().cloned().collect::<Vec<Vehicle>>(), io::stdout());
 
@@ -1,20 +1,20 @@
-use shared::{Site, Segment, Vehicle, Battery};
+use shared::{ColumnGenerationIteration, Horizon, InstanceManifest, OccupancySchedule, VehicleTimeline, solution_geojson};
 
 #[cfg(feature = "profiling_enabled")]
 use std::path::PathBuf;
 
 
-extern crate colored;
-use colored::*;
 use std::sync::Arc;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
 use std::sync::atomic::{AtomicBool};
 
-use column_generation::fixed_size::solution_approach_variable::SolutionApproachVariable;
+use column_generation::{Instance, PlannerConfig, SiteSizing, Stabilization};
+use column_generation::lp::Backend;
 use std::time::{Instant};
 use clap::{App, Arg};
-use grb::{Env,param};
-use indexmap::IndexMap;
-use itertools::Itertools;
+use colored::*;
 #[cfg(feature = "profiling_enabled")]
 use rust_hawktracer::{HawktracerInstance, HawktracerListenerType};
 
@@ -25,6 +25,8 @@ pub fn main() {
         shared::setup_metrics_printer();
     }
 
+    let default_solver = Backend::default().to_string();
+
     let matches = App::new("Benders")
 
 
@@ -33,7 +35,7 @@ pub fn main() {
             .long("vehicles")
             .value_name("FILE")
             .help("Vehicles to load")
-                 .required(true)
+                 .required_unless("instance")
                  .multiple(true)
                  .min_values(1)
                  .takes_value(true))
@@ -42,8 +44,10 @@ pub fn main() {
             .short("b")
             .long("battery")
             .value_name("FILE")
-            .help("Battery Config to load")
-            .required(true)
+            .help("Battery Config to load, repeat or use one table per profile for several battery profiles")
+            .required_unless("instance")
+            .multiple(true)
+            .number_of_values(1)
             .takes_value(true))
 
         .arg(Arg::with_name("trips")
@@ -51,7 +55,7 @@ pub fn main() {
             .long("trips")
             .value_name("FILE")
             .help("Trips to load")
-                 .required(true)
+                 .required_unless("instance")
                  .multiple(true)
                  .min_values(1)
                  .takes_value(true)
@@ -62,7 +66,7 @@ pub fn main() {
             .long("sites")
             .value_name("FILE")
             .help("Sites to load")
-            .required(true)
+            .required_unless("instance")
             .takes_value(true))
 
         .arg( Arg::with_name("duration")
@@ -72,10 +76,27 @@ pub fn main() {
 
         .arg( Arg::with_name("workers")
             .long("workers")
+            .help("Threads of the solver for the master problems")
             .env("SLURM_CPUS_PER_TASK")
             .default_value("1")
         )
 
+        .arg( Arg::with_name("pricing_workers")
+            .long("pricing_workers")
+            .help("Threads solving the pricing problems of the vehicles within every scenario")
+            .default_value("1")
+        )
+        .arg( Arg::with_name("scenario_workers")
+            .long("scenario_workers")
+            .help("Threads solving the scenarios of a site configuration concurrently")
+            .default_value("1")
+        )
+        .arg( Arg::with_name("dual_smoothing")
+            .long("dual_smoothing")
+            .help("Weight of the stability center in the duals priced by the column generation, 0 disables smoothing")
+            .default_value("0")
+        )
+
         .arg( Arg::with_name("min_num_sites")
             .long("sites_min")
             .default_value("5")
@@ -91,6 +112,46 @@ pub fn main() {
             .long("charge_processes_file")
             .default_value("/dev/null")
         )
+        .arg( Arg::with_name("solution_output")
+            .long("solution_output")
+            .value_name("FILE")
+            .help("Json report of the open sites, bounds, statistics, scenarios and charge plans")
+            .default_value("/dev/null")
+        )
+        .arg( Arg::with_name("timeline_output")
+            .long("timeline_output")
+            .value_name("FILE")
+            .help("Segments and charging stops of every vehicle with their state of charge, as csv if the file ends in .csv and as json otherwise")
+            .default_value("/dev/null")
+        )
+        .arg( Arg::with_name("column_generation_output")
+            .long("column_generation_output")
+            .value_name("FILE")
+            .help("Csv of the master objective, Lagrangian bound and added columns of every pricing round")
+            .default_value("/dev/null")
+        )
+        .arg( Arg::with_name("occupancy_output")
+            .long("occupancy_output")
+            .value_name("DIR")
+            .help("Directory to write the charger occupancy of the open sites to, as occupancy.csv, charger_slots.csv and site_summary.csv")
+            .takes_value(true)
+        )
+        .arg( Arg::with_name("occupancy_svg")
+            .long("occupancy_svg")
+            .help("Also draw a Gantt chart of the chargers of every scenario")
+            .requires("occupancy_output")
+        )
+        .arg( Arg::with_name("geojson_output")
+            .long("geojson_output")
+            .value_name("FILE")
+            .help("GeoJSON of all candidate sites with their open level, cost and utilisation")
+            .takes_value(true)
+        )
+        .arg( Arg::with_name("geojson_detours")
+            .long("geojson_detours")
+            .help("Also add a line for every charging stop from its segment via the site")
+            .requires("geojson_output")
+        )
         .arg( Arg::with_name("cuts_output")
             .long("cuts_output")
             .default_value("/dev/null")
@@ -119,6 +180,7 @@ pub fn main() {
         )
         .arg( Arg::with_name("site_size")
                   .long("site_size")
+                  .help("Number of chargers to open sites at, or 'variable'")
                   .conflicts_with_all(&["do_variable", "do_low", "do_high_low"])
                   .takes_value(true)
         )
@@ -157,6 +219,38 @@ pub fn main() {
             .takes_value(true)
             .help("Use this value to calculate feasibility in benevolent feasibility check. Set to -1 to use real input number")
         )
+        .arg(Arg::with_name("period_length")
+            .long("period_length")
+            .value_name("MINUTES")
+            .help("Length of a period in minutes, unless set by the instance manifest")
+            .default_value("5")
+            .takes_value(true))
+        .arg(Arg::with_name("horizon")
+            .long("horizon")
+            .value_name("MINUTES")
+            .help("Length of the planning horizon in minutes, later periods wrap around to its start, unless set by the instance manifest")
+            .default_value("1440")
+            .takes_value(true))
+        .arg(Arg::with_name("instance")
+            .short("i")
+            .long("instance")
+            .value_name("FILE")
+            .help("Instance manifest listing sites, battery profiles and scenarios")
+            .conflicts_with_all(&["vehicles", "trips", "battery", "sites"])
+            .takes_value(true))
+        .arg(Arg::with_name("solver")
+            .long("solver")
+            .value_name("SOLVER")
+            .help("LP and MIP solver, gurobi or the licence-free simplex. The simplex keeps a dense tableau and a depth first branch and bound, it is meant for small instances of a few dozen sites and vehicles")
+            .possible_values(&["gurobi", "simplex"])
+            .default_value(&default_solver)
+            .takes_value(true))
+        .arg(Arg::with_name("seed")
+            .long("seed")
+            .value_name("SEED")
+            .help("Seed of all random decisions and of the Gurobi solver")
+            .default_value("12345")
+            .takes_value(true))
         .get_matches();
 
 
@@ -179,119 +273,70 @@ pub fn main() {
 
 
 
-    let sites_path =matches.value_of("sites").unwrap();
-    let battery_path =matches.value_of("battery").unwrap();
+    let manifest = match matches.value_of("instance") {
+        Some(instance_path) => InstanceManifest::load(instance_path).unwrap_or_else(|e| panic!("{}", e)),
+        None => InstanceManifest::from_paths(
+            matches.value_of("sites").unwrap(),
+            &matches.values_of("battery").unwrap().collect::<Vec<&str>>(),
+            &matches.values_of("vehicles").unwrap().zip(matches.values_of("trips").unwrap()).collect::<Vec<(&str,&str)>>(),
+        ),
+    };
     let _duration = matches.value_of("duration").unwrap().parse::<u64>().unwrap();
+    let instance = Instance::new(manifest).unwrap_or_else(|e| panic!("{}", e));
+    let scenarios = &instance.data().scenarios;
+    println!("First is:, {:?}", scenarios.first().map(|scenario| &scenario.name));
+    eprintln!("{}","♞ Loading Data Completed".on_green().bold());
+    println!("Working with {} scenarios", scenarios.len());
+    println!("Working with {} vehicles per scenario", scenarios.first().map_or(0, |scenario| scenario.vehicles.len()));
+
+    let sizing = if matches.is_present("do_low") {
+        SiteSizing::Smallest
+    } else if matches.is_present("do_variable") {
+        SiteSizing::Variable
+    } else if matches.is_present("do_high_low") {
+        SiteSizing::HighToLow
+    } else {
+        match matches.value_of("site_size") {
+            Some("variable") => SiteSizing::Variable,
+            Some(size) => SiteSizing::Fixed(size.parse::<u8>().expect("Invalid site_size")),
+            None => SiteSizing::Largest,
+        }
+    };
 
+    let dual_smoothing : f64 = matches.value_of("dual_smoothing").unwrap().parse().expect("Invalid dual_smoothing");
 
-    let quorum_accept_percent : u8 = matches.value_of("quorum_accept_percent").unwrap().parse().expect("Invalid quorum percent");
-    let benevolent_accept_percent : u8 = matches.value_of("benevolent_accept_percent").unwrap().parse().expect("Invalid benevolent_accept_percent");
-    let max_activate_per_generation : usize = matches.value_of("max_activate_per_generation").unwrap().parse().expect("Invalid max_activate_per_generation");
     let total_num_vehicles : i64 = matches.value_of("total_num_vehicles").unwrap().parse().expect("Invalid total_num_feasible");
 
-
-    let workers = matches.value_of("workers").unwrap().parse::<i32>().unwrap();
-
-    let num_sites = matches.value_of("min_num_sites").unwrap().parse::<usize>().unwrap();
-
-    let sites = Site::load(sites_path);
-    let battery = Battery::load(battery_path);
-
-
-
-
-    let do_low = matches.is_present("do_low");
-    let mut do_variable = matches.is_present("do_variable");
-    let mut do_high_low = matches.is_present("do_high_low");
-
-
-    if let Some(size) = matches.value_of("site_size") {
-        match size {
-            "4" => {},
-            "2" => { do_high_low = true }
-            "variable" => { do_variable = true}
-            _ => unreachable!("Not an option")
-        }
-    }
-
+    let mut config = PlannerConfig::default()
+        .sizing(sizing)
+        .solver(matches.value_of("solver").unwrap().parse().unwrap_or_else(|e| panic!("{}", e)))
+        .min_num_sites(matches.value_of("min_num_sites").unwrap().parse::<usize>().unwrap())
+        .percent_infeasible_allowed(matches.value_of("percent_infeasible_allowed").unwrap().parse::<f64>().unwrap())
+        .threads(matches.value_of("workers").unwrap().parse::<i32>().unwrap())
+        .pricing_workers(matches.value_of("pricing_workers").unwrap().parse::<usize>().unwrap())
+        .scenario_workers(matches.value_of("scenario_workers").unwrap().parse::<usize>().unwrap())
+        .stabilization(if dual_smoothing > 0.0 { Stabilization::Smoothing(dual_smoothing) } else { Stabilization::None })
+        .quorum_accept_percent(matches.value_of("quorum_accept_percent").unwrap().parse().expect("Invalid quorum percent"))
+        .benevolent_accept_percent(matches.value_of("benevolent_accept_percent").unwrap().parse().expect("Invalid benevolent_accept_percent"))
+        .max_activate_per_generation(matches.value_of("max_activate_per_generation").unwrap().parse().expect("Invalid max_activate_per_generation"))
+        .activate_all(matches.is_present("activate_all"))
+        .activate_iis(matches.value_of("activate_iis").unwrap().parse::<bool>().unwrap())
+        .total_num_vehicles(if total_num_vehicles < 0 { None } else { Some(total_num_vehicles as usize) })
+        .horizon(Horizon::new(
+            matches.value_of("period_length").unwrap().parse().expect("Invalid period_length"),
+            matches.value_of("horizon").unwrap().parse().expect("Invalid horizon"),
+        ).unwrap_or_else(|e| panic!("{}", e)))
+        .seed(matches.value_of("seed").unwrap().parse().expect("Invalid seed"));
+    let path = |arg| matches.value_of(arg).filter(|path| *path != "/dev/null").map(String::from);
+    config.charge_processes_output = path("charge_processes_output");
+    config.cuts_output = path("cuts_output");
+    config.cuts_input = path("cuts_input");
+    config.record_iterations = path("column_generation_output").is_some();
+    let horizon = instance.horizon(&config);
 
 
     let should_stop = Arc::new(AtomicBool::new(false));
 
-
-
-    let scenarios : Vec<(&str,&str)> = matches.values_of("vehicles").unwrap().zip(matches.values_of("trips").unwrap()).into_iter()
-        .unique() // keep only first on duplicates. is snakemake gives seed scenario first, then all scenarios again!
-        .collect();
-    println!("First is:, {:?}", &scenarios[0]);
-
-    let all_segments = scenarios.iter().map(|(_,spath)| {
-        Segment::load(&sites, spath)
-    }).fold( IndexMap::new() as IndexMap<u32,Segment>, | mut agg,item| {
-        agg.extend(item);
-        agg
-    });
-
-
-
-    let scenarios_data : Vec<(IndexMap<u32,Segment>,Vec<Vehicle>)> = scenarios.into_iter().map(|(v,s)| {
-        (
-            Segment::load(&sites, s),
-            Vehicle::load(&all_segments,v,&battery)
-        )
-    }).collect();
-
-
-
-    let input_data : Vec<&[Vehicle]> = scenarios_data.iter().map(|(_s,v)| {
-        v.as_slice()
-    }).collect();
-
-
-    let num_infeasible_allowed = ((matches.value_of("percent_infeasible_allowed").unwrap().parse::<f64>().unwrap()) * input_data[0].len() as f64).round() as usize;
-    if num_infeasible_allowed > 0 {
-        // only suitable for non robust setting
-        assert_eq!(input_data.len(),1);
-    }
-
-
-
-    eprintln!("{}","♞ Loading Data Completed".on_green().bold());
-
-    eprintln!("Working with {} scenarios", input_data.len());
-    println!("Working with {} scenarios", input_data.len());
-
-    eprintln!("Working with {} vehicles per scenario", input_data[0].len());
-    println!("Working with {} vehicles per scenario", input_data[0].len());
-
-
-    let mut env = Env::new("").unwrap();
-    env.set(param::Threads, 1).unwrap();
-    // 2= barrier; test with concurrent has shown that
-    // this usually wins!
-    //env.set(param::Method, 2).unwrap();
-    env.set(param::Seed, 12345).unwrap();
-    env.set(param::LogToConsole, 0).unwrap();
-
-
-    // set low time limit; we mainly want the integer solution by branching this function is only for quick wins;
-    let mut env_integer = Env::new("").unwrap();
-    env_integer.set(param::Threads, 1).unwrap();
-    env_integer.set(param::Seed, 12345).unwrap();
-    env_integer.set(param::LogToConsole, 0).unwrap();
-    env_integer.set(param::TimeLimit, 60.0).unwrap();
-
-
-    let mut seq = SolutionApproachVariable::new(
-        num_sites,num_infeasible_allowed, sites.clone(), &input_data, workers, true, quorum_accept_percent, benevolent_accept_percent, max_activate_per_generation,
-        matches.is_present("activate_all"),
-        matches.value_of("activate_iis").unwrap().parse::<bool>().unwrap(), total_num_vehicles,
-        &env, &env_integer
-    );
-
-
-
-
     let start = Instant::now();
 
 
@@ -305,14 +350,53 @@ pub fn main() {
     });*/
 
 
-    let solution = seq.run(should_stop, matches.value_of("charge_processes_output").unwrap(),matches.value_of("cuts_output").unwrap(),matches.value_of("cuts_input").unwrap(),
-    do_low,do_high_low, do_variable
-    );
-
+    let plan = instance.solve_until(&config, should_stop).unwrap_or_else(|e| panic!("{}", e));
     eprintln!("{}","♞ Column Generation Completed".on_green().bold());
+    let solution = &plan.solution;
+    let report = &plan.report;
 
     println!("Solution: {}", solution.cost);
     println!("Solution Sites: {:?}", solution.sites_open);
     println!("Duration: {}s", start.elapsed().as_secs());
 
+    let solution_output = matches.value_of("solution_output").unwrap();
+    let timeline_output = matches.value_of("timeline_output").unwrap();
+    if solution_output != "/dev/null" {
+        report.write(solution_output).expect("Could not write solution report");
+    }
+    if timeline_output != "/dev/null" {
+        let file = File::create(timeline_output).expect("Could not create timeline output");
+        if timeline_output.ends_with(".csv") {
+            VehicleTimeline::write_csv(&plan.timelines, file).expect("Could not write timelines");
+        } else {
+            serde_json::to_writer_pretty(BufWriter::new(file), &plan.timelines).expect("Could not write timelines");
+        }
+    }
+    let column_generation_output = matches.value_of("column_generation_output").unwrap();
+    if column_generation_output != "/dev/null" {
+        let file = File::create(column_generation_output).expect("Could not create column generation output");
+        ColumnGenerationIteration::write_csv(&plan.cg_iterations, file).expect("Could not write column generation iterations");
+    }
+    let schedules : Vec<OccupancySchedule> = report.scenarios.iter()
+        .filter(|scenario| scenario.feasible)
+        .map(|scenario| OccupancySchedule::new(scenario, &report.open_sites, &horizon))
+        .collect();
+    if let Some(dir) = matches.value_of("occupancy_output") {
+        let dir = Path::new(dir);
+        std::fs::create_dir_all(dir).expect("Could not create occupancy output");
+        OccupancySchedule::write_occupancy_csv(&schedules, File::create(dir.join("occupancy.csv")).unwrap()).expect("Could not write occupancy");
+        OccupancySchedule::write_slots_csv(&schedules, File::create(dir.join("charger_slots.csv")).unwrap()).expect("Could not write charger slots");
+        OccupancySchedule::write_summary_csv(&schedules, File::create(dir.join("site_summary.csv")).unwrap()).expect("Could not write site summary");
+        if matches.is_present("occupancy_svg") {
+            for schedule in &schedules {
+                std::fs::write(dir.join(schedule.svg_file_name()), schedule.to_svg(&horizon)).expect("Could not write occupancy chart");
+            }
+        }
+    }
+    if let Some(path) = matches.value_of("geojson_output") {
+        let detours = if matches.is_present("geojson_detours") { Some(&instance.data().segments) } else { None };
+        let geojson = solution_geojson(instance.sites(), report, &schedules, detours);
+        serde_json::to_writer(File::create(path).expect("Could not create geojson output"), &geojson).expect("Could not write geojson");
+    }
+
 }
@@ -0,0 +1,99 @@
+use shared::{Horizon, Reachability, Site, TravelMatrix};
+
+use std::fs::File;
+use std::io;
+use clap::{App, Arg};
+
+
+/// Recomputes the potentialSites column of a trips file for a sites file,
+/// e.g. after adding or moving candidate sites.
+
+pub fn main() {
+
+    let matches = App::new("UpdateReachableSites")
+
+        .arg(Arg::with_name("trips")
+            .short("t")
+            .long("trips")
+            .value_name("FILE")
+            .help("Trips to update")
+            .required(true)
+            .takes_value(true))
+        .arg(Arg::with_name("sites")
+            .short("s")
+            .long("sites")
+            .value_name("FILE")
+            .help("Candidate sites")
+            .required(true)
+            .takes_value(true))
+        .arg(Arg::with_name("matrix")
+            .short("m")
+            .long("matrix")
+            .value_name("FILE")
+            .help("Driving durations and distances with columns from, to, duration (s) and distance (m). Pairs not listed are estimated")
+            .takes_value(true))
+        .arg(Arg::with_name("detour_factor")
+            .long("detour_factor")
+            .value_name("FACTOR")
+            .help("Ratio of road to great-circle distance for estimated pairs")
+            .default_value("1.3")
+            .takes_value(true))
+        .arg(Arg::with_name("speed")
+            .long("speed")
+            .value_name("KMH")
+            .help("Average speed for estimated pairs")
+            .default_value("30")
+            .takes_value(true))
+        .arg(Arg::with_name("max_detour")
+            .long("max_detour")
+            .value_name("METERS")
+            .help("Longest extra distance to drive via a site compared to the trip itself")
+            .takes_value(true))
+        .arg(Arg::with_name("min_charging_periods")
+            .long("min_charging_periods")
+            .value_name("PERIODS")
+            .help("Fewest periods between the arrival at a site and the departure from it, at least one")
+            .default_value("1")
+            .takes_value(true))
+        .arg(Arg::with_name("period_length")
+            .long("period_length")
+            .value_name("MINUTES")
+            .help("Length of a period in minutes")
+            .default_value("5")
+            .takes_value(true))
+        .arg(Arg::with_name("horizon")
+            .long("horizon")
+            .value_name("MINUTES")
+            .help("Length of the planning horizon in minutes")
+            .default_value("1440")
+            .takes_value(true))
+        .arg(Arg::with_name("output")
+            .short("o")
+            .long("output")
+            .value_name("FILE")
+            .help("Where to write the updated trips, standard output if not given")
+            .takes_value(true))
+        .get_matches();
+
+
+    let horizon = Horizon::new(
+        matches.value_of("period_length").unwrap().parse().expect("Invalid period_length"),
+        matches.value_of("horizon").unwrap().parse().expect("Invalid horizon"),
+    ).unwrap_or_else(|e| panic!("{}", e));
+
+    let mut reachability = Reachability::new(&horizon);
+    reachability.matrix = matches.value_of("matrix").map(|path| TravelMatrix::load(path).unwrap_or_else(|e| panic!("{}", e)));
+    reachability.detour_factor = matches.value_of("detour_factor").unwrap().parse().expect("Invalid detour_factor");
+    reachability.speed_kmh = matches.value_of("speed").unwrap().parse().expect("Invalid speed");
+    reachability.max_detour_meters = matches.value_of("max_detour").map(|m| m.parse().expect("Invalid max_detour"));
+    reachability.min_charging_periods = matches.value_of("min_charging_periods").unwrap().parse().expect("Invalid min_charging_periods");
+
+    let sites = Site::load(matches.value_of("sites").unwrap()).unwrap_or_else(|e| panic!("{}", e));
+    let trips_path = matches.value_of("trips").unwrap();
+
+    let result = match matches.value_of("output") {
+        Some(path) => reachability.rewrite_trips(&sites, trips_path, File::create(path).expect("Could not create output")),
+        None => reachability.rewrite_trips(&sites, trips_path, io::stdout()),
+    };
+    result.unwrap_or_else(|e| panic!("{}", e));
+}
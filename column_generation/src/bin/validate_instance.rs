@@ -0,0 +1,93 @@
+use shared::{validate_instance, InstanceManifest};
+
+use std::process;
+use serde_json::json;
+use clap::{App, Arg};
+
+
+/// Checks an instance for consistency before solving it and prints
+/// a json report of all issues found.
+/// Exits with 1 if there are issues and with 2 if the instance could not be read.
+
+pub fn main() {
+
+    let matches = App::new("ValidateInstance")
+
+
+        .arg(Arg::with_name("vehicles")
+            .short("v")
+            .long("vehicles")
+            .value_name("FILE")
+            .help("Vehicles to check, one per scenario")
+            .required_unless("instance")
+            .multiple(true)
+            .min_values(1)
+            .takes_value(true))
+
+        .arg(Arg::with_name("battery")
+            .short("b")
+            .long("battery")
+            .value_name("FILE")
+            .help("Battery Config to load, repeat or use one table per profile for several battery profiles")
+            .required_unless("instance")
+            .multiple(true)
+            .number_of_values(1)
+            .takes_value(true))
+
+        .arg(Arg::with_name("trips")
+            .short("t")
+            .long("trips")
+            .value_name("FILE")
+            .help("Trips to check, one per scenario")
+            .required_unless("instance")
+            .multiple(true)
+            .min_values(1)
+            .takes_value(true))
+        .arg(Arg::with_name("sites")
+            .short("s")
+            .long("sites")
+            .value_name("FILE")
+            .help("Sites to load")
+            .required_unless("instance")
+            .takes_value(true))
+        .arg(Arg::with_name("instance")
+            .short("i")
+            .long("instance")
+            .value_name("FILE")
+            .help("Instance manifest listing sites, battery profiles and scenarios")
+            .conflicts_with_all(&["vehicles", "trips", "battery", "sites"])
+            .takes_value(true))
+        .get_matches();
+
+
+    let manifest = match matches.value_of("instance") {
+        Some(instance_path) => InstanceManifest::load(instance_path),
+        None => Ok(InstanceManifest::from_paths(
+            matches.value_of("sites").unwrap(),
+            &matches.values_of("battery").unwrap().collect::<Vec<&str>>(),
+            &matches.values_of("vehicles").unwrap().zip(matches.values_of("trips").unwrap()).collect::<Vec<(&str,&str)>>(),
+        )),
+    };
+
+
+    let report = manifest
+        .and_then(|manifest| Ok((manifest.load_sites()?, manifest.load_batteries()?, manifest)))
+        .and_then(|(sites, batteries, manifest)| validate_instance(&sites, &batteries, &manifest.scenario_paths()));
+
+    match report {
+        Ok(report) => {
+            println!("{:#}", report.to_json());
+            if !report.is_valid() {
+                process::exit(1);
+            }
+        }
+        Err(e) => {
+            println!("{:#}", json!({
+                "valid": false,
+                "error": e.to_string(),
+                "path": e.path(),
+            }));
+            process::exit(2);
+        }
+    }
+}
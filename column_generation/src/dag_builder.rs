@@ -5,10 +5,11 @@ use typed_arena::Arena;
 
 
 use std::io::Write;
-use shared::{Segment, Site, Period, ReachableSite, Vehicle, MAX_PERIOD, MIN_PER_PERIOD, charge_time_to_capacity_charge_time};
+use shared::{Segment, Site, Period, ReachableSite, Vehicle, Horizon};
 use petgraph::graph::NodeIndex;
-use std::cell::Cell;
-use std::rc::Rc;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering::Relaxed;
+use std::sync::Arc;
 use core::{fmt, cmp};
 use petgraph::Graph;
 use std::fs::OpenOptions;
@@ -22,27 +23,49 @@ use petgraph::prelude::EdgeRef;
 
 
 
+/// Dual price shared by the edges of the dags. It is written between column generation iterations
+/// and read by the pricing workers, thus kept as the bits of the float in an atomic.
+#[derive(Default)]
+pub struct DualTerm(AtomicU64);
+
+impl DualTerm {
+    pub fn get(&self) -> f64 {
+        f64::from_bits(self.0.load(Relaxed))
+    }
+
+    pub fn set(&self, value : f64) {
+        self.0.store(value.to_bits(), Relaxed)
+    }
+}
+
+/// Creates the duals of the capacity constraints, one per site and period, all starting at zero.
+pub fn new_site_period_duals(num_sites : usize, horizon : &Horizon) -> Arc<Array2<Arc<DualTerm>>> {
+    // initialize with individual pointers; with shorthand methods the
+    // Arc gets cloned resulting in all cells pointing to the same orgin. We do not want that!
+    Arc::new(Array2::from_shape_simple_fn((num_sites, horizon.periods), || Arc::new(DualTerm::default())))
+}
+
 pub struct EdgeWeight {
     pub(crate) distance_m: u32, // distance that is driven
     pub(crate) charge_duration_minutes : u8,
     start_of_charge : bool,
-    pub(crate) edge_dual_term: Rc<Cell<f64>>,
+    pub(crate) edge_dual_term: Arc<DualTerm>,
 }
 
 #[derive(Debug,Clone)]
 pub struct NodeWeight<'a> {
     title: String,
-    pub(crate) segment: Option<&'a Segment<'a>>,
-    pub(crate) site: Option<&'a ReachableSite<'a>>,
+    pub(crate) segment: Option<&'a Segment>,
+    pub(crate) site: Option<&'a ReachableSite>,
     pub(crate) charge_period: Option<Period>,
     pub(crate) time_period : Period
 }
 
 impl<'a> NodeWeight<'a> {
-    pub fn get_segment(&self) -> Option<&'a Segment<'a>> {
+    pub fn get_segment(&self) -> Option<&'a Segment> {
         self.segment
     }
-    pub fn get_site(&self) -> Option<&'a ReachableSite<'a>> {
+    pub fn get_site(&self) -> Option<&'a ReachableSite> {
         self.site
     }
 }
@@ -96,7 +119,7 @@ pub fn save_dag(name : &str, dag : &Graph<NodeWeight, EdgeWeight, petgraph::Dire
     Build a DAG of the vehicles choices with reduced costs attached to the nodes
 */
 
-pub fn build_dag <'s, 'a : 's>(vehicle : &'a Vehicle, no_dual : Rc<Cell<f64>>, site_period_duals : Rc<Array2<Rc<Cell<f64>>>>, charge_filters : &[BranchingFilter], site_sizes : SiteConf) ->  (NodeIndex<u32>, NodeIndex<u32>, Graph<NodeWeight<'s>, EdgeWeight, petgraph::Directed>) {
+pub fn build_dag <'s, 'a : 's>(vehicle : &'a Vehicle, no_dual : Arc<DualTerm>, site_period_duals : Arc<Array2<Arc<DualTerm>>>, charge_filters : &[BranchingFilter], site_sizes : SiteConf, horizon : &Horizon) ->  (NodeIndex<u32>, NodeIndex<u32>, Graph<NodeWeight<'s>, EdgeWeight, petgraph::Directed>) {
 
     let mut dag = Graph::<NodeWeight, EdgeWeight, petgraph::Directed>::new();
 
@@ -114,7 +137,7 @@ pub fn build_dag <'s, 'a : 's>(vehicle : &'a Vehicle, no_dual : Rc<Cell<f64>>, s
         site: None,
         segment: None,
         charge_period: None,
-        time_period : MAX_PERIOD as Period,
+        time_period : horizon.end(),
     });
 
 
@@ -200,7 +223,7 @@ pub fn build_dag <'s, 'a : 's>(vehicle : &'a Vehicle, no_dual : Rc<Cell<f64>>, s
 
 
 
-            let mut min_charge = MAX_PERIOD as Period;
+            let mut min_charge = horizon.end();
             let mut max_charge = 0;
 
 
@@ -320,7 +343,8 @@ pub fn build_dag <'s, 'a : 's>(vehicle : &'a Vehicle, no_dual : Rc<Cell<f64>>, s
                     continue;
                 }
 
-                let periods_availiable : Period = cmp::min(10,departure_period - arrival_period);
+                // charge for at most 50 minutes per stop
+                let periods_availiable : Period = cmp::min(horizon.periods_in(50),departure_period - arrival_period);
 
 
                 // if we do not have time to reach this it is infeasible
@@ -329,7 +353,7 @@ pub fn build_dag <'s, 'a : 's>(vehicle : &'a Vehicle, no_dual : Rc<Cell<f64>>, s
                 }
 
                 // 10 minute minimum for charging
-                if periods_availiable <= 10 / MIN_PER_PERIOD as u16 {
+                if periods_availiable <= horizon.periods_in(10) {
                     continue;
                 }
 
@@ -447,8 +471,8 @@ pub fn build_dag <'s, 'a : 's>(vehicle : &'a Vehicle, no_dual : Rc<Cell<f64>>, s
                                 dag.add_edge(site_node, site_exit_node,
                                              EdgeWeight {
                                                  distance_m: 0,
-                                                 charge_duration_minutes : MIN_PER_PERIOD,
-                                                 edge_dual_term: site_period_duals[[site.site.index, charge_time_to_capacity_charge_time(&charge_time)]].clone(),
+                                                 charge_duration_minutes : horizon.minutes_per_period,
+                                                 edge_dual_term: site_period_duals[[site.site.index, horizon.capacity_period(&charge_time)]].clone(),
                                                  start_of_charge: false
                                              });
                             }
@@ -474,16 +498,16 @@ pub fn build_dag <'s, 'a : 's>(vehicle : &'a Vehicle, no_dual : Rc<Cell<f64>>, s
                             if add_edge_to_exit {
                                 dag.add_edge(site_node, site_exit_node, EdgeWeight {
                                     distance_m: 0,
-                                    charge_duration_minutes : MIN_PER_PERIOD,
-                                    edge_dual_term: site_period_duals[[site.site.index, charge_time_to_capacity_charge_time(&(arrival_period + period))]].clone(),
+                                    charge_duration_minutes : horizon.minutes_per_period,
+                                    edge_dual_term: site_period_duals[[site.site.index, horizon.capacity_period(&(arrival_period + period))]].clone(),
                                     start_of_charge: false
                                 });
                             }
 
                             dag.add_edge(last, site_node, EdgeWeight {
                                 distance_m: 0,
-                                charge_duration_minutes : MIN_PER_PERIOD,
-                                edge_dual_term: site_period_duals[[last_site.unwrap(),charge_time_to_capacity_charge_time(&(arrival_period + period - 1))]].clone(),
+                                charge_duration_minutes : horizon.minutes_per_period,
+                                edge_dual_term: site_period_duals[[last_site.unwrap(),horizon.capacity_period(&(arrival_period + period - 1))]].clone(),
                                 start_of_charge : false
                             });
                         }
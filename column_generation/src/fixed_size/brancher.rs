@@ -4,11 +4,8 @@ pub const DUMMY_COST : f64 = 1.0;
 #[cfg(feature = "profiling_enabled")]
 use rust_hawktracer::*;
 
-use grb::{Status, param, Model, Env, attr, INFINITY, Var, Constr, ConstrSense, Expr, c};
 
 use std::io::{Read, BufWriter};
-use std::rc::Rc;
-use std::cell::Cell;
 use std::collections::VecDeque;
 use std::io::Write;
 use std::iter::Sum;
@@ -20,15 +17,17 @@ macro_rules! pause {
     };
 }
 
-use shared::{Segment, Vehicle, Site, Period, CustomMultiHashMap, CustomHashMap, MAX_PERIOD, charge_time_to_capacity_charge_time, ReachableSite, CustomHashSet};
+use shared::{Segment, Vehicle, Site, Period, CustomMultiHashMap, CustomHashMap, Horizon, ReachableSite, CustomHashSet, ColumnGenerationIteration};
 use crate::{SiteArrayRef, CG_EPSILON, format_pattern};
 use indexmap::IndexMap;
 use itertools::assert_equal;
 use crate::fixed_size::site_conf::{SiteConf, SiteConfFactory};
 use crate::pattern_pool::{Pattern, PatternEntry, PatternPool};
 use ndarray::Array2;
-use crate::dag_builder::{build_dag, NodeWeight, EdgeWeight};
+use crate::dag_builder::{build_dag, new_site_period_duals, DualTerm, NodeWeight, EdgeWeight};
 use crate::rcsp::generate_patterns;
+use crate::pricing;
+use crate::stabilization::{Duals, Stabilization, Stabilizer};
 use std::io;
 use petgraph::graph::NodeIndex;
 use petgraph::{Graph, Directed};
@@ -96,10 +95,8 @@ pub trait HasPriority {
     fn get_priority_when_existing_bound(&self) -> u32;
 }
 use binary_heap_plus::{BinaryHeap, FnComparator, KeyComparator};
-use grb::attribute::ObjAttrGet;
-use grb::constr::IneqExpr;
-use grb::prelude::Continuous;
-use grb::VarType::{Binary, Integer};
+use crate::lp::{Constr, Sense, SolverEnv, Status, Var};
+use crate::lp::VarType::Binary;
 use crate::branching_filter::Dir::{Greater, Less};
 use crate::fixed_size::cg_model::{CgModel, SegmentId, SiteIndex, VehicleIndex};
 
@@ -155,7 +152,7 @@ pub struct BranchNode {
 
 impl BranchNode {
 
-    pub fn root(env : &Env, sites : Vec<Site>, site_sizes : Vec<u8>, vehicles : &[Vehicle]) -> Self {
+    pub fn root(env : &SolverEnv, sites : Vec<Site>, site_sizes : Vec<u8>, vehicles : &[Vehicle]) -> Self {
         let root_id = ProcessUniqueId::new();
         BranchNode {
             id : root_id,
@@ -260,21 +257,35 @@ impl HasPriority for BranchNode {
     }
 }
 
-pub struct Brancher<'a> {
+pub struct Brancher {
     sites:  Vec<Site>,
-    vehicles: Vec<Vehicle<'a>>,
+    vehicles: Vec<Vehicle>,
+    horizon : Horizon,
     allowed_infeasible : usize,
     site_sizes:  SiteConf,
     current_upper_bound: Option<f64>,
     current_best_pattern: Option<ResultPattern>,
     pattern_pool: PatternPool,
     open_branches : BranchQueue<BranchNode>,
-    pub env : &'a Env,
-    pub env_integer : &'a Env,
+    // environments of this brancher only, it may be solved on any thread
+    pub env : SolverEnv,
+    pub env_integer : SolverEnv,
     cg_model : CgModel,
     should_stop : Arc<AtomicBool>,
+    // stops this brancher alone, once its result is no longer needed
+    pub cancel : Arc<AtomicBool>,
     invisibility_event_counter : usize,
-    sort_many_columns_first : bool
+    sort_many_columns_first : bool,
+    // threads solving the pricing problems of the vehicles
+    pricing_workers : usize,
+    stabilization : Stabilization,
+    cg_runs : usize,
+    // pricing rounds and mis-pricings of all column generation runs so far
+    pricing_rounds : usize,
+    mis_pricings : usize,
+    // the rounds themselves are only kept on request, they grow with every branch node
+    record_iterations : bool,
+    cg_iterations : Vec<ColumnGenerationIteration>
 }
 
 #[derive(Debug,Clone)]
@@ -288,11 +299,15 @@ pub enum SolveError {
 
 
 
-impl<'a,'b : 'a> Brancher<'a> {
+impl Brancher {
 
 
 
-    pub fn load_columns(&'b mut self, path : PathBuf) {
+    fn is_stopped(&self) -> bool {
+        self.should_stop.load(Relaxed) || self.cancel.load(Relaxed)
+    }
+
+    pub fn load_columns(&mut self, path : PathBuf) {
         self.pattern_pool.read_from_disk(path, self.vehicles.clone(), &self.sites);
     }
 
@@ -304,14 +319,31 @@ impl<'a,'b : 'a> Brancher<'a> {
         &self.pattern_pool
     }
 
+    /// Pricing rounds of all column generation runs since they are recorded.
+    pub fn get_cg_iterations(&self) -> &[ColumnGenerationIteration] {
+        &self.cg_iterations
+    }
+
+    /// Numbers of pricing rounds and of mis-pricings among them, recorded or not.
+    pub fn get_pricing_rounds(&self) -> (usize, usize) {
+        (self.pricing_rounds, self.mis_pricings)
+    }
+
+    pub fn record_iterations(&mut self, record : bool) {
+        self.record_iterations = record;
+    }
+
     pub fn new(sites: Vec<Site>,
-               vehicles: Vec<Vehicle<'a>>,
+               vehicles: Vec<Vehicle>,
                site_sizes: SiteConf,
-               env : &'a Env,
-               env_integer : &'a Env,
+               env : SolverEnv,
+               env_integer : SolverEnv,
                allowed_infeasible : usize,
                                sort_many_columns_first : bool,
-               should_stop : Arc<AtomicBool>, pattern_pool : PatternPool) -> Brancher<'a> {
+               should_stop : Arc<AtomicBool>, pattern_pool : PatternPool,
+               horizon : Horizon,
+               pricing_workers : usize,
+               stabilization : Stabilization) -> Brancher {
 
 
 
@@ -320,10 +352,11 @@ impl<'a,'b : 'a> Brancher<'a> {
 
 
         Brancher {
-            cg_model : CgModel::new(&env, sites.clone(), site_sizes.clone(), vehicles.clone()),
+            cg_model : CgModel::new(&env, sites.clone(), site_sizes.clone(), vehicles.clone(), horizon),
             pattern_pool,
             sites,
             vehicles,
+            horizon,
             site_sizes,
             sort_many_columns_first,
             current_upper_bound: None,
@@ -334,7 +367,15 @@ impl<'a,'b : 'a> Brancher<'a> {
             env_integer,
             allowed_infeasible,
             should_stop,
-            invisibility_event_counter : 0
+            cancel : Arc::new(AtomicBool::new(false)),
+            invisibility_event_counter : 0,
+            pricing_workers : pricing_workers.max(1),
+            stabilization,
+            cg_runs : 0,
+            pricing_rounds : 0,
+            mis_pricings : 0,
+            record_iterations : false,
+            cg_iterations : Vec::new()
         }
 
 
@@ -366,7 +407,7 @@ impl<'a,'b : 'a> Brancher<'a> {
 
 
 
-    pub fn get_vehicles(&self) -> &[Vehicle<'a>] {
+    pub fn get_vehicles(&self) -> &[Vehicle] {
         &self.vehicles
     }
 
@@ -392,7 +433,7 @@ impl<'a,'b : 'a> Brancher<'a> {
 
         #[cfg(feature = "level_print")]
         println!("- Solving Operational Problem");
-        if self.should_stop.load(Relaxed) {
+        if self.is_stopped() {
             return Err(SolveError::StoppedByExternal);
         }
 
@@ -586,7 +627,7 @@ impl<'a,'b : 'a> Brancher<'a> {
     #[hawktracer(solve_integer_problem)]
     fn solve_integer_problem(&mut self, charge_filters: &[BranchingFilter], find_num_infeasible : bool) -> Result<SolvedCGResult, SolveError> {
 
-            if self.should_stop.load(Relaxed) {
+            if self.is_stopped() {
                 return Err(SolveError::StoppedByExternal);
             }
 
@@ -596,7 +637,7 @@ impl<'a,'b : 'a> Brancher<'a> {
 
 
             // create an empty model which associated with `env`:
-            let mut integer_master = Model::with_env("integer_env", self.env_integer).unwrap();
+            let mut integer_master = self.env_integer.model("integer_env");
 
             // initialize hashmaps for model variables and patterns
             let mut vehicle_patterns = CustomMultiHashMap::default();
@@ -604,15 +645,15 @@ impl<'a,'b : 'a> Brancher<'a> {
 
 
 
-            let mut site_constraints = Vec::with_capacity(self.sites.len() * MAX_PERIOD);
+            let mut site_constraints = Vec::with_capacity(self.sites.len() * self.horizon.periods);
             for site in &self.sites {
-                for p in 0..MAX_PERIOD {
+                for p in 0..self.horizon.periods {
                     site_constraints.push(
                         integer_master.add_constr(&format!("maxCapacity[{},{},{}]", site.id, site.index, p),
-                                                  c!( 0 <= f64::from(self.site_sizes[site.index]))/*site.capacity.into()*/).unwrap());
+                                                  &[], Sense::Less, f64::from(self.site_sizes[site.index])/*site.capacity.into()*/));
                 }
             }
-            let constr_max_capacity: Array2<Constr> = Array2::from_shape_vec((self.sites.len(), MAX_PERIOD), site_constraints).unwrap();
+            let constr_max_capacity: Array2<Constr> = Array2::from_shape_vec((self.sites.len(), self.horizon.periods), site_constraints).unwrap();
 
 
             let mut site_time_branch_constraint : CustomMultiHashMap<(SiteIndex,Period), Constr> = CustomMultiHashMap::default();
@@ -622,15 +663,13 @@ impl<'a,'b : 'a> Brancher<'a> {
                     site_time_branch_constraint.insert((*site,*period),
                                                        integer_master.add_constr(
                                                            &format!("branchCapacity[{},{},{}]", site.index(), period, value.float()),
-                                                           IneqExpr{
-                                                               lhs: Expr::default(),
-                                                               sense: match direction {
-                                                                   Dir::Less => ConstrSense::Less,
-                                                                   Dir::Greater => ConstrSense::Greater,
-                                                               },
-                                                               rhs:  Expr::Constant(value.float())
-                                                           }
-                                                        ).unwrap()
+                                                           &[],
+                                                           match direction {
+                                                               Dir::Less => Sense::Less,
+                                                               Dir::Greater => Sense::Greater,
+                                                           },
+                                                           value.float()
+                                                        )
                     )
                 }
             }
@@ -640,11 +679,11 @@ impl<'a,'b : 'a> Brancher<'a> {
             let mut pattern_counter = 0;
             let mut all_dummy_vars = Vec::with_capacity(self.get_vehicles().len());
             for vehicle in self.get_vehicles() {
-                let dummy_var = integer_master.add_var(&format!("dummy_column[{}]", vehicle.id), Binary, DUMMY_COST, 0.0, 1.0, []).unwrap();
-                let constr_convexity = integer_master.add_constr(&format!("convexity[{}]", vehicle.id), c!(1.0 * dummy_var == 1.0)).unwrap();
+                let dummy_var = integer_master.add_var(&format!("dummy_column[{}]", vehicle.id), Binary, DUMMY_COST, 0.0, 1.0, &[]);
+                let constr_convexity = integer_master.add_constr(&format!("convexity[{}]", vehicle.id), &[(dummy_var, 1.0)], Sense::Equal, 1.0);
                 all_dummy_vars.push(dummy_var);
 
-                vehicle_convexity.insert(vehicle, constr_convexity.clone());
+                vehicle_convexity.insert(vehicle, constr_convexity);
 
                 // add the existing patterns from the pool
                 // copy paste from code in column generation below
@@ -664,14 +703,14 @@ impl<'a,'b : 'a> Brancher<'a> {
                         for (_, site, period) in entry.pattern.iter() {
 
 
-                            coef_vec.push((constr_max_capacity[[site.index(), charge_time_to_capacity_charge_time(period)]].clone(),1.0));
+                            coef_vec.push((constr_max_capacity[[site.index(), self.horizon.capacity_period(period)]],1.0));
 
 
 
                             // register the site_time branch constraints!
                             if let Some(entry) = site_time_branch_constraint.get_vec(&(*site,*period)) {
                                 for constr in entry {
-                                    coef_vec.push((constr.clone(), 1.0));
+                                    coef_vec.push((*constr, 1.0));
 
                                 }
                             }
@@ -680,7 +719,7 @@ impl<'a,'b : 'a> Brancher<'a> {
 
 
 
-                        let var_use_pattern = integer_master.add_var(&format!("usePattern[{}]", pattern_counter), Binary, 0.0, 0.0, 1.0, coef_vec).unwrap();
+                        let var_use_pattern = integer_master.add_var(&format!("usePattern[{}]", pattern_counter), Binary, 0.0, 0.0, 1.0, &coef_vec);
                         vehicle_patterns.insert(vehicle, (var_use_pattern, entry.pattern.clone()));
                         pattern_counter += 1;
                     }
@@ -689,25 +728,21 @@ impl<'a,'b : 'a> Brancher<'a> {
             }
 
             if ! find_num_infeasible {
-                integer_master.add_constr("limitInfeasible", c!(Expr::sum(all_dummy_vars.iter()) <=  self.allowed_infeasible as f64)).unwrap();
+                integer_master.add_constr("limitInfeasible", &all_dummy_vars.iter().map(|var| (*var, 1.0)).collect::<Vec<(Var, f64)>>(), Sense::Less, self.allowed_infeasible as f64);
             }
 
 
-            integer_master.update().unwrap();
-            integer_master.optimize().unwrap();
-
-
-            if integer_master.status().unwrap() != Status::Optimal {
+            if integer_master.optimize() != Status::Optimal {
                 return Err(Generic("could not solve correctly"));
             }
 
 
         // do test if we are infeasible
 
-        let has_dummy_values = integer_master.get_obj_attr_batch(attr::X, all_dummy_vars.clone()).unwrap().iter().filter(|&v| *v >= CG_EPSILON).count();
+        let has_dummy_values = integer_master.x(&all_dummy_vars).iter().filter(|&v| *v >= CG_EPSILON).count();
         if has_dummy_values > self.allowed_infeasible {
 
-            let infeasible_vehicles = integer_master.get_obj_attr_batch(attr::X, all_dummy_vars.clone()).unwrap().iter().zip(self.get_vehicles()).filter_map(|(value,vehicle)| {
+            let infeasible_vehicles = integer_master.x(&all_dummy_vars).iter().zip(self.get_vehicles()).filter_map(|(value,vehicle)| {
                 if *value > CG_EPSILON {
                     Some(VehicleIndex::new(vehicle))
                 } else {
@@ -724,10 +759,10 @@ impl<'a,'b : 'a> Brancher<'a> {
 
         Ok(
             SolvedCGResult {
-                master_x: integer_master.get_attr(attr::ObjVal).unwrap(),
+                master_x: integer_master.obj_val(),
                 patterns: self.get_vehicles().iter().map(|vehicle| {
                     if let Some(patterns) = &vehicle_patterns.get_vec(vehicle) {
-                        let solution_values = integer_master.get_obj_attr_batch(attr::X, patterns.iter().map(|(var, _)| var.clone()).collect::<Vec<Var>>()).unwrap();
+                        let solution_values = integer_master.x(&patterns.iter().map(|(var, _)| *var).collect::<Vec<Var>>());
 
                         (VehicleIndex::new(vehicle),
                          patterns.iter()
@@ -746,22 +781,13 @@ impl<'a,'b : 'a> Brancher<'a> {
     }
 
 
-    pub fn get_vehicles_that_can_be_feasible<'f>(vehicles: impl Iterator<Item=&'f Vehicle<'f>>, site_conf : SiteConf) -> Vec<&'f Vehicle<'f>> {
-        // Following block is to initialize an ndarray with individual RC pointers; With shorthand methods the
-        // RC gets cloned resulting in all cells pointing to the same orgin. We do not want that!
-        let mut tmp_vec: Vec<Rc<Cell<f64>>> = Vec::with_capacity(site_conf.len() * MAX_PERIOD);
-        for _ in 0..(site_conf.len() * MAX_PERIOD) {
-            tmp_vec.push(Rc::new(Cell::new(0_f64)))
-        }
-        let site_period_duals = Array2::<Rc<Cell<f64>>>::from_shape_vec((site_conf.len(), MAX_PERIOD), tmp_vec).unwrap();
-
-
-        let no_dual: Rc<Cell<f64>> = Rc::new(Cell::new(0_f64));
-        let arc_site_period_duals = Rc::new(site_period_duals);
+    pub fn get_vehicles_that_can_be_feasible<'f>(vehicles: impl Iterator<Item=&'f Vehicle>, site_conf : SiteConf, horizon : &Horizon) -> Vec<&'f Vehicle> {
+        let no_dual = Arc::new(DualTerm::default());
+        let arc_site_period_duals = new_site_period_duals(site_conf.len(), horizon);
 
 
         vehicles
-            .map(|vehicle| (vehicle,build_dag(vehicle, no_dual.clone(), arc_site_period_duals.clone(), &Vec::new(), site_conf.clone())))
+            .map(|vehicle| (vehicle,build_dag(vehicle, no_dual.clone(), arc_site_period_duals.clone(), &Vec::new(), site_conf.clone(), horizon)))
             .filter(|(vehicle,(root, destination, dag))| {
 
 
@@ -805,7 +831,7 @@ impl<'a,'b : 'a> Brancher<'a> {
         10 * count_sites_that_are_not_in_both + count_times_that_are_not_in_both
     }
 
-    fn retain_diverse_columns_and_first(&self, patterns: &mut Vec<(f64, f64, Pattern)>, k : usize) {
+    fn retain_diverse_columns_and_first(patterns: &mut Vec<(f64, f64, Pattern)>, k : usize) {
 
 
 
@@ -861,7 +887,7 @@ impl<'a,'b : 'a> Brancher<'a> {
         #[cfg(feature = "level_print")]
         println!("--- Running Column Generation");
 
-        if self.should_stop.load(Relaxed) {
+        if self.is_stopped() {
             return Err(SolveError::StoppedByExternal);
         }
 
@@ -879,22 +905,13 @@ impl<'a,'b : 'a> Brancher<'a> {
 
 
 
-        // Following block is to initialize an ndarray with individual RC pointers; With shorthand methods the
-        // RC gets cloned resulting in all cells pointing to the same orgin. We do not want that!
-        let mut tmp_vec: Vec<Rc<Cell<f64>>> = Vec::with_capacity(self.sites.len() * MAX_PERIOD);
-        for _ in 0..(self.sites.len() * MAX_PERIOD) {
-            tmp_vec.push(Rc::new(Cell::new(0_f64)))
-        }
-        let site_period_duals = Array2::<Rc<Cell<f64>>>::from_shape_vec((self.sites.len(), MAX_PERIOD), tmp_vec).unwrap();
-
-
-        let no_dual : Rc<Cell<f64>> = Rc::new(Cell::new(0_f64));
-        let arc_site_period_duals = Rc::new(site_period_duals);
+        let no_dual = Arc::new(DualTerm::default());
+        let arc_site_period_duals = new_site_period_duals(self.sites.len(), &self.horizon);
 
 
         // build dags vor all vehicles, not just active ones so that the index of the dag array matches.
         let vehicle_dags: Vec<(NodeIndex, NodeIndex, Graph<NodeWeight, EdgeWeight, Directed>)> = self.vehicles.iter()
-            .map(|vehicle| build_dag(vehicle, no_dual.clone(), arc_site_period_duals.clone(), &charge_filters, self.site_sizes.clone())).collect();
+            .map(|vehicle| build_dag(vehicle, no_dual.clone(), arc_site_period_duals.clone(), &charge_filters, self.site_sizes.clone(), &self.horizon)).collect();
 
 
 
@@ -939,20 +956,13 @@ impl<'a,'b : 'a> Brancher<'a> {
             für jedes taxi ist eine covexity constraint angelegt
         */
 
-        let mut last_convexity_dual_cost: IndexMap<&Vehicle, f64> = IndexMap::default();
-
-
-
-        // TODO: column generation smoothing
-        // let cg_stabilizer = CG_Stabilizer::new();
+        let mut stabilizer = Stabilizer::new(self.stabilization);
+        self.cg_runs += 1;
+        let mut iteration = 0;
 
+        'masterLoop: loop {
 
-
-
-
-        loop {
-
-                if self.should_stop.load(Relaxed) {
+                if self.is_stopped() {
                     return Err(SolveError::StoppedByExternal);
                 }
 
@@ -975,20 +985,19 @@ impl<'a,'b : 'a> Brancher<'a> {
                 } != Status::Optimal {
                     return Err(SolveError::Generic("To Many Vehicles Infeasible"));
                 }
+                iteration += 1;
+                stabilizer.new_iteration();
 
 
 
                 let max_capacity_constraint_duals = self.cg_model.get_capacity_const_duals();
-                let vehicle_convexity_duals = self.cg_model.get_vehicle_convexity_const_duals();
-
-
-
 
 
                 #[cfg(feature = "column_generation_debug")]
                 println!("{:?}",&max_capacity_constraint_duals);
 
-                // update the charging station capacity duals in the shared array which is mapped to the dag edges
+                // collect the charging station capacity duals per site and period
+                let mut site_period_duals = Array2::zeros(arc_site_period_duals.dim());
                 for (site, duals) in self.sites.iter().zip(max_capacity_constraint_duals.outer_iter()) {
                     for (period_idx, capacity_dual) in duals.iter().enumerate() {
 
@@ -996,87 +1005,90 @@ impl<'a,'b : 'a> Brancher<'a> {
                         // if we have additonal duals from the branching, add them here
                      self.cg_model.get_site_time_branch_const_duals(SiteIndex::new(site), period_idx as Period);
 
-                        arc_site_period_duals[[site.index, period_idx]].set(dual);
+                        site_period_duals[[site.index, period_idx]] = dual;
                     }
                 }
 
+                let master_duals = Duals {
+                    site_period : site_period_duals,
+                    convexity : self.cg_model.get_vehicle_convexity_const_duals(),
+                    value : self.cg_model.obj_value(),
+                };
 
 
-                #[cfg(feature = "dag_output")] {
+                // price until a column is added, smoothed duals that do not yield any are moved towards the master duals
+                loop {
+                    let smoothing = stabilizer.smoothing();
+                    let priced_duals = stabilizer.duals_to_price(&master_duals);
 
-                    master.write("/tmp/problem_rust.sol").unwrap();
-                    master.write("/tmp/problem_rust.lp").unwrap();
-
-                    if arc_site_period_duals.iter().filter(|i| i.get() != 0.0).count() > 100 {
-                        for (idx, (_, _, dag)) in vehicle_dags.iter().enumerate() {
-                            crate::dag_builder::save_dag(&format!("problem_rust_graph_{}", idx), dag);
-                        }
-                        pause!("Pause at Loop; Has Written LP Files");
+                    // update the shared array which is mapped to the dag edges
+                    for ((site_idx, period_idx), dual) in priced_duals.site_period.indexed_iter() {
+                        arc_site_period_duals[[site_idx, period_idx]].set(*dual);
                     }
-                }
 
 
-                // update the vehicle convexity constraint duals
+                    #[cfg(feature = "dag_output")] {
 
-                for (vehicle, dual) in self.vehicles.iter().zip(vehicle_convexity_duals.iter()) {
-                    #[cfg(feature = "column_generation_debug")]
-                        println!("Updated convexity dual for vehicle {} to {}", vehicle.id, dual);
-                    last_convexity_dual_cost.insert(vehicle, *dual);
-                }
+                        master.write("/tmp/problem_rust.sol").unwrap();
+                        master.write("/tmp/problem_rust.lp").unwrap();
 
+                        if arc_site_period_duals.iter().filter(|i| i.get() != 0.0).count() > 100 {
+                            for (idx, (_, _, dag)) in vehicle_dags.iter().enumerate() {
+                                crate::dag_builder::save_dag(&format!("problem_rust_graph_{}", idx), dag);
+                            }
+                            pause!("Pause at Loop; Has Written LP Files");
+                        }
+                    }
 
-                let mut did_add_columns = false;
-                {
 
-                    scoped_tracepoint!(_inner_pricing_problem);
-                    let mut infeasible_counter = 0;
-                    for vehicle in &self.vehicles {
-                        let (root, destination, ref dag) = vehicle_dags[vehicle.index];
+                    // update the vehicle convexity constraint duals
+                    let convexity_dual_cost : IndexMap<&Vehicle, f64> = self.vehicles.iter().zip(priced_duals.convexity.iter().cloned()).collect();
+                    #[cfg(feature = "column_generation_debug")]
+                    for (vehicle, dual) in convexity_dual_cost.iter() {
+                        println!("Updated convexity dual for vehicle {} to {}", vehicle.id, dual);
+                    }
 
-                        let forbidden_columns = charge_filters.iter().filter_map(|cf: &BranchingFilter| {
-                            match cf {
-                                BranchingFilter::MasterMustUseColumn(filter_vehicle, filter_visits, filter_must_use) => {
-                                    if *filter_vehicle != VehicleIndex::new(vehicle) {
-                                        None
-                                    } else {
-                                        // only forbid if must_use = false (== must not use)
-                                        if *filter_must_use == false {
-                                            None
-                                        } else {
-                                            Some(filter_visits)
-                                        }
-                                    }
-                                }
 
-                                // all other that are not forbidden columns
-                                BranchingFilter::ChargeSegmentSiteTime(_, _, _, _, _) => None,
-                                BranchingFilter::ChargeSegmentSite(_, _, _, _) => None,
-                                BranchingFilter::OpenSite(_, _) => None,
-                                BranchingFilter::OpenSiteGroupMin(_, _) => None,
-                                BranchingFilter::OpenSiteGroupMax(_, _) => None,
-                                BranchingFilter::MasterNumberOfCharges(_, _, _, _) => None,
-                            }
-                        });
+                    let mut columns_added = 0;
+                    // the dummy column bounds the reduced costs of every vehicle
+                    let mut lagrangian_bound = if vehicles_with_forced_column.is_empty() { Some(priced_duals.value) } else { None };
+                    {
 
-                        let has_forced_column = vehicles_with_forced_column.contains(&VehicleIndex::new(vehicle));
+                        scoped_tracepoint!(_inner_pricing_problem);
 
-                        if !has_forced_column {
-                            match generate_patterns(vehicle, &dag, root, destination, last_convexity_dual_cost[&vehicle], false, &[]) {
-                                Ok(mut path) => {
-                                    // loop over all received patterns
+                        // vehicles with a forced column keep it, no need to price them
+                        let priced_vehicles : Vec<&Vehicle> = self.vehicles.iter()
+                            .filter(|vehicle| !vehicles_with_forced_column.contains(&VehicleIndex::new(vehicle)))
+                            .collect();
 
+                        let priced_patterns = pricing::map_in_order(&priced_vehicles, self.pricing_workers, |vehicle| {
+                            let (root, destination, ref dag) = vehicle_dags[vehicle.index];
+                            generate_patterns(vehicle, dag, root, destination, convexity_dual_cost[vehicle], false, &[])
+                                .map(|mut path| {
                                     if path.len() > 50 {
-                                        self.retain_diverse_columns_and_first(&mut path, 50);
+                                        Self::retain_diverse_columns_and_first(&mut path, 50);
                                     }
+                                    path
+                                })
+                        });
 
-                                    for (detour_cost, reduced_costs, pattern) in path
-                                        .iter() {
-
-
+                        // merge in vehicle order, so that the pool is the same for any number of workers
+                        let mut infeasible_counter = 0;
+                        for (vehicle, patterns) in priced_vehicles.iter().zip(priced_patterns) {
+                            let mut lowest_reduced_cost = DUMMY_COST - convexity_dual_cost[vehicle];
+                            match patterns {
+                                Ok(path) => {
+                                    // loop over all received patterns
+                                    for (detour_cost, reduced_costs, pattern) in path {
+                                        lowest_reduced_cost = lowest_reduced_cost.min(reduced_costs);
+                                        // patterns priced at smoothed duals may already be in the master
+                                        if smoothing > 0.0 && master_duals.reduced_cost(vehicle.index, &pattern, &self.horizon) >= -CG_EPSILON {
+                                            continue;
+                                        }
                                         // add the pattern to the pool
-                                        if let Some(entry) = self.pattern_pool.add_pattern(VehicleIndex::new(vehicle), *detour_cost, pattern.clone()) {
+                                        if let Some(entry) = self.pattern_pool.add_pattern(VehicleIndex::new(vehicle), detour_cost, pattern) {
                                             self.cg_model.add_column(VehicleIndex::new(vehicle), entry);
-                                            did_add_columns = true;
+                                            columns_added += 1;
                                         }
                                     }
                                 }
@@ -1094,12 +1106,34 @@ impl<'a,'b : 'a> Brancher<'a> {
                                     }
                                 }
                             }
+                            lagrangian_bound = lagrangian_bound.map(|bound| bound + lowest_reduced_cost.min(0.0));
                         }
                     }
-                }
 
-                if !did_add_columns {
-                    break
+                    let mis_priced = columns_added == 0 && smoothing > 0.0;
+                    self.pricing_rounds += 1;
+                    self.mis_pricings += usize::from(mis_priced);
+                    if self.record_iterations {
+                        self.cg_iterations.push(ColumnGenerationIteration {
+                            scenario : 0,
+                            run : self.cg_runs,
+                            iteration,
+                            master_objective : master_duals.value,
+                            lagrangian_bound,
+                            smoothing,
+                            columns_added,
+                            mis_priced,
+                        });
+                    }
+                    stabilizer.priced(priced_duals, lagrangian_bound, columns_added > 0);
+
+                    if columns_added > 0 {
+                        break
+                    }
+                    if !mis_priced {
+                        // no column at the master duals
+                        break 'masterLoop
+                    }
                 }
             }
         #[cfg(feature = "column_generation_debug")]
@@ -1421,9 +1455,11 @@ impl<'a,'b : 'a> Brancher<'a> {
             // Anytime where the number of usages of a site segment tuple is not equal to the total number of active patterns (paths/columns)
             // there must be at least one point (involving the given pattern) where one pattern does not use the segment / site combo
             // eg. if i use a site after a segment all active patterns must use it. Otherwise there is a divergence in the path.
-            let  multiple_sites_in_segment = site_segment_combo_count.iter()
+            let mut multiple_sites_in_segment = site_segment_combo_count.iter()
                     .filter(|(_,&combo_counts)| usize::from(combo_counts) != active_patterns_count)
                     .collect::<Vec<_>>() ;
+            // the map is iterated in arbitrary order, sort to branch on the same combination on every run
+            multiple_sites_in_segment.sort_unstable_by_key(|(combo,_)| **combo);
 
 
             #[cfg(feature = "infeasibility_events")] {
@@ -1435,16 +1471,11 @@ impl<'a,'b : 'a> Brancher<'a> {
                     use crate::petgraph::visit::NodeIndexable;
                     use crate::petgraph::visit::IntoNodeReferences;
                     use crate::petgraph::visit::NodeRef;
-                    let mut tmp_vec: Vec<Rc<Cell<f64>>> = Vec::with_capacity(self.sites.len() * MAX_PERIOD);
-                    for _ in 0..(self.sites.len() * MAX_PERIOD) {
-                        tmp_vec.push(Rc::new(Cell::new(0_f64)))
-                    }
-                    let site_period_duals = Array2::<Rc<Cell<f64>>>::from_shape_vec((self.sites.len(), MAX_PERIOD), tmp_vec).unwrap();
-                    let no_dual: Rc<Cell<f64>> = Rc::new(Cell::new(0_f64));
-                    let arc_site_period_duals = Rc::new(site_period_duals);
+                    let no_dual = Arc::new(DualTerm::default());
+                    let arc_site_period_duals = new_site_period_duals(self.sites.len(), &self.horizon);
 
 
-                    let (start, end, dag) = build_dag(vehicle, no_dual.clone(), arc_site_period_duals.clone(), &Vec::new(), self.site_sizes.clone());
+                    let (start, end, dag) = build_dag(vehicle, no_dual.clone(), arc_site_period_duals.clone(), &Vec::new(), self.site_sizes.clone(), &self.horizon);
 
 
                     let folder = Path::new("/tmp/infeasibility_events").join(self.invisibility_event_counter.to_string());
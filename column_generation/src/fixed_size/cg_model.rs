@@ -1,18 +1,17 @@
-use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
 use std::iter::Sum;
 
-use grb::constr::IneqExpr;
-use grb::prelude::*;
 use indexmap::IndexMap;
 use itertools::Itertools;
 use ndarray::Array2;
 use petgraph::visit::Walker;
-use shared::{charge_time_to_capacity_charge_time, CustomHashMap, CustomHashSet, CustomMultiHashMap, MAX_PERIOD, Period, Segment, Site, Vehicle};
+use shared::{CustomHashMap, CustomHashSet, CustomMultiHashMap, Horizon, Period, Segment, Site, SiteRef, Vehicle};
 use crate::branching_filter::{BranchingFilter, Dir};
 use crate::CG_EPSILON;
 use crate::fixed_size::brancher::{DUMMY_COST, SinglePattern};
 use crate::fixed_size::site_conf::SiteConf;
+use crate::lp::{Constr, LpModel, Sense, SolverEnv, Status, Var};
+use crate::lp::VarType::Continuous;
 use crate::pattern_pool::{Pattern, PatternEntry, PatternPool};
 
 #[derive(Copy,Debug,Clone,Eq, Hash, PartialEq)]
@@ -27,7 +26,7 @@ impl VehicleIndex {
 }
 #[derive(Copy,Clone,Eq, Hash, PartialEq)]
 pub struct ColumnIndex(usize);
-#[derive(Copy,Clone,Eq, Hash, PartialEq)]
+#[derive(Copy,Clone,Eq, Hash, PartialEq, PartialOrd, Ord)]
 pub struct SegmentId(u32);
 impl SegmentId {
     pub fn index(&self) -> u32 {
@@ -39,14 +38,14 @@ impl SegmentId {
 }
 
 
-#[derive(Copy,Clone,Eq, Hash, PartialEq,Debug)]
+#[derive(Copy,Clone,Eq, Hash, PartialEq,Debug, PartialOrd, Ord)]
 pub struct SiteIndex(usize);
 impl SiteIndex {
     pub fn index(&self) -> usize {
         self.0
     }
-    pub fn new(v : &Site) -> Self {
-        SiteIndex(v.index)
+    pub fn new(v : impl Into<SiteRef>) -> Self {
+        SiteIndex(v.into().index)
     }
 }
 
@@ -56,7 +55,9 @@ pub struct CgModel {
 
     current_site_sizes : Vec<u8>,
 
-    gurobi_model : RefCell<Model>,
+    horizon : Horizon,
+
+    model : Box<dyn LpModel>,
 
     // initialize hashmaps for model variables and patterns
     vehicle_patterns : CustomMultiHashMap<VehicleIndex,(ColumnIndex, Var,Pattern)>,
@@ -79,22 +80,22 @@ impl CgModel {
 
         let mut coef_vec: Vec<(Constr, f64)> = Vec::with_capacity(new_column.pattern.len()+1);
         // make column use one unit of convexity constraint
-        coef_vec.push( (self.vehicle_convexity[vehicle.index()].clone(),1.0));
+        coef_vec.push( (self.vehicle_convexity[vehicle.index()],1.0));
 
 
 
         // make column use one unit of capacity at every used site
         for (_, site, period) in new_column.pattern.iter() {
-            coef_vec.push((self.constr_max_capacity[[site.index(), charge_time_to_capacity_charge_time(period)]].clone(),1.0));
+            coef_vec.push((self.constr_max_capacity[[site.index(), self.horizon.capacity_period(period)]],1.0));
             // register the site_time branch constraints!
             if let Some(entry) = self.site_time_branch_constraint.get_vec(&(*site,*period)) {
                 for constr in entry {
-                    coef_vec.push((constr.1.clone(),1.0));
+                    coef_vec.push((constr.1,1.0));
                 }
             }
         }
 
-        let var_use_pattern = self.gurobi_model.get_mut().add_var(&format!("usePattern[{}]", new_column.id), Continuous, 0.0, 0.0, 1.0, coef_vec).unwrap();
+        let var_use_pattern = self.model.add_var(&format!("usePattern[{}]", new_column.id), Continuous, 0.0, 0.0, 1.0, &coef_vec);
         self.vehicle_patterns.insert(vehicle, (id,var_use_pattern, new_column.pattern.clone()));
 
 
@@ -112,7 +113,7 @@ impl CgModel {
 
         let var: Var = self.vehicle_patterns.get_vec(&vehicle).unwrap().iter().filter_map(|(iter_column_id, variable, _pattern)| {
             if iter_column_id.0 == column_id.0 {
-                Some(variable.clone())
+                Some(*variable)
             } else {
                 None
             }
@@ -120,7 +121,7 @@ impl CgModel {
 
 
         self.vehicle_patterns.get_vec_mut(&vehicle).unwrap().retain(|(id,_,_)| if id.0 == column_id.0 { false} else { true });
-        self.gurobi_model.get_mut().remove(var).unwrap();
+        self.model.remove_var(var);
 
 
     }
@@ -131,11 +132,12 @@ impl CgModel {
 
         let mut added_columns = 0;
 
-        let active_columns : HashSet<ColumnIndex> =  {
+        // keep the order of insertion, columns are then removed in the same order on every run
+        let active_columns : Vec<ColumnIndex> =  {
             if let Some(data) = self.vehicle_patterns.get_vec(&vehicle) {
                 data.iter().map(|(id,_,_)| id.clone()).collect()
             } else {
-                HashSet::default()
+                Vec::default()
             }
         };
 
@@ -174,9 +176,9 @@ impl CgModel {
 
         for ((old_size,new_size), constraints) in self.current_site_sizes.iter().zip(new_site_sizes.iter()).zip(self.constr_max_capacity.outer_iter()) {
             if old_size != new_size {
-                self.gurobi_model.get_mut().set_obj_attr_batch(attr::RHS,
-                                                               constraints.into_iter().cloned().zip(vec![*new_size as f64; constraints.len()])
-                ).unwrap();
+                for constr in constraints {
+                    self.model.set_rhs(*constr, f64::from(*new_size));
+                }
             }
         }
 
@@ -198,7 +200,7 @@ impl CgModel {
                 patterns.iter().for_each(|(_,var,pattern)| {
                     for (p_segment,p_site,p_period) in pattern {
                         if site == p_site && period == p_period {
-                            active_capacities.push(var.clone());
+                            active_capacities.push(*var);
                             break;
                         }
                     }
@@ -209,17 +211,15 @@ impl CgModel {
 
             self.site_time_branch_constraint.insert((*site, *period),
                                                     (filter.clone(),
-                                                    self.gurobi_model.get_mut().add_constr(
+                                                    self.model.add_constr(
                                                         &format!("branchCapacity[{},{},{}]", site.index(), period, value.float()),
-                                                        IneqExpr{
-                                                            lhs: Expr::sum(active_capacities.iter()),
-                                                            sense: match direction {
-                                                                Dir::Less => ConstrSense::Less,
-                                                                Dir::Greater => ConstrSense::Greater,
-                                                            },
-                                                            rhs:  Expr::Constant(value.float())
-                                                        }
-                                                    ).unwrap()
+                                                        &active_capacities.iter().map(|var| (*var, 1.0)).collect::<Vec<(Var, f64)>>(),
+                                                        match direction {
+                                                            Dir::Less => Sense::Less,
+                                                            Dir::Greater => Sense::Greater,
+                                                        },
+                                                        value.float()
+                                                    )
                                                     )
             );
         }
@@ -237,7 +237,7 @@ impl CgModel {
             {
                 for (constr_filter, constr) in constrs.iter() {
                     if constr_filter == filter {
-                        self.gurobi_model.get_mut().remove(constr.clone()).unwrap();
+                        self.model.remove_constr(*constr);
                         break;
                     }
                 }
@@ -276,8 +276,6 @@ impl CgModel {
 
     pub fn update<'a>(&mut self, site_sizes : SiteConf, pattern_pool : &PatternPool,  vehicles : &'a [Vehicle], charge_filters : &[BranchingFilter]) {
 
-        self.model_update();
-
         for vehicle in vehicles {
             // add the existing patterns from the pool
             {
@@ -294,37 +292,32 @@ impl CgModel {
 
     }
 
-    pub fn model_update(&mut self)  {
-        self.gurobi_model.get_mut().update().unwrap();
-    }
     pub fn solve(&mut self)  -> Status {
-        self.gurobi_model.get_mut().optimize().unwrap();
-        return self.gurobi_model.get_mut().status().unwrap();
+        self.model.optimize()
     }
 
     pub fn get_capacity_const_duals(&self) -> Array2<f64> {
-        // collect the duals of the convexity from gurobi c api
-        Array2::from_shape_vec((self.sites.len(), MAX_PERIOD),
-                               self.gurobi_model.borrow().get_obj_attr_batch(attr::Pi, self.constr_max_capacity.clone()
-                               ).unwrap()).unwrap()
+        // collect the duals of the convexity from the solver
+        Array2::from_shape_vec((self.sites.len(), self.horizon.periods),
+                               self.model.pi(self.constr_max_capacity.as_slice().unwrap())).unwrap()
     }
 
     pub fn get_site_time_branch_const_duals(&self, site : SiteIndex, period : Period) -> f64 {
         if let Some(entry) = self.site_time_branch_constraint.get_vec(&(site, period)) {
-            self.gurobi_model.borrow().get_obj_attr_batch(attr::Pi, entry.iter().map(|(_,constr)| constr.clone())).unwrap().into_iter().sum()
+            self.model.pi(&entry.iter().map(|(_,constr)| *constr).collect::<Vec<Constr>>()).into_iter().sum()
         } else { 0.0 }
     }
 
     pub fn get_vehicle_convexity_const_duals(&self)  -> Vec<f64> {
-        self.gurobi_model.borrow().get_obj_attr_batch(attr::Pi, self.vehicle_convexity.values().cloned().collect::<Vec<Constr>>()).unwrap()
+        self.model.pi(&self.vehicle_convexity.values().cloned().collect::<Vec<Constr>>())
     }
 
 
     pub fn obj_value(&self) -> f64 {
-        self.gurobi_model.borrow().get_attr(attr::ObjVal).unwrap()
+        self.model.obj_val()
     }
     pub fn get_dummy_vars_x(&self) -> Vec<f64> {
-        self.gurobi_model.borrow().get_obj_attr_batch(attr::X, self.dummy_vars.clone()).unwrap()
+        self.model.x(&self.dummy_vars)
     }
 
 
@@ -332,7 +325,7 @@ impl CgModel {
 
 
         if let Some(patterns) = self.vehicle_patterns.get_vec(&vehicle) {
-            let solution_values = self.gurobi_model.borrow().get_obj_attr_batch(attr::X, patterns.iter().map(|(id,var, _)| var.clone()).collect::<Vec<Var>>()).unwrap();
+            let solution_values = self.model.x(&patterns.iter().map(|(id,var, _)| *var).collect::<Vec<Var>>());
 
              patterns.iter()
                  .map(|(id,_, pattern)| pattern.clone())
@@ -348,31 +341,31 @@ impl CgModel {
     }
 
 
-    pub fn new(env : &Env, sites : Vec<Site>, site_sizes : Vec<u8>, vehicles : Vec<Vehicle>) -> Self {
+    pub fn new(env : &SolverEnv, sites : Vec<Site>, site_sizes : Vec<u8>, vehicles : Vec<Vehicle>, horizon : Horizon) -> Self {
 
-        let mut model = Model::with_env("master", env).unwrap();
+        let mut model = env.model("master");
 
         // setup site constaints
-        let mut site_constraints = Vec::with_capacity(sites.len() * MAX_PERIOD);
+        let mut site_constraints = Vec::with_capacity(sites.len() * horizon.periods);
         for site in &sites {
-            for p in 0..MAX_PERIOD {
+            for p in 0..horizon.periods {
                 site_constraints.push(
                     model.add_constr(
-                        &format!("maxCapacity[{},{},{}]", site.id, site.index, p),c!(Expr::default() <= f64::from(site_sizes[site.index]))
-                    ).unwrap());
+                        &format!("maxCapacity[{},{},{}]", site.id, site.index, p), &[], Sense::Less, f64::from(site_sizes[site.index])
+                    ));
             }
         }
-        let constr_max_capacity: Array2<Constr> = Array2::from_shape_vec((sites.len(), MAX_PERIOD), site_constraints).unwrap();
+        let constr_max_capacity: Array2<Constr> = Array2::from_shape_vec((sites.len(), horizon.periods), site_constraints).unwrap();
 
 
         // setup vehicles
         let mut dummy_vars = Vec::default();
         let mut vehicle_convexity = IndexMap::default();
         for vehicle in &vehicles {
-            let dummy_var = model.add_var(&format!("dummy_column[{}]", vehicle.id), Continuous, DUMMY_COST, 0.0, 1.0, []).unwrap();
-            let constr_convexity = model.add_constr(&format!("convexity[{}]", vehicle.id), c!(1.0 * dummy_var == 1.0)).unwrap();
+            let dummy_var = model.add_var(&format!("dummy_column[{}]", vehicle.id), Continuous, DUMMY_COST, 0.0, 1.0, &[]);
+            let constr_convexity = model.add_constr(&format!("convexity[{}]", vehicle.id), &[(dummy_var, 1.0)], Sense::Equal, 1.0);
             dummy_vars.push(dummy_var);
-            vehicle_convexity.insert(VehicleIndex::new(vehicle), constr_convexity.clone());
+            vehicle_convexity.insert(VehicleIndex::new(vehicle), constr_convexity);
         }
 
 
@@ -380,94 +373,38 @@ impl CgModel {
 
         CgModel {
             sites,
-            gurobi_model : RefCell::new(model),
+            model,
             vehicle_patterns : CustomMultiHashMap::default(),
             vehicle_convexity,
             dummy_vars,
             constr_max_capacity : constr_max_capacity,
             site_time_branch_constraint : CustomMultiHashMap::default(),
             applied_filters : CustomHashSet::default(),
-            current_site_sizes : site_sizes
+            current_site_sizes : site_sizes,
+            horizon
         }
     }
 
-    pub fn copy(&self) -> Self {
-
-        let mut model = self.gurobi_model.borrow_mut();
-        model.update().unwrap();
-
-        let mut cloned_model = model.try_clone().unwrap();
-        cloned_model.update().unwrap();
-
-
-        let mut constr_map : CustomHashMap<Constr,Constr> = CustomHashMap::default();
-
-        let new_constr = cloned_model.get_constrs().unwrap().to_vec();
-        let old_constr = model.get_constrs().unwrap();
-
-
-        assert_eq!(
-            cloned_model.get_obj_attr(attr::ConstrName, &new_constr[0]).unwrap(),
-            model.get_obj_attr(attr::ConstrName, &old_constr[0]).unwrap()
-        );
-
-        old_constr.into_iter().zip(new_constr.into_iter()).for_each(|(old,new)| {
-           constr_map.insert(*old,new);
-        });
-
-
-
-
-        let new_vars = cloned_model.get_vars().unwrap().to_vec();
-        let old_vars = model.get_vars().unwrap();
-        let mut var_map : CustomHashMap<Var,Var> = CustomHashMap::default();
-
-
-        assert_eq!(
-            cloned_model.get_obj_attr(attr::VarName, &new_vars[0]).unwrap(),
-            model.get_obj_attr(attr::VarName, &old_vars[0]).unwrap()
-        );
-
-        old_vars.into_iter().zip(new_vars.into_iter()).for_each(|(old,new)| {
-            var_map.insert(*old,new);
-        });
-
-        let mut new_constr_max_capacity = self.constr_max_capacity.clone();
-        new_constr_max_capacity.iter_mut().for_each(|e| *e = constr_map[e]);
-
-        let mut new_vehicle_convexity = self.vehicle_convexity.clone();
-        new_vehicle_convexity.iter_mut().for_each(|(_,c)| *c = constr_map[c] );
-
-
-
-        let mut new_site_time_branch_constraint = self.site_time_branch_constraint.clone();
-        new_site_time_branch_constraint.iter_all_mut().for_each(|(_k,cv)| cv.iter_mut().for_each(|c| (*c).1 = constr_map[&c.1]));
-
-
-
-
-        let new_dummy_vars : Vec<Var> = self.dummy_vars.iter().map(|v| var_map[v]).collect();
-
-        let mut new_vehicle_patterns = self.vehicle_patterns.clone();
-        new_vehicle_patterns.iter_all_mut().for_each(|(_,arr)| arr.iter_mut().for_each(|(_,var,_)| *var = var_map[var]));
-
+    pub fn copy(&mut self) -> Self {
 
+        // handles stay valid for the copied model
         CgModel {
             sites: self.sites.clone(),
             current_site_sizes: self.current_site_sizes.clone(),
-            gurobi_model : RefCell::new(cloned_model),
+            horizon: self.horizon,
+            model : self.model.box_clone(),
 
             applied_filters: self.applied_filters.clone(),
 
 
             // vars
-            vehicle_patterns : new_vehicle_patterns,
-            dummy_vars : new_dummy_vars,
+            vehicle_patterns : self.vehicle_patterns.clone(),
+            dummy_vars : self.dummy_vars.clone(),
 
             // constr
-            vehicle_convexity : new_vehicle_convexity,
-            constr_max_capacity : new_constr_max_capacity,
-            site_time_branch_constraint: new_site_time_branch_constraint,
+            vehicle_convexity : self.vehicle_convexity.clone(),
+            constr_max_capacity : self.constr_max_capacity.clone(),
+            site_time_branch_constraint: self.site_time_branch_constraint.clone(),
 
         }
     }
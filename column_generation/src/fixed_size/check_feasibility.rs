@@ -1,12 +1,13 @@
 use indexmap::IndexMap;
-use shared::{Site, Segment, Vehicle};
+use shared::{Site, SiteId, Segment, Vehicle, Horizon};
 use crate::SiteArray;
 use crate::fixed_size::brancher::{Brancher, SolveError};
 use crate::fixed_size::site_conf::{SiteConfFactory};
 use std::sync::Arc;
 use std::sync::atomic::AtomicBool;
-use grb::{Env,param};
+use crate::lp::{Backend, SolverEnv, SolverParams};
 use crate::pattern_pool::PatternPool;
+use crate::stabilization::Stabilization;
 
 pub struct CheckFeasibility {
 
@@ -14,7 +15,7 @@ pub struct CheckFeasibility {
 
 impl CheckFeasibility{
 
-    pub fn has_feasibility_error<'a>(sites: &'a IndexMap<u8, Site>, _segments_: &'a IndexMap<u32, Segment<'a>>, vehicles: Vec<Vehicle<'a>>, num_infeasible_allowed : usize) -> Option<SolveError> {
+    pub fn has_feasibility_error(sites: &IndexMap<SiteId, Site>, _segments_: &IndexMap<u32, Arc<Segment>>, vehicles: Vec<Vehicle>, num_infeasible_allowed : usize, horizon : &Horizon, seed : u64, backend : Backend) -> Option<SolveError> {
 
         let site_array: Vec<Site> = sites.into_iter().map(|(_i,site)| site.clone()).collect();
         let site_conf_factory = SiteConfFactory {
@@ -27,21 +28,19 @@ impl CheckFeasibility{
         for (site, conf) in site_array.iter().zip(site_conf.iter_mut()) {
             *conf = site.capacity;
         }
-        let mut env = Env::new("").unwrap();
-        env.set(param::Threads, 1).unwrap();
-        // 2= barrier; test with concurrent has shown that
-        // this usually wins!
-        env.set(param::Method, 2).unwrap();
-        env.set(param::Seed, 12345).unwrap();
-        env.set(param::LogToConsole, 0).unwrap();
+        let env = SolverEnv::new(backend, SolverParams {
+            seed,
+            barrier : true,
+            ..SolverParams::default()
+        }).unwrap_or_else(|e| panic!("{}", e));
 
 
         // set low time limit; we mainly want the integer solution by branching this function is only for quick wins;
-        let mut env_integer = Env::new("").unwrap();
-        env_integer.set(param::Threads, 1).unwrap();
-        env_integer.set(param::Seed, 12345).unwrap();
-        env_integer.set(param::LogToConsole, 0).unwrap();
-        env_integer.set(param::TimeLimit, 20.0).unwrap();
+        let env_integer = SolverEnv::new(backend, SolverParams {
+            seed,
+            time_limit : Some(20.0),
+            ..SolverParams::default()
+        }).unwrap_or_else(|e| panic!("{}", e));
 
 
         let num_vehicles = vehicles.len();
@@ -50,12 +49,15 @@ impl CheckFeasibility{
             site_array,
             vehicles,
             site_conf,
-            &env,
-            &env_integer,
+            env,
+            env_integer,
             num_infeasible_allowed,
             false,
             Arc::new(AtomicBool::new(false)),
-            PatternPool::new(num_vehicles)
+            PatternPool::new(num_vehicles),
+            *horizon,
+            1,
+            Stabilization::None
         );
 
         match brancher.solve(false, true) {
@@ -65,10 +67,10 @@ impl CheckFeasibility{
 
     }
 
-    pub fn get_potentially_feasible<'a>(sites: &'a IndexMap<u8, Site>, _segments: &'a IndexMap<u32, Segment<'a>>, vehicles: &'a [Vehicle<'a>]) -> Vec<&'a Vehicle<'a>> {
+    pub fn get_potentially_feasible<'a>(sites: &'a IndexMap<SiteId, Site>, _segments: &IndexMap<u32, Arc<Segment>>, vehicles: &'a [Vehicle], horizon : &Horizon) -> Vec<&'a Vehicle> {
             let site_conf_factory = SiteConfFactory {
                 num_sites : sites.len()
             };
-            Brancher::get_vehicles_that_can_be_feasible(vehicles.iter(),site_conf_factory.full(1))
+            Brancher::get_vehicles_that_can_be_feasible(vehicles.iter(),site_conf_factory.full(1), horizon)
     }
 }
\ No newline at end of file
@@ -1,6 +1,11 @@
 
 pub const UPPER_BOUND_COST : u32 = 9_999_999;
 
+/// Gurobi only accepts non-negative 32 bit seeds, fold the master seed into that range.
+pub fn gurobi_seed(seed : u64) -> i32 {
+    (seed % (i32::MAX as u64 + 1)) as i32
+}
+
 pub mod brancher;
 
 pub mod check_feasibility;
@@ -2,14 +2,14 @@ use std::cmp;
 use std::iter::Sum;
 use std::ops::Div;
 use indexmap::IndexMap;
-use shared::{Site, Segment, Vehicle, Period, MIN_PER_PERIOD, CustomMultiHashMap, MAX_PERIOD, charge_time_to_capacity_charge_time};
+use shared::{Site, SiteId, Segment, Vehicle, Period, Horizon, CustomMultiHashMap};
 use crate::{CG_EPSILON, SiteArray};
 use crate::fixed_size::brancher::{Brancher, BranchNode, BranchQueue, DUMMY_COST, ResultPattern, SinglePattern, SolveError};
 use crate::fixed_size::site_conf::{SiteConf, SiteConfFactory};
 use std::sync::Arc;
 use std::sync::atomic::AtomicBool;
-use grb::{attr, c, Constr, Env, Model, param, Status};
-use grb::prelude::Binary;
+use crate::lp::{Backend, Constr, Sense, SolverEnv, SolverParams, Status};
+use crate::lp::VarType::Binary;
 use ndarray::Array2;
 use rand::prelude::{IteratorRandom, StdRng};
 use rand::{Rng, SeedableRng};
@@ -43,7 +43,7 @@ impl PolicyFeasibility{
 
 
 
-    pub fn has_feasibility_error<'a>(sites: &'a IndexMap<u8, Site>, _segments_: &'a IndexMap<u32, Segment<'a>>, vehicles: Vec<Vehicle<'a>>) -> Option<SolveError> {
+    pub fn has_feasibility_error(sites: &IndexMap<SiteId, Site>, _segments_: &IndexMap<u32, Arc<Segment>>, vehicles: Vec<Vehicle>, horizon : &Horizon, seed : u64, backend : Backend) -> Option<SolveError> {
 
         /* high level:
         for every vehicle generate graph. Try to find path trough network
@@ -58,18 +58,17 @@ impl PolicyFeasibility{
 
 
         let outer_loops = 100;
-        let fuzzer_seed = 12345;
         let fuzzer_inner_loops = 3;
         let choice_top_closest = 3;
 
-        let mut rng =  StdRng::seed_from_u64(fuzzer_seed);
+        let mut rng =  StdRng::seed_from_u64(seed);
 
 
         let site_array : Vec<Site> = sites.iter().map(|(_i,site)| site.clone()).collect();
         let site_conf_factory = SiteConfFactory {
             num_sites: site_array.len()
         };
-        let site_conf = site_conf_factory.full(4);
+        let site_conf = site_conf_factory.largest(&site_array);
 
         let mut inf_results = Vec::default();
         for l in 0..outer_loops {
@@ -83,7 +82,7 @@ impl PolicyFeasibility{
 
                 // run fuzzer loops to generate feasible columns
                 for i in 0..fuzzer_inner_loops {
-                    if let Some(pattern) = Self::gen_fuzzy_charge_pattern(vehicle, &mut rng, &site_conf, choice_top_closest) {
+                    if let Some(pattern) = Self::gen_fuzzy_charge_pattern(vehicle, &mut rng, &site_array, &site_conf, choice_top_closest, horizon) {
                         patterns.push(pattern)
                     }
                 }
@@ -91,14 +90,15 @@ impl PolicyFeasibility{
             }
 
 
-            let mut env_integer = Env::new("/tmp/fuzzer_gurobi.log").unwrap();
+            let env_integer = SolverEnv::new(backend, SolverParams {
+                seed,
+                log_file : Some("/tmp/fuzzer_gurobi.log".to_string()),
+                log_to_console : true,
+                ..SolverParams::default()
+            }).unwrap_or_else(|e| panic!("{}", e));
 
-            env_integer.set(grb::param::LogToConsole, 1).unwrap();
-            env_integer.set(grb::param::Threads, 1).unwrap();
-            env_integer.set(grb::param::Seed, 12345).unwrap();
 
-
-            let inf = Self::solve_assignment_problem(&env_integer, site_array.clone(), &site_conf, &vehicles, vehicle_patterns);
+            let inf = Self::solve_assignment_problem(&env_integer, site_array.clone(), &site_conf, &vehicles, vehicle_patterns, horizon);
 
             inf_results.push(inf);
         }
@@ -110,7 +110,7 @@ impl PolicyFeasibility{
 
     }
 
-    fn gen_fuzzy_charge_pattern(vehicle : &Vehicle, mut rng: &mut StdRng, site_sizes : &SiteConf, take_top_closest : usize) -> Option<SinglePattern> {
+    fn gen_fuzzy_charge_pattern(vehicle : &Vehicle, mut rng: &mut StdRng, sites : &[Site], site_sizes : &SiteConf, take_top_closest : usize, horizon : &Horizon) -> Option<SinglePattern> {
 
 
         let mut charge_pattern : SinglePattern = Vec::default();
@@ -157,7 +157,7 @@ impl PolicyFeasibility{
                     let potential_charge_site = segment.reachable_sites.iter()
                         .filter(|rc| {
 
-                            let capacity = rc.site.capacity.min(site_sizes[rc.site.index]);
+                            let capacity = sites[rc.site.index].capacity.min(site_sizes[rc.site.index]);
                             if capacity <= 0 {
                                 return  false;
                             }
@@ -182,7 +182,7 @@ impl PolicyFeasibility{
                                 return false;
                             }
                             // 10 minute minimum for charging
-                            if periods_available <= 10 / MIN_PER_PERIOD as u16 {
+                            if periods_available <= horizon.periods_in(10) {
                                 return false;
                             }
 
@@ -205,7 +205,7 @@ impl PolicyFeasibility{
 
                         let arrival_period = site.arrival_time;
                         let latest_departure_period = site.departure_time;
-                        let max_periods_available: Period = cmp::min(10, latest_departure_period - arrival_period);
+                        let max_periods_available: Period = cmp::min(horizon.periods_in(50), latest_departure_period - arrival_period);
 
 
                         let charge_threshold = get_high_tresh(&mut rng);
@@ -214,7 +214,7 @@ impl PolicyFeasibility{
                             // charge for that period
 
                             charge_pattern.push((SegmentId::new(segment), SiteIndex::new(site.site), charge_period));
-                            soc = vehicle.get_new_soc_after_charging(soc, MIN_PER_PERIOD);
+                            soc = vehicle.get_new_soc_after_charging(soc, horizon.minutes_per_period);
                             // test if we have charged enough
                             if soc >= charge_threshold {
                                 actual_departure_period = charge_period;
@@ -255,14 +255,14 @@ impl PolicyFeasibility{
     }
 
 
-    fn solve_assignment_problem(env_integer : &Env, sites : Vec<Site>, site_sizes : &[u8], vehicles : &[Vehicle], patterns : Vec<Vec<SinglePattern>>) -> usize {
+    fn solve_assignment_problem(env_integer : &SolverEnv, sites : Vec<Site>, site_sizes : &[u8], vehicles : &[Vehicle], patterns : Vec<Vec<SinglePattern>>, horizon : &Horizon) -> usize {
 
 
             // integer problem is only solved as kind of heuristic on the root node, thus only give limited amount of time.
 
 
             // create an empty model which associated with `env`:
-            let mut integer_master = Model::with_env("integer_env", env_integer).unwrap();
+            let mut integer_master = env_integer.model("integer_env");
 
             // initialize hashmaps for model variables and patterns
             let mut vehicle_patterns = CustomMultiHashMap::default();
@@ -270,15 +270,15 @@ impl PolicyFeasibility{
 
 
 
-            let mut site_constraints = Vec::with_capacity(sites.len() * MAX_PERIOD);
+            let mut site_constraints = Vec::with_capacity(sites.len() * horizon.periods);
             for site in &sites {
-                for p in 0..MAX_PERIOD {
+                for p in 0..horizon.periods {
                     site_constraints.push(
                         integer_master.add_constr(&format!("maxCapacity[{},{},{}]", site.id, site.index, p),
-                                                  c!( 0 <= f64::from( site_sizes[site.index].min(site.capacity)))/*site.capacity.into()*/).unwrap());
+                                                  &[], Sense::Less, f64::from( site_sizes[site.index].min(site.capacity))/*site.capacity.into()*/));
                 }
             }
-            let constr_max_capacity: Array2<Constr> = Array2::from_shape_vec((sites.len(), MAX_PERIOD), site_constraints).unwrap();
+            let constr_max_capacity: Array2<Constr> = Array2::from_shape_vec((sites.len(), horizon.periods), site_constraints).unwrap();
 
 
 
@@ -289,11 +289,11 @@ impl PolicyFeasibility{
 
 
             for vehicle in vehicles {
-                let dummy_var = integer_master.add_var(&format!("dummy_column[{}]", vehicle.id), Binary, DUMMY_COST, 0.0, 1.0, []).unwrap();
-                let constr_convexity = integer_master.add_constr(&format!("convexity[{}]", vehicle.id), c!(1.0 * dummy_var == 1.0)).unwrap();
+                let dummy_var = integer_master.add_var(&format!("dummy_column[{}]", vehicle.id), Binary, DUMMY_COST, 0.0, 1.0, &[]);
+                let constr_convexity = integer_master.add_constr(&format!("convexity[{}]", vehicle.id), &[(dummy_var, 1.0)], Sense::Equal, 1.0);
                 all_dummy_vars.push(dummy_var);
 
-                vehicle_convexity.insert(vehicle, constr_convexity.clone());
+                vehicle_convexity.insert(vehicle, constr_convexity);
 
                 // add the existing patterns from the pool
                 // copy paste from code in column generation below
@@ -311,12 +311,12 @@ impl PolicyFeasibility{
 
                         // make column use one unit of capacity at every used site
                         for (_, site, period) in entry.iter() {
-                            coef_vec.push((constr_max_capacity[[site.index(), charge_time_to_capacity_charge_time(period)]].clone(),1.0));
+                            coef_vec.push((constr_max_capacity[[site.index(), horizon.capacity_period(period)]],1.0));
                         }
 
 
 
-                        let var_use_pattern = integer_master.add_var(&format!("usePattern[{}]", pattern_counter), Binary, 0.0, 0.0, 1.0, coef_vec).unwrap();
+                        let var_use_pattern = integer_master.add_var(&format!("usePattern[{}]", pattern_counter), Binary, 0.0, 0.0, 1.0, &coef_vec);
                         vehicle_patterns.insert(vehicle, (var_use_pattern, entry.clone()));
                         pattern_counter += 1;
                     }
@@ -330,21 +330,17 @@ impl PolicyFeasibility{
 
 
 
-            integer_master.update().unwrap();
-            integer_master.optimize().unwrap();
-
-
-            if integer_master.status().unwrap() != Status::Optimal {
+            if integer_master.optimize() != Status::Optimal {
                 panic!("INFEASIBLE");
             }
 
 
             // do test if we are infeasible
 
-            let has_dummy_values = integer_master.get_obj_attr_batch(attr::X, all_dummy_vars.clone()).unwrap().iter().filter(|&v| *v >= CG_EPSILON).count();
+            let has_dummy_values = integer_master.x(&all_dummy_vars).iter().filter(|&v| *v >= CG_EPSILON).count();
             if has_dummy_values > 0 {
 
-                let infeasible_vehicles = integer_master.get_obj_attr_batch(attr::X, all_dummy_vars.clone()).unwrap().iter().zip(vehicles).filter_map(|(value,vehicle)| {
+                let infeasible_vehicles = integer_master.x(&all_dummy_vars).iter().zip(vehicles).filter_map(|(value,vehicle)| {
                     if *value > CG_EPSILON {
                         Some(VehicleIndex::new(vehicle))
                     } else {
@@ -1,15 +1,25 @@
-use crate::fixed_size::brancher::Brancher;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering::Relaxed;
 
-pub struct ScenarioManager<'a> {
-    pub branchers : Vec<Brancher<'a>>,
+use crossbeam::channel;
+use crossbeam_deque::{Injector, Steal};
+
+use crate::fixed_size::brancher::{Brancher, ResultPattern, SolveError};
+use crate::fixed_size::site_conf::SiteConf;
+
+pub type OracleResult = Result<(f64, ResultPattern), SolveError>;
+
+pub struct ScenarioManager {
+    pub branchers : Vec<Brancher>,
     pub active_sets : Vec<bool>,
     pub generation_set : Vec<bool>
 
 }
 
-impl <'a> ScenarioManager<'a> {
+impl ScenarioManager {
 
-    pub fn new(branchers : Vec<Brancher<'a>>) -> Self {
+    pub fn new(branchers : Vec<Brancher>) -> Self {
 
         let active_sets = vec![false;branchers.len()];
         let generation_set = vec![false;branchers.len()];
@@ -20,7 +30,7 @@ impl <'a> ScenarioManager<'a> {
         }
     }
 
-    pub fn add_brancher_and_activate(&mut self, brancher : Brancher<'a>) {
+    pub fn add_brancher_and_activate(&mut self, brancher : Brancher) {
         self.branchers.push(brancher);
         self.active_sets.push(true);
         self.generation_set.push(true);
@@ -31,11 +41,11 @@ impl <'a> ScenarioManager<'a> {
     }
 
 
-    pub fn get_all_branchers(&mut self) -> impl Iterator<Item =  (usize,&mut Brancher<'a>)>{
+    pub fn get_all_branchers(&mut self) -> impl Iterator<Item =  (usize,&mut Brancher)>{
         self.branchers.iter_mut().enumerate()
     }
 
-    pub fn get_active_branchers(&mut self) -> impl Iterator<Item = (usize,&mut Brancher<'a>)>{
+    pub fn get_active_branchers(&mut self) -> impl Iterator<Item = (usize,&mut Brancher)>{
         self.active_sets.iter().zip(self.branchers.iter_mut().enumerate())
             .filter_map(|(f, b) | {
                 if *f == true {
@@ -47,7 +57,7 @@ impl <'a> ScenarioManager<'a> {
     }
 
 
-    pub fn get_inactive_branchers(&mut self) -> impl Iterator<Item =  (usize,&mut Brancher<'a>)>{
+    pub fn get_inactive_branchers(&mut self) -> impl Iterator<Item =  (usize,&mut Brancher)>{
         self.active_sets.iter().zip(self.branchers.iter_mut().enumerate())
             .filter_map(|(f, b) | {
                 if *f == false {
@@ -74,4 +84,144 @@ impl <'a> ScenarioManager<'a> {
         self.active_sets[index] = false;
         self.generation_set[index] = true;
     }
-}
\ No newline at end of file
+
+    /// Solves every scenario for the site sizes on up to `workers` threads. Results are in the
+    /// order of the branchers.
+    pub fn solve_all(&mut self, site_sizes : &SiteConf, find_num_infeasible : bool, workers : usize) -> Vec<(usize, OracleResult)> {
+        let branchers = self.branchers.iter_mut().enumerate().collect();
+        solve_scenarios(branchers, site_sizes, find_num_infeasible, workers, |_| false)
+    }
+
+    /// Solves the active scenarios for the site sizes on up to `workers` threads until
+    /// `quorum_required` of them are feasible or that many can no longer be. Scenarios not started
+    /// by then are skipped. Results are in the order of the branchers, with a single worker
+    /// the scenarios are solved one after the other up to the decision.
+    pub fn solve_active_until_quorum(&mut self, site_sizes : &SiteConf, quorum_required : usize, workers : usize) -> Vec<(usize, OracleResult)> {
+        let num_active = self.num_active();
+        let branchers = self.get_active_branchers().collect();
+        solve_scenarios(branchers, site_sizes, false, workers, |results| {
+            let oracle_ok = results.iter().filter(|(_, result)| result.is_ok()).count();
+            let oracle_denied = results.len() - oracle_ok;
+            oracle_ok >= quorum_required || num_active - oracle_denied < quorum_required
+        })
+    }
+}
+
+
+/// Hands the branchers to the workers, each brancher is solved by a single thread. No further
+/// branchers are started once `is_decided` holds for the results so far.
+fn solve_scenarios<F>(branchers : Vec<(usize, &mut Brancher)>, site_sizes : &SiteConf, find_num_infeasible : bool, workers : usize, is_decided : F) -> Vec<(usize, OracleResult)>
+    where F : Fn(&[(usize, OracleResult)]) -> bool {
+
+    let jobs = branchers.into_iter()
+        .map(|(idx, brancher)| (idx, brancher.cancel.clone(), brancher))
+        .collect();
+    solve_until_decided(jobs, workers, |brancher : &mut Brancher| {
+        brancher.replace_site_sizes(site_sizes.clone());
+        brancher.solve(false, find_num_infeasible)
+    }, is_decided)
+}
+
+/// Solves the jobs on up to `workers` threads. Once `is_decided` holds for the results so far, no
+/// further jobs are started and the cancel flags of the running ones are raised. Their results are
+/// discarded, the flags are lowered again before returning. Results are in the order of the jobs.
+fn solve_until_decided<T, S, F>(jobs : Vec<(usize, Arc<AtomicBool>, T)>, workers : usize, solve : S, is_decided : F) -> Vec<(usize, OracleResult)>
+    where T : Send, S : Fn(T) -> OracleResult + Sync, F : Fn(&[(usize, OracleResult)]) -> bool {
+
+    let mut results = Vec::with_capacity(jobs.len());
+    let workers = workers.min(jobs.len());
+
+    if workers <= 1 {
+        for (idx, _, job) in jobs {
+            results.push((idx, solve(job)));
+            if is_decided(&results) {
+                break;
+            }
+        }
+        return results;
+    }
+
+    let cancels : Vec<Arc<AtomicBool>> = jobs.iter().map(|(_, cancel, _)| cancel.clone()).collect();
+    let queue = Injector::new();
+    for (idx, _, job) in jobs {
+        queue.push((idx, job));
+    }
+    let decided = AtomicBool::new(false);
+    let (sender, receiver) = channel::unbounded();
+
+    crossbeam::thread::scope(|scope| {
+        for _ in 0..workers {
+            let (queue, decided, sender, solve) = (&queue, &decided, sender.clone(), &solve);
+            scope.spawn(move |_| {
+                while !decided.load(Relaxed) {
+                    match queue.steal() {
+                        Steal::Success((idx, job)) => {
+                            sender.send((idx, solve(job))).unwrap();
+                        }
+                        Steal::Retry => continue,
+                        Steal::Empty => break,
+                    }
+                }
+            });
+        }
+        // the receiver ends once all workers are done
+        drop(sender);
+
+        for result in receiver.iter() {
+            if decided.load(Relaxed) {
+                // solves still running when the decision was made are cancelled, not denied
+                if !matches!(result.1, Err(SolveError::StoppedByExternal)) {
+                    results.push(result);
+                }
+                continue;
+            }
+            results.push(result);
+            if is_decided(&results) {
+                decided.store(true, Relaxed);
+                for cancel in &cancels {
+                    cancel.store(true, Relaxed);
+                }
+            }
+        }
+    }).unwrap();
+
+    for cancel in &cancels {
+        cancel.store(false, Relaxed);
+    }
+    results.sort_by_key(|(idx, _)| *idx);
+    results
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_decided_quorum_cancels_running_solves() {
+        let slow = [true, false, false, true];
+        let cancels : Vec<Arc<AtomicBool>> = slow.iter().map(|_| Arc::new(AtomicBool::new(false))).collect();
+        let jobs = slow.iter().zip(&cancels).enumerate()
+            .map(|(idx, (slow, cancel))| (idx, cancel.clone(), (*slow, cancel.clone())))
+            .collect();
+
+        let start = Instant::now();
+        let results = solve_until_decided(jobs, slow.len(), |(slow, cancel) : (bool, Arc<AtomicBool>)| {
+            if !slow {
+                return Ok((0.0, Vec::new()));
+            }
+            // runs until it is cancelled, as a brancher checks its flag between solves
+            while !cancel.load(Relaxed) {
+                assert!(start.elapsed() < Duration::from_secs(30), "the solve was never cancelled");
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            Err(SolveError::StoppedByExternal)
+        }, |results| results.iter().filter(|(_, result)| result.is_ok()).count() >= 2);
+
+        // the cancelled solves count neither as accepted nor as denied
+        assert_eq!(results.iter().map(|(idx, _)| *idx).collect::<Vec<usize>>(), vec![1, 2]);
+        assert!(results.iter().all(|(_, result)| result.is_ok()));
+        assert!(cancels.iter().all(|cancel| !cancel.load(Relaxed)));
+    }
+}
@@ -3,15 +3,14 @@ use std::cmp;
 use std::iter::Sum;
 use std::ops::Div;
 use indexmap::IndexMap;
-use shared::{Site, Segment, Vehicle, Period, MIN_PER_PERIOD, CustomMultiHashMap, MAX_PERIOD, charge_time_to_capacity_charge_time, ReachableSite};
+use shared::{Site, SiteId, Segment, Vehicle, Period, Horizon, CustomMultiHashMap, ReachableSite};
 use crate::{CG_EPSILON, SiteArray};
 use crate::fixed_size::brancher::{Brancher, BranchNode, BranchQueue, DUMMY_COST, ResultPattern, SinglePattern, SolveError};
 use crate::fixed_size::site_conf::{SiteConf, SiteConfFactory};
 use std::sync::Arc;
 use std::sync::atomic::AtomicBool;
 use chrono::Weekday::Sat;
-use grb::{attr, c, Constr, Env, Model, param, Status};
-use grb::prelude::Binary;
+
 use ndarray::Array2;
 use rand::prelude::{IteratorRandom, StdRng};
 use rand::{Rng, SeedableRng};
@@ -23,15 +22,15 @@ use rand_distr::{Normal, Distribution};
 use crate::pattern_pool::PatternPool;
 
 
-pub struct SimulationFeasibility2<'a> {
+pub struct SimulationFeasibility2 {
 
     vehicle_states : Vec<Cell<VehicleState>>,
     vehicle_tour_position : Vec<Cell<usize>>,
     station_free: Vec<Cell<u8>>,
 
-    sites: &'a IndexMap<u8, Site>,
-    segments: &'a IndexMap<u32, Segment<'a>>,
-    vehicles: Vec<Vehicle<'a>>
+    sites: IndexMap<SiteId, Site>,
+    segments: IndexMap<u32, Arc<Segment>>,
+    vehicles: Vec<Vehicle>
 }
 
 #[derive(Clone,Copy)]
@@ -83,19 +82,21 @@ impl StateUpdate {
 
 #[derive(Clone,Debug)]
 struct  State {
+    capacity : Vec<u8>,
     free_capacity : Vec<Vec<u8>>
 }
 
 impl State {
-    fn new(site_sizes :  Vec<u8> ) -> Self {
+    fn new(site_sizes :  Vec<u8>, horizon : &Horizon) -> Self {
         Self {
-            free_capacity : site_sizes.into_iter().map(|s| vec![s; MAX_PERIOD * 2]).collect()
+            free_capacity : site_sizes.iter().map(|s| vec![*s; horizon.periods * 2]).collect(),
+            capacity : site_sizes
         }
     }
 }
 
-struct SimulatedVehicle<'a> {
-    vehicle : Vehicle<'a>,
+struct SimulatedVehicle {
+    vehicle : Vehicle,
 
     _soc : Cell<f64>,
     _active_action : Cell<VehicleAction>,
@@ -105,21 +106,24 @@ struct SimulatedVehicle<'a> {
 
     _handled_segments : Cell<usize>,
 
+    horizon : Horizon,
+
     low_tresh : f64, low_tresh_late : f64, high_tresh : f64
 }
 
 
 
 
-impl<'a> SimulatedVehicle<'a> {
+impl SimulatedVehicle {
 
-    pub fn new(vehicle : Vehicle<'a>, low_tresh : f64, low_tresh_late : f64, high_tresh : f64) -> Self {
+    pub fn new(vehicle : Vehicle, low_tresh : f64, low_tresh_late : f64, high_tresh : f64, horizon : Horizon) -> Self {
         Self {
             _soc : Cell::new(vehicle.battery.initial_charge),
             vehicle,
             _active_action : Cell::new(VehicleAction::IdleUntil(0)),
             _handled_segments : Cell::new(0),
             _current_segment_index : Cell::new(0),
+            horizon,
             low_tresh , low_tresh_late , high_tresh
         }
     }
@@ -151,8 +155,8 @@ impl<'a> SimulatedVehicle<'a> {
     }
 
 
-    fn get_current_segment(&self) -> Option<Segment> {
-        self.vehicle.tour.get(self._current_segment_index.get()).map(|x| x.clone().clone())
+    fn get_current_segment(&self) -> Option<Arc<Segment>> {
+        self.vehicle.tour.get(self._current_segment_index.get()).cloned()
     }
 
     fn mark_segment_done(&self) {
@@ -169,7 +173,7 @@ impl<'a> SimulatedVehicle<'a> {
 
 
 
-    fn make_decision_at_start_of_segment(&self, p : Period, segment : Segment, state : &State) -> Vec<StateUpdate> {
+    fn make_decision_at_start_of_segment(&self, p : Period, segment : Arc<Segment>, state : &State) -> Vec<StateUpdate> {
 
         self._handled_segments.update(|x| x + 1);
 
@@ -181,7 +185,7 @@ impl<'a> SimulatedVehicle<'a> {
 
                 let potential_charge_sites = segment.reachable_sites.iter()
                     .filter(|rc| {
-                        if rc.site.capacity <= 0 { // site does not exit
+                        if state.capacity[rc.site.index] == 0 { // site does not exit
                             return false;
                         }
 
@@ -206,7 +210,7 @@ impl<'a> SimulatedVehicle<'a> {
                             return false;
                         }
                         // 10 minute minimum for charging
-                        if periods_available < 2 {
+                        if periods_available < self.horizon.periods_in(10) {
                             return false;
                         }
 
@@ -353,7 +357,7 @@ impl<'a> SimulatedVehicle<'a> {
 
                         let arrival_period = p;
                         let latest_departure_period = inbound_info.max_until;
-                        let max_periods_available: Period = cmp::min(10, latest_departure_period - arrival_period + 1);
+                        let max_periods_available: Period = cmp::min(self.horizon.periods_in(50), latest_departure_period - arrival_period + 1);
 
 
                         let mut actual_departure_period = latest_departure_period;
@@ -363,7 +367,7 @@ impl<'a> SimulatedVehicle<'a> {
                             if state.free_capacity[inbound_info.at.index()][charge_period as usize] > 0 {
                                 updates.push(StateUpdate::UseCapacity(inbound_info.at, charge_period));
                                 self._soc.set(
-                                    self.vehicle.get_new_soc_after_charging(self.soc(), MIN_PER_PERIOD)
+                                    self.vehicle.get_new_soc_after_charging(self.soc(), self.horizon.minutes_per_period)
                                 );
                                 // test if we have charged enough
                                 if self.soc() > self.high_tresh {
@@ -382,7 +386,7 @@ impl<'a> SimulatedVehicle<'a> {
                             if let Some(segment) = self.get_current_segment() {
                                 segment.stop_time + 1
                             } else {
-                                MAX_PERIOD  as Period
+                                self.horizon.end()
                             }
                         ));
                         self.mark_segment_done();
@@ -416,7 +420,7 @@ impl<'a> SimulatedVehicle<'a> {
                         if let Some(segment) = self.get_current_segment() {
                             segment.stop_time + 1
                         } else {
-                            MAX_PERIOD as Period
+                            self.horizon.end()
                         }
                     ));
                     self.mark_segment_done();
@@ -429,7 +433,7 @@ impl<'a> SimulatedVehicle<'a> {
 
                     let arrival_period = p;
                     let latest_departure_period = waiting_info.max_until;
-                    let max_periods_available: Period = cmp::min(10, latest_departure_period - arrival_period + 1);
+                    let max_periods_available: Period = cmp::min(self.horizon.periods_in(50), latest_departure_period - arrival_period + 1);
 
 
                     let mut actual_departure_period = latest_departure_period;
@@ -439,7 +443,7 @@ impl<'a> SimulatedVehicle<'a> {
                         if state.free_capacity[waiting_info.at.index()][charge_period as usize] > 0 {
                             updates.push(StateUpdate::UseCapacity(waiting_info.at, charge_period));
                             self._soc.set(
-                                self.vehicle.get_new_soc_after_charging(self.soc(), MIN_PER_PERIOD)
+                                self.vehicle.get_new_soc_after_charging(self.soc(), self.horizon.minutes_per_period)
                             );
                             // test if we have charged enough
                             if self.soc() > self.high_tresh {
@@ -458,7 +462,7 @@ impl<'a> SimulatedVehicle<'a> {
                         if let Some(segment) = self.get_current_segment() {
                             segment.stop_time + 1
                         } else {
-                            MAX_PERIOD  as Period
+                            self.horizon.end()
                         }
                     ));
                     self.mark_segment_done();
@@ -486,11 +490,11 @@ pub struct SimulationFeasibility {
 impl SimulationFeasibility {
 
 
-    pub fn run<'a>(sites: &'a IndexMap<u8, Site>, segments: &'a IndexMap<u32, Segment<'a>>, vehicles: Vec<Vehicle<'a>>, rng : &mut StdRng) -> usize {
+    pub fn run(sites: &IndexMap<SiteId, Site>, segments: &IndexMap<u32, Arc<Segment>>, vehicles: Vec<Vehicle>, rng : &mut StdRng, horizon : &Horizon) -> usize {
 
 
         let site_sizes  =  sites.iter().map(|(_i,site)| site.capacity).collect();
-        let mut state = State::new(site_sizes);
+        let mut state = State::new(site_sizes, horizon);
 
 
         let low_dist =  Normal::new(0.30, 0.05).unwrap();
@@ -502,14 +506,15 @@ impl SimulationFeasibility {
             SimulatedVehicle::new(v,
             rng.sample(low_dist),
             rng.sample(low_late_dist),
-            rng.sample(high_dist))
+            rng.sample(high_dist),
+            *horizon)
         ).collect();
 
 
 
 
 
-        for t in (0 as Period)..(MAX_PERIOD as Period)*2 {
+        for t in (0 as Period)..horizon.end()*2 {
             for vehicle in simulated_vehicles.iter_mut() {
                 for update in vehicle.tick(t,&state) { state = update.apply(&state); }
             }
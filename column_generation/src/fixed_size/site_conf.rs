@@ -15,6 +15,16 @@ impl SiteConfFactory {
         vec![size; self.num_sites]
     }
 
+    /// every site opened at its largest level
+    pub fn largest(&self, site_array : &[Site]) -> SiteConf {
+        site_array.iter().map(|site| site.max_level()).collect()
+    }
+
+    /// every site opened at its largest level not exceeding `size`, closed if it has none
+    pub fn at_size(&self, site_array : &[Site], size : u8) -> SiteConf {
+        site_array.iter().map(|site| site.level_at_most(size)).collect()
+    }
+
     pub fn from_closed_vector(&self, closed_sites : &[bool], site_array : &[Site], site_cap : u8) -> SiteConf {
         let mut current_pattern = vec![0; self.num_sites];
         for (((_idx, el), result_val), site) in current_pattern.iter_mut().enumerate().zip(closed_sites.iter()).zip(site_array.iter()) {
@@ -4,7 +4,7 @@ use std::sync::atomic::AtomicBool;
 
 use indexmap::IndexMap;
 use itertools::Itertools;
-use shared::{Segment, Simple, Site, Vehicle};
+use shared::{relative_gap, ChargePlan, ChargeVisit, ColumnGenerationIteration, Horizon, OpenSite, ScenarioResult, Segment, Simple, Site, SiteId, SolutionReport, SolveStatistics, Vehicle, VehicleTimeline};
 use std::iter::Sum;
 
 use crate::fixed_size::site_conf::{SiteConf, SiteConfFactory};
@@ -18,8 +18,6 @@ use rust_hawktracer::*;
 
 use rand::prelude::{StdRng, SliceRandom};
 use rand::{SeedableRng, Rng};
-use grb::prelude::*;
-use grb::expr::LinExpr;
 use std::path::PathBuf;
 
 use std::fs::File;
@@ -33,13 +31,30 @@ use std::io;
 use std::io::BufRead;
 use std::cmp::max;
 
-use grb::{attr, Var};
+use crate::lp::{Backend, Constr, LpModel, Sense, SolverEnv, SolverError, SolverParams, Status, Var};
+use crate::lp::VarType::Binary;
 use petgraph::visit::Walker;
 use crate::fixed_size::cg_model::VehicleIndex;
 use crate::fixed_size::scenario_manager::ScenarioManager;
+use crate::stabilization::Stabilization;
 use crate::pattern_pool::{PatternEntry, PatternPool};
+use crate::planner::PlanningError;
+
+/// Site sizing and files of a [`SolutionApproachVariable::run`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RunSettings<'a> {
+    /// Level all sites are opened at, the largest one if not given.
+    pub static_target_size : Option<u8>,
+    /// Approach the static level from the largest one.
+    pub do_low_high_swap : bool,
+    /// Open every site at a level of its own.
+    pub do_variable_sizing : bool,
+    pub charge_processes_output : Option<&'a str>,
+    pub cuts_output : Option<&'a str>,
+    pub cuts_input : Option<&'a str>,
+}
 
-pub struct SolutionApproachVariable<'a> {
+pub struct SolutionApproachVariable {
     rng : StdRng,
     min_num_sites: usize,
     site_array : Vec<Site>,
@@ -48,7 +63,7 @@ pub struct SolutionApproachVariable<'a> {
     best_pattern : SiteConf,
     best_brancher_pattern : Option<ResultPattern>,
     tested_cuts : HashSet<Cut>,
-    scenario_manager : ScenarioManager<'a>,
+    scenario_manager : ScenarioManager,
     allowed_infeasible : usize,
     quorum_accept_percent : u8,
     benevolent_accept_percent : u8,
@@ -56,19 +71,26 @@ pub struct SolutionApproachVariable<'a> {
     activate_all : bool,
     iis_activate : bool,
     total_num_vehicles : i64,
+    horizon : Horizon,
+    seed : u64,
     should_stop : Arc<AtomicBool>,
-    gurobi_threads : i32
+    gurobi_threads : i32,
+    // threads solving the pricing problems within a scenario
+    pricing_workers : usize,
+    record_iterations : bool,
+    // threads solving the scenarios of a configuration
+    scenario_workers : usize,
+    stabilization : Stabilization,
+    backend : Backend,
+    // input scenario of every brancher, virtual scenarios of infeasible vehicles come after the inputs
+    brancher_scenarios : Vec<usize>,
+    // outcome of the last evaluation of the best pattern per brancher, with the number of infeasible vehicles
+    last_evaluation : Vec<(usize, bool, usize, Option<ResultPattern>)>,
+    lower_bound : f64,
+    statistics : SolveStatistics
 }
 
 
-const MAX_FIXED_SIZE : u8 = 4;
-
-
-
-const SITE_LOW_LEVEL: u8 = 2;
-const SITE_HIGH_LEVEL: u8 = 4;
-
-
 #[derive(PartialEq)]
 enum SubsetFeasibility {
     FEASIBLE,
@@ -87,36 +109,22 @@ impl Cut {
     }
 
 
-    fn forbid_site_size_array(size_of_site : &[u8], fixed_size_level : Option<u8>) -> Self {
+    fn forbid_site_size_array(size_of_site : &[u8], site_array : &[Site], fixed_levels : Option<&[u8]>) -> Self {
         // list all level shifts that can be feasible
         let mut cut_items : Vec<CutItem> = Vec::new();
-        for (idx,size) in size_of_site.iter().enumerate() {
-
-            if let Some(size_level) = fixed_size_level {
-                match *size {
-                    0 => {
-                        cut_items.push(CutItem::new(idx, size_level));
-                    },
-                    size_level => { /* cant open anymore */}
-                    _ => unreachable!()
+        for ((idx,size), site) in size_of_site.iter().enumerate().zip(site_array) {
+
+            if let Some(levels) = fixed_levels {
+                debug_assert!(*size == 0 || *size == levels[idx]);
+                // closed sites can/must be opened at their fixed level to change,
+                // open ones cant open anymore
+                if *size == 0 && levels[idx] > 0 {
+                    cut_items.push(CutItem::new(idx, levels[idx]));
                 }
-
             } else {
-                match *size {
-                    0 => {
-
-                        // can/must open either low or high to change
-                        cut_items.push(CutItem::new(idx, SITE_LOW_LEVEL));
-                        cut_items.push(CutItem::new(idx, SITE_HIGH_LEVEL));
-                    },
-                    SITE_LOW_LEVEL => {
-                        // can/must open high to change
-                        cut_items.push(CutItem::new(idx, SITE_HIGH_LEVEL));
-                    }
-                    SITE_HIGH_LEVEL => {
-                        // cant open anymore
-                    }
-                    _ => unreachable!()
+                // can/must open at any of the larger levels to change
+                for level in site.levels.iter().filter(|l| l.chargers > *size) {
+                    cut_items.push(CutItem::new(idx, level.chargers));
                 }
             }
         }
@@ -124,6 +132,22 @@ impl Cut {
     }
 }
 
+/// cost of a site at a size of a site configuration, which only ever holds levels of the site
+fn conf_cost(site : &Site, size : u8) -> u32 {
+    site.level_cost(size).unwrap_or_else(|| panic!("site configuration holds no level of site s{}: {}", site.id, size))
+}
+
+/// sites that can still be opened at a larger level
+fn openable_site_indexes(size_of_site : &[u8], site_array : &[Site], fixed_levels : Option<&[u8]>) -> Vec<usize> {
+    size_of_site.iter().enumerate().zip(site_array).filter_map(|((idx,size), site)| {
+        if *size < if let Some(levels) = fixed_levels { levels[idx] } else { site.max_level() } {
+            Some(idx)
+        } else {
+            None
+        }
+    }).collect()
+}
+
 #[derive(PartialEq,Hash,Eq,Clone,Debug)]
 struct CutItem {
     site_index : usize,
@@ -138,14 +162,17 @@ impl CutItem {
     }
 }
 
-impl<'a> SolutionApproachVariable<'a> {
+impl SolutionApproachVariable {
 
     pub fn new(
         min_num_sites: usize,
         allowed_infeasible : usize,
-        sites: IndexMap<u8, Site>,
-        scenario_vehicle_sets : &'a [ &'a [Vehicle<'a>]],
+        sites: IndexMap<SiteId, Site>,
+        scenario_vehicle_sets : &[&[Vehicle]],
         gurobi_threads : i32,
+        pricing_workers : usize,
+        scenario_workers : usize,
+        stabilization : Stabilization,
         sort_many_columns_first : bool,
         quorum_accept_percent : u8,
         benevolent_accept_percent : u8,
@@ -153,9 +180,11 @@ impl<'a> SolutionApproachVariable<'a> {
         activate_all : bool,
         iis_activate : bool,
         total_num_vehicles : i64,
-        env : &'a Env,
-        env_integer : &'a Env
-    ) -> Self {
+        horizon : Horizon,
+        seed : u64,
+        env : &SolverEnv,
+        env_integer : &SolverEnv
+    ) -> Result<Self, SolverError> {
 
 
 
@@ -164,26 +193,30 @@ impl<'a> SolutionApproachVariable<'a> {
         let site_conf_factory = SiteConfFactory {
             num_sites: site_array.len()
         };
+        let best_pattern = site_conf_factory.largest(&site_array);
 
 
 
 
 
-        // create one brancher per vehicle_set item in scenario manager
+        // create one brancher per vehicle_set item in scenario manager, each with its own
+        // environments as the scenario workers solve them concurrently
         let mut scenario_manager = ScenarioManager::new(
             scenario_vehicle_sets.iter().map(|v| {
-                Brancher::new(site_array.clone(),
+                Ok(Brancher::new(site_array.clone(),
                               v.to_vec(),
                               site_conf_factory.empty(),
-                              &env,
-                              &env_integer,
+                              env.fork()?,
+                              env_integer.fork()?,
                               allowed_infeasible,
                               true,
                               Arc::new(AtomicBool::new(false)),
-                              PatternPool::new(v.len())
-
-                )
-            }).collect()
+                              PatternPool::new(v.len()),
+                              horizon,
+                              pricing_workers,
+                              stabilization
+                ))
+            }).collect::<Result<Vec<Brancher>, SolverError>>()?
         );
         scenario_manager.new_generation();
 
@@ -192,33 +225,44 @@ impl<'a> SolutionApproachVariable<'a> {
 
 
 
-        SolutionApproachVariable {
+        Ok(SolutionApproachVariable {
             min_num_sites,
             site_array,
-            best_pattern : site_conf_factory.full(MAX_FIXED_SIZE),
+            best_pattern,
             site_conf_factory : site_conf_factory,
             best_cost : u32::MAX,
             allowed_infeasible,
             best_brancher_pattern : None,
-            rng : StdRng::seed_from_u64(12345),
+            rng : StdRng::seed_from_u64(seed),
             tested_cuts : HashSet::new(),
             scenario_manager,
             should_stop,
             quorum_accept_percent,
             benevolent_accept_percent,
             gurobi_threads,
+            pricing_workers,
+            record_iterations : false,
+            scenario_workers : scenario_workers.max(1),
+            stabilization,
+            backend : env.backend(),
             max_activate_per_generation,
             activate_all,
             iis_activate,
-            total_num_vehicles
-        }
+            total_num_vehicles,
+            horizon,
+            seed,
+            brancher_scenarios : (0..scenario_vehicle_sets.len()).collect(),
+            last_evaluation : Vec::new(),
+            lower_bound : 0.0,
+            statistics : SolveStatistics::default()
+        })
     }
 
-    fn load_cuts(&mut self, cut_file_input: &str, model: &mut Model,
-                 mut site_open_vars: &IndexMap<usize,Var>, active_cuts: &mut Vec<(Cut, Constr)>, num_cuts: &mut usize, fixed_size_level : Option<u8>) {
+    fn load_cuts(&mut self, cut_file_input: Option<&str>, model: &mut dyn LpModel,
+                 site_open_vars: &IndexMap<usize,Var>, active_cuts: &mut Vec<(Cut, Constr)>, num_cuts: &mut usize, fixed_levels : Option<&[u8]>) {
         {
             // copy cuts from first level
-            if cut_file_input != "/dev/null" {
+            if let Some(cut_file_input) = cut_file_input {
                 let file = File::open(cut_file_input).unwrap();
                 let mut lines = io::BufReader::new(file).lines();
                 // first line is bound
@@ -245,8 +289,8 @@ impl<'a> SolutionApproachVariable<'a> {
 
                             // if we are at a size level only take those that are exclusive for
                             // that level
-                            if let Some(size_level) = fixed_size_level {
-                                    if sites_in_cut.iter().any(|c| c.open_level != size_level) {
+                            if let Some(levels) = fixed_levels {
+                                    if sites_in_cut.iter().any(|c| c.open_level != levels[c.site_index]) {
                                         continue
                                     }
                             }
@@ -254,9 +298,8 @@ impl<'a> SolutionApproachVariable<'a> {
                             let cut = Cut::new(sites_in_cut);
 
                             let constr = model.add_constr(&format!("benderCut[{}]", num_cuts),
-                                                          c!(
-                                                          Expr::sum(cut.items.iter().map(|c| site_open_vars.get_index(c.site_index).unwrap().1)) >= 1
-                                                          )).unwrap();
+                                                          &cut.items.iter().map(|c| (*site_open_vars.get_index(c.site_index).unwrap().1, 1.0)).collect::<Vec<(Var, f64)>>(),
+                                                          Sense::Greater, 1.0);
 
                             active_cuts.push((cut.clone(), constr));
                             self.tested_cuts.insert(cut);
@@ -269,11 +312,15 @@ impl<'a> SolutionApproachVariable<'a> {
         }
     }
 
-    pub fn run(&mut self, should_stop: Arc<AtomicBool>, path_charge_process : &str, cut_file_output: &str, cut_file_input: &str,
-               do_low_level : bool,
-               do_low_high_swap : bool,
-               do_variable_sizing : bool
-    ) -> Simple {
+    pub fn run(&mut self, should_stop: Arc<AtomicBool>, settings : &RunSettings) -> Simple {
+        let RunSettings {
+            static_target_size,
+            do_low_high_swap,
+            do_variable_sizing,
+            charge_processes_output : path_charge_process,
+            cuts_output : cut_file_output,
+            cuts_input : cut_file_input,
+        } = *settings;
 
 
         let start_cutting_plane = Instant::now();
@@ -281,14 +328,13 @@ impl<'a> SolutionApproachVariable<'a> {
 
 
 
-        let mut env = Env::new("/tmp/gurobi_cutting_plane_variable.log").unwrap();
-
-        env.set(grb::param::LogToConsole, 0).unwrap();
-        env.set(grb::param::Threads, self.gurobi_threads).unwrap();
-        env.set(grb::param::Seed, 12345).unwrap();
-
-        #[cfg(not(feature = "column_generation_debug"))]
-            env.set(grb::param::OutputFlag, 0).unwrap();
+        let env = SolverEnv::new(self.backend, SolverParams {
+            threads : self.gurobi_threads,
+            seed : self.seed,
+            #[cfg(feature = "column_generation_debug")]
+            log_file : Some("/tmp/gurobi_cutting_plane_variable.log".to_string()),
+            ..SolverParams::default()
+        }).unwrap_or_else(|e| panic!("{}", e));
 
 
         let mut num_cuts : usize = 0;
@@ -301,23 +347,27 @@ impl<'a> SolutionApproachVariable<'a> {
 
 
         // create an empty model which associated with `env`:
-        let mut cutting_plane_master = Model::with_env("cutting_plane_master", &env).unwrap();
-        cutting_plane_master.set_attr(attr::ModelSense, grb::ModelSense::Minimize).unwrap();
+        let mut cutting_plane_master = env.model("cutting_plane_master");
 
-        let mut open_site_at_level_high: IndexMap<usize, Var> = IndexMap::default();
+        let mut open_site_at_static_level: IndexMap<usize, Var> = IndexMap::default();
         for site in &self.site_array {
-            open_site_at_level_high.insert(site.index,
-                                           cutting_plane_master.add_var(&format!("openSiteLevelHigh[{}]", site.index), Binary, (f64::from(site.cost_4)), 0.0, 1.0, []).unwrap()
+            open_site_at_static_level.insert(site.index,
+                                           cutting_plane_master.add_var(&format!("openSiteStatic[{}]", site.index), Binary, f64::from(conf_cost(site, site.max_level())), 0.0, 1.0, &[])
             );
         }
 
 
-        fn get_trueish_vars(model : &Model, vars : &IndexMap<usize,Var>) -> Vec<bool> {
-            model.get_obj_attr_batch(attr::X, vars.iter().map(|(_idx, var)| var).cloned().collect::<Vec<Var>>())
-                .unwrap().iter().map(|el| *el > CG_EPSILON).collect()
+        fn get_trueish_vars<K>(model : &dyn LpModel, vars : &IndexMap<K,Var>) -> Vec<bool> {
+            model.x(&vars.iter().map(|(_idx, var)| var).cloned().collect::<Vec<Var>>())
+                .iter().map(|el| *el > CG_EPSILON).collect()
         }
 
-        // always start at 4, then decrease to target_static_station size
+        // always start at the largest level, then decrease to target_static_station size
+        let size_levels : Vec<u8> = self.site_array.iter()
+            .flat_map(|site| site.levels.iter().map(|l| l.chargers))
+            .sorted().dedup().collect();
+        let lowest_level = size_levels[0];
+        let highest_level = size_levels[size_levels.len() - 1];
 
 
 
@@ -332,23 +382,23 @@ impl<'a> SolutionApproachVariable<'a> {
         // set up static loop based on input settings
         match (do_low_high_swap,do_variable_sizing){
             (false, false) => {
-                target_static_station_size =
-                    if do_low_level {SITE_LOW_LEVEL} else {SITE_HIGH_LEVEL};
+                target_static_station_size = static_target_size.unwrap_or(highest_level);
                 current_station_size = target_static_station_size;
 
             }, // if neither high or low swap, set sizing level to requested
             (true, false) => {
-                target_static_station_size = SITE_LOW_LEVEL;
-                current_station_size = SITE_HIGH_LEVEL;
+                target_static_station_size = static_target_size.unwrap_or(lowest_level);
+                current_station_size = highest_level;
             }, // if we do high low swap, target low lwevel
             (false, true) => {
-                target_static_station_size = SITE_HIGH_LEVEL;
-                current_station_size = SITE_HIGH_LEVEL;
-            }, // if we do variable sizing: target high level first, low level is dealt with later,
+                target_static_station_size = highest_level;
+                current_station_size = highest_level;
+            }, // if we do variable sizing: target high level first, lower levels are dealt with later,
             _ => unreachable!("Invalid combination")
         };
 
         println!("Target: {}, Start:{}", target_static_station_size, current_station_size);
+        assert!(size_levels.contains(&target_static_station_size), "No site has a level of size {}", target_static_station_size);
 
         if self.activate_all{
             for s in 0..self.scenario_manager.branchers.len() {
@@ -359,7 +409,8 @@ impl<'a> SolutionApproachVariable<'a> {
         }
 
         {
-            self.load_cuts(cut_file_input, &mut cutting_plane_master, &mut open_site_at_level_high, &mut active_cuts, &mut num_cuts, Some(current_station_size));
+            let static_levels = self.site_conf_factory.at_size(&self.site_array, current_station_size);
+            self.load_cuts(cut_file_input, cutting_plane_master.as_mut(), &mut open_site_at_static_level, &mut active_cuts, &mut num_cuts, Some(&static_levels));
         }
 
         while current_station_size >= target_static_station_size {
@@ -370,7 +421,9 @@ impl<'a> SolutionApproachVariable<'a> {
             // therefore not a feasible upper bound at the lower level
             // we are still dealing with fixed station sizes not variable
             // otherwise bound would work!
-            self.best_pattern = self.site_conf_factory.full(current_station_size);
+            // sites are opened at their largest level that fits the current station size
+            let static_levels = self.site_conf_factory.at_size(&self.site_array, current_station_size);
+            self.best_pattern = static_levels.clone();
             self.best_cost = u32::MAX;
             self.best_brancher_pattern = None;
             self.tested_cuts = HashSet::new();
@@ -380,13 +433,10 @@ impl<'a> SolutionApproachVariable<'a> {
 
             // update objective function contribution for closing stations based on current station size
             for site in &self.site_array {
-                let v: &Var = &open_site_at_level_high[site.index];
-                cutting_plane_master.set_obj_attr(grb::attr::Obj, v, f64::from(
-                    if current_station_size == SITE_LOW_LEVEL { site.cost_2}
-                         else if current_station_size == SITE_HIGH_LEVEL { site.cost_4}
-                         else { unreachable!()}
-                )
-                ).unwrap();
+                let v: Var = open_site_at_static_level[site.index];
+                cutting_plane_master.set_obj(v, f64::from(conf_cost(site, static_levels[site.index])));
+                // sites without a level of that size can not be opened
+                cutting_plane_master.set_ub(v, if static_levels[site.index] > 0 { 1.0 } else { 0.0 });
             }
 
             let mut last_pattern_cost = u32::MAX;
@@ -394,7 +444,7 @@ impl<'a> SolutionApproachVariable<'a> {
             'scenarioLoop: loop {
                 // reset the pattern stats, as now our brancher setup might look different!
                 self.best_cost = std::u32::MAX;
-                self.best_pattern = self.site_conf_factory.full(MAX_FIXED_SIZE);
+                self.best_pattern = self.site_conf_factory.largest(&self.site_array);
 
                 'patternLoop: loop {
                     scoped_tracepoint!(cut_loop);
@@ -403,21 +453,25 @@ impl<'a> SolutionApproachVariable<'a> {
                         break;
                     }
 
-                    {
+                    let status = {
                         scoped_tracepoint!(bd_master_optimize);
-                        cutting_plane_master.optimize().unwrap();
-                    }
+                        let status = cutting_plane_master.optimize();
+                        self.statistics.iterations += 1;
+                        status
+                    };
 
 
-                    if cutting_plane_master.status().unwrap() != Status::Optimal {
+                    if status != Status::Optimal {
                         panic!("{}", "Error in solving cutting plane master!");
                     }
+                    // every pattern cheaper than the master optimum is cut off, for the active scenarios
+                    self.lower_bound = cutting_plane_master.obj_bound();
 
 
-                    let opened_site_levels: Vec<bool> = get_trueish_vars(&cutting_plane_master, &open_site_at_level_high);
+                    let opened_site_levels: Vec<bool> = get_trueish_vars(cutting_plane_master.as_ref(), &open_site_at_static_level);
                     let mut current_pattern: SiteConf = self.site_conf_factory.empty();
-                    for (pattern_val, is_open) in current_pattern.iter_mut().zip(&opened_site_levels) {
-                        *pattern_val = if *is_open { current_station_size } else { 0 }
+                    for ((pattern_val, is_open), level) in current_pattern.iter_mut().zip(&opened_site_levels).zip(&static_levels) {
+                        *pattern_val = if *is_open { *level } else { 0 }
                     }
                     let pattern_cost = self.get_pattern_cost(&current_pattern);
 
@@ -430,11 +484,9 @@ impl<'a> SolutionApproachVariable<'a> {
 
 
                         let results = self.scenario_manager
-                            .get_active_branchers()
-                            .map(|(_idx, br)| {
-                                br.replace_site_sizes(current_pattern.clone());
-                                br.solve(false, false)
-                            });
+                            .solve_active_until_quorum(&current_pattern, quorum_required, self.scenario_workers)
+                            .into_iter()
+                            .map(|(_idx, result)| result);
 
                         let mut oracle_ok = 0;
                         let mut oracle_denied = 0;
@@ -468,7 +520,7 @@ impl<'a> SolutionApproachVariable<'a> {
                     }
 
 
-                    if opened_site_levels.iter().all(|f| *f) {
+                    if opened_site_levels.iter().zip(&static_levels).all(|(f, level)| *f || *level == 0) {
                         panic!("{}", "Opened all sites!");
                     }
 
@@ -477,14 +529,14 @@ impl<'a> SolutionApproachVariable<'a> {
                     println!("BEND|{:?}|{}|{}|cols:{}|{}|{:?}", current_pattern, pattern_cost, self.best_cost, self.scenario_manager.branchers.iter().map(|b| b.get_num_colums()).sum::<usize>(), start_cutting_plane.elapsed().as_secs(), false);
 
 
-                    let delta_pattern_cost = pattern_cost - last_pattern_cost;
+                    let delta_pattern_cost = pattern_cost.wrapping_sub(last_pattern_cost);
                     last_pattern_cost = pattern_cost;
 
                     // since we are infeasible try to generate cuts
                     let mut potential_cuts = self.improve_cuts(&current_pattern,
-                                                               Duration::from_secs(cutting_plane_master.get_attr(grb::attr::Runtime).unwrap().round() as u64),
+                                                               Duration::from_secs(cutting_plane_master.runtime().round() as u64),
                                                                delta_pattern_cost,
-                                                               Some(current_station_size)
+                                                               Some(&static_levels)
                     );
 
 
@@ -525,12 +577,9 @@ impl<'a> SolutionApproachVariable<'a> {
 */
 
                         let constr = cutting_plane_master.add_constr(&format!("benderCut[{}]", num_cuts),
-                                                                     c!(
-                                                           Expr::sum(
-                                                                   cut.items.iter().map(|item| open_site_at_level_high.get_index(item.site_index).unwrap().1))
-                                                              >= 1
-                                                               )
-                        ).unwrap();
+                                                                     &cut.items.iter().map(|item| (*open_site_at_static_level.get_index(item.site_index).unwrap().1, 1.0)).collect::<Vec<(Var, f64)>>(),
+                                                                     Sense::Greater, 1.0
+                        );
                         active_cuts.push((cut.clone(), constr));
                     };
                 }
@@ -539,8 +588,11 @@ impl<'a> SolutionApproachVariable<'a> {
                     break 'scenarioLoop;
                 }
             }
-            // decrease station size by 2, only usefull if we did start with value != target size
-            current_station_size -= 2;
+            // decrease station size to the next smaller level, only usefull if we did start with value != target size
+            match size_levels.iter().rev().find(|level| **level < current_station_size) {
+                Some(level) => current_station_size = *level,
+                None => break
+            }
         }
 
 
@@ -551,31 +603,37 @@ impl<'a> SolutionApproachVariable<'a> {
             // if we are variable, then proceed with variable sizing model,
             // but we keep the cuts from high bit
 
-            let mut open_site_at_level_low: IndexMap<usize, Var> = IndexMap::default();
+            let mut open_site_at_level: IndexMap<(usize, u8), Var> = IndexMap::default();
             for site in &self.site_array {
 
-                // for all cuts that require a site to be opened at the old (high) level,
-                // there is now the possibility to open the site at the low level instead
+                // for all cuts that require a site to be opened at the old (largest) level,
+                // there is now the possibility to open the site at a smaller level instead
                 // we collect the constraints for those cuts and include them in the col
-                // vector of the variable with a coeef of 1
+                // vector of the variables with a coeef of 1
                 let high_level_cuts_where_site_is_included: Vec<(Constr, f64)> = active_cuts.iter().filter_map(|(cut, constr)| {
                     let cut: &Cut = cut;
                     for i in &cut.items {
                         if i.site_index == site.index {
-                            return Some((constr.clone(), 1.0))
+                            return Some((*constr, 1.0))
                         }
                     }
 
                     return None
                 }).collect();
 
-                open_site_at_level_low.insert(site.index,
-                                              cutting_plane_master.add_var(&format!("openSiteLevelLow\
-                                 [{}]", site.index), Binary, f64::from(site.cost_2), 0.0, 1.0, high_level_cuts_where_site_is_included).unwrap()
-                );
+                let mut site_level_vars: Vec<Var> = Vec::with_capacity(site.levels.len());
+                for level in &site.levels {
+                    let var = if level.chargers == site.max_level() {
+                        open_site_at_static_level[site.index]
+                    } else {
+                        cutting_plane_master.add_var(&format!("openSiteLevel[{},{}]", site.index, level.chargers), Binary, f64::from(level.cost), 0.0, 1.0, &high_level_cuts_where_site_is_included)
+                    };
+                    site_level_vars.push(var);
+                    open_site_at_level.insert((site.index, level.chargers), var);
+                }
 
-                // add convexity constraint: Cant open at both levels.
-                cutting_plane_master.add_constr(&format!("closeSizeConv[{}]", site.index), c!(open_site_at_level_low[site.index] + open_site_at_level_high[site.index] <= 1.0)).unwrap();
+                // add convexity constraint: Cant open at multiple levels.
+                cutting_plane_master.add_constr(&format!("closeSizeConv[{}]", site.index), &site_level_vars.iter().map(|var| (*var, 1.0)).collect::<Vec<(Var, f64)>>(), Sense::Less, 1.0);
             }
 
 
@@ -583,7 +641,7 @@ impl<'a> SolutionApproachVariable<'a> {
 
             'scenarioLoop: loop {
                 self.best_cost = std::u32::MAX;
-                self.best_pattern = self.site_conf_factory.full(MAX_FIXED_SIZE);
+                self.best_pattern = self.site_conf_factory.largest(&self.site_array);
 
                 'patternLoop: loop {
                     scoped_tracepoint!(_cut_loop);
@@ -592,32 +650,30 @@ impl<'a> SolutionApproachVariable<'a> {
                         break;
                     }
 
-                    {
+                    let status = {
                         scoped_tracepoint!(_bd_master_optimize);
-                        cutting_plane_master.optimize().unwrap();
-                    }
+                        let status = cutting_plane_master.optimize();
+                        self.statistics.iterations += 1;
+                        status
+                    };
 
 
-                    if cutting_plane_master.status().unwrap() != Status::Optimal {
-                        cutting_plane_master.write("/tmp/lp.lp").unwrap();
-                        panic!("{}: {:?}", "Error in solving cutting plane master!", cutting_plane_master.status().unwrap());
+                    if status != Status::Optimal {
+                        panic!("{}: {:?}", "Error in solving cutting plane master!", status);
                     }
+                    self.lower_bound = cutting_plane_master.obj_bound();
 
 
-                    let result_open_high: Vec<bool> = get_trueish_vars(&cutting_plane_master, &open_site_at_level_high);
-                    let result_open_low: Vec<bool> = get_trueish_vars(&cutting_plane_master, &open_site_at_level_low);
+                    let result_open: Vec<bool> = get_trueish_vars(cutting_plane_master.as_ref(), &open_site_at_level);
 
-                    let size_of_site: Vec<u8> = self.site_array.iter().map(|site| {
-                        // must not both be true
-                        assert!(!(result_open_high[site.index] && result_open_low[site.index]));
-                        if result_open_high[site.index] {
-                            SITE_HIGH_LEVEL
-                        } else if result_open_low[site.index] {
-                            SITE_LOW_LEVEL
-                        } else {
-                            0
+                    let mut size_of_site: Vec<u8> = vec![0; self.site_array.len()];
+                    for ((site_index, level), is_open) in open_site_at_level.keys().zip(&result_open) {
+                        if *is_open {
+                            // must not be open at multiple levels
+                            assert_eq!(size_of_site[*site_index], 0);
+                            size_of_site[*site_index] = *level;
                         }
-                    }).collect();
+                    }
 
 
                     // create pattern
@@ -628,7 +684,7 @@ impl<'a> SolutionApproachVariable<'a> {
                     // calculate pattern cost
 
                     let pattern_cost = self.get_pattern_cost(&current_pattern);
-                    let delta_pattern_cost = pattern_cost - last_pattern_cost;
+                    let delta_pattern_cost = pattern_cost.wrapping_sub(last_pattern_cost);
                     last_pattern_cost = pattern_cost;
 
                     {
@@ -638,11 +694,9 @@ impl<'a> SolutionApproachVariable<'a> {
                         let quorum_required = (num_active as f32 * (self.quorum_accept_percent as f32 / 100.0)).round() as usize;
 
                         let results = self.scenario_manager
-                            .get_active_branchers()
-                            .map(|(_idx, br)| {
-                                br.replace_site_sizes(current_pattern.clone());
-                                br.solve(false, false)
-                            });
+                            .solve_active_until_quorum(&current_pattern, quorum_required, self.scenario_workers)
+                            .into_iter()
+                            .map(|(_idx, result)| result);
 
                         let mut oracle_ok = 0;
                         let mut oracle_denied = 0;
@@ -670,8 +724,7 @@ impl<'a> SolutionApproachVariable<'a> {
                         }
                     }
 
-                    let all_open = (size_of_site.len() * SITE_HIGH_LEVEL as usize) as u16;
-                    if size_of_site.iter().fold(0_u16, |a, b| a + (*b as u16)) == all_open {
+                    if size_of_site.iter().zip(&self.site_array).all(|(size, site)| *size == site.max_level()) {
                         panic!("{}", "Opened all sites!");
                     }
 
@@ -680,7 +733,7 @@ impl<'a> SolutionApproachVariable<'a> {
 
 
                     // since we are infeasible try to generate cuts
-                    let mut potential_cuts = self.improve_cuts(&size_of_site, Duration::from_secs(cutting_plane_master.get_attr(attr::Runtime).unwrap().round() as u64), delta_pattern_cost, None);
+                    let mut potential_cuts = self.improve_cuts(&size_of_site, Duration::from_secs(cutting_plane_master.runtime().round() as u64), delta_pattern_cost, None);
 
                     potential_cuts.sort_unstable_by(|a, b| a.items.len().cmp(&b.items.len()));
 
@@ -731,10 +784,8 @@ impl<'a> SolutionApproachVariable<'a> {
 
                         let mut open_set: Vec<Var> = cut.items.iter().filter_map(|ci| {
                             match ci.open_level {
-                                SITE_HIGH_LEVEL => Some(open_site_at_level_high[&ci.site_index].clone()),
-                                SITE_LOW_LEVEL => Some(open_site_at_level_low[&ci.site_index].clone()),
                                 0 => None,
-                                _ => unreachable!()
+                                level => Some(open_site_at_level[&(ci.site_index, level)])
                             }
                         }
                         ).collect();
@@ -746,8 +797,8 @@ impl<'a> SolutionApproachVariable<'a> {
 
 
                         let constr = cutting_plane_master.add_constr(&format!("planeCut[{}]", num_cuts),
-                                                                     c!( Expr::sum(open_set.iter())
-                                                            >= 1.0)).unwrap();
+                                                                     &open_set.iter().map(|var| (*var, 1.0)).collect::<Vec<(Var, f64)>>(),
+                                                                     Sense::Greater, 1.0);
 
 
                         active_cuts.push((cut.clone(),constr));
@@ -778,7 +829,7 @@ impl<'a> SolutionApproachVariable<'a> {
 
         // write charge processes to file
         {
-            if path_charge_process != "/dev/null" {
+            if let Some(path_charge_process) = path_charge_process {
                 let write_file = File::create(path_charge_process).unwrap();
                 let mut writer = BufWriter::new(&write_file);
 
@@ -792,7 +843,7 @@ impl<'a> SolutionApproachVariable<'a> {
 
         // write cuts to file
         {
-            if cut_file_output != "/dev/null" {
+            if let Some(cut_file_output) = cut_file_output {
                 let write_file = File::create(cut_file_output).unwrap();
                 let mut writer = BufWriter::new(&write_file);
                 write!(&mut writer, "{}\n", self.best_cost).unwrap();
@@ -803,6 +854,12 @@ impl<'a> SolutionApproachVariable<'a> {
         }
 
         println!("Total Number of Columns: {}", self.scenario_manager.branchers.iter().map(|b| b.get_num_colums()).sum::<usize>());
+        self.statistics.cuts = num_cuts;
+        self.statistics.columns = self.scenario_manager.branchers.iter().map(|b| b.get_num_colums()).sum::<usize>();
+        self.statistics.runtime_seconds = start_cutting_plane.elapsed().as_secs_f64();
+        let pricing_rounds = self.scenario_manager.branchers.iter().map(|b| b.get_pricing_rounds());
+        (self.statistics.pricing_rounds, self.statistics.mis_pricings) = pricing_rounds
+            .fold((0, 0), |(rounds, mis_pricings), (r, m)| (rounds + r, mis_pricings + m));
 
 
         Simple {
@@ -814,14 +871,103 @@ impl<'a> SolutionApproachVariable<'a> {
         }
     }
 
+    /// Keeps the pricing rounds of the column generation of all branchers for `cg_iterations`.
+    pub fn record_iterations(&mut self, record : bool) {
+        self.record_iterations = record;
+        for brancher in &mut self.scenario_manager.branchers {
+            brancher.record_iterations(record);
+        }
+    }
+
+    /// Pricing rounds of the column generation of all branchers, with the input scenario they
+    /// belong to, if they are recorded.
+    pub fn cg_iterations(&self) -> Vec<ColumnGenerationIteration> {
+        self.scenario_manager.branchers.iter().enumerate().flat_map(|(idx, brancher)| {
+            brancher.get_cg_iterations().iter().map(move |iteration| ColumnGenerationIteration {
+                scenario : self.brancher_scenarios[idx],
+                ..iteration.clone()
+            })
+        }).collect()
+    }
+
+    /// Describes a solution returned by `run`, with the outcome of the last evaluation of its sites
+    /// in every input scenario. `scenario_names` are the names of the input scenarios in order.
+    pub fn report(&self, solution : &Simple, scenario_names : &[String]) -> Result<SolutionReport, PlanningError> {
+        let open_sites = solution.sites_open.iter()
+            .filter(|(size, _)| *size > 0)
+            .map(|&(size, index)| {
+                let site = &self.site_array[index];
+                let cost = site.level_cost(size).ok_or_else(|| PlanningError::Invalid(
+                    format!("site s{} has no level with {} chargers", site.id, size)))?;
+                Ok(OpenSite { id : site.id, chargers : size, cost })
+            }).collect::<Result<_, PlanningError>>()?;
+
+        let scenarios : Vec<ScenarioResult> = scenario_names.iter().enumerate().map(|(scenario, name)| {
+            let vehicles = self.scenario_manager.branchers[scenario].get_vehicles();
+            let (feasible, infeasible_vehicles, charge_plans) = match self.last_evaluation.iter().find(|(bidx, ..)| *bidx == scenario) {
+                Some((_, feasible, infeasible_vehicles, pattern)) => {
+                    let charge_plans = pattern.iter().flatten().map(|(vehicle, visits)| {
+                        let vehicle = &vehicles[vehicle.index()];
+                        ChargePlan {
+                            vehicle : vehicle.id,
+                            original_id : vehicle.original_id,
+                            visits : visits.iter().map(|(segment, site, period)| ChargeVisit {
+                                segment : segment.index(),
+                                site : self.site_array[site.index()].id,
+                                period : *period
+                            }).collect()
+                        }
+                    }).collect();
+                    (*feasible, *infeasible_vehicles, charge_plans)
+                },
+                None => (false, 0, Vec::new())
+            };
+
+            // a scenario is also active through the virtual scenario of its infeasible vehicles
+            let active = self.brancher_scenarios.iter().zip(&self.scenario_manager.active_sets)
+                .any(|(source, active)| *source == scenario && *active);
+
+            ScenarioResult { name : name.clone(), active, feasible, infeasible_vehicles, charge_plans }
+        }).collect();
+
+        Ok(SolutionReport {
+            cost : solution.cost,
+            lower_bound : self.lower_bound,
+            gap : relative_gap(solution.cost as f64, self.lower_bound),
+            open_sites,
+            active_scenarios : scenarios.iter().filter(|scenario| scenario.active).count(),
+            statistics : self.statistics.clone(),
+            scenarios
+        })
+    }
+
+    /// Timelines with the state of charge of every vehicle for the charge plans of a report.
+    pub fn timelines(&self, report : &SolutionReport) -> Result<Vec<VehicleTimeline>, PlanningError> {
+        let mut timelines = Vec::new();
+        for (scenario, result) in report.scenarios.iter().enumerate() {
+            let vehicles = self.scenario_manager.branchers[scenario].get_vehicles();
+            for plan in &result.charge_plans {
+                let vehicle = vehicles.iter().find(|vehicle| vehicle.id == plan.vehicle).ok_or_else(|| PlanningError::Invalid(
+                    format!("scenario {} has no vehicle {}", result.name, plan.vehicle)))?;
+                let timeline = VehicleTimeline::new(&result.name, vehicle, &plan.visits, &self.horizon)
+                    .map_err(|reason| PlanningError::Invalid(format!("scenario {}: {}", result.name, reason)))?;
+                timelines.push(timeline);
+            }
+        }
+        Ok(timelines)
+    }
+
     fn evaluate_all_scenarios_and_update_active(&mut self, should_stop: &Arc<AtomicBool>)  -> bool{
         let mut infeasible_scenarios = Vec::new();
-
-        for (bidx, b) in self.scenario_manager.get_all_branchers() {
-            b.replace_site_sizes(self.best_pattern.clone());
-            match b.solve(false, true) {
-                Ok(_) => {
-                    println!("Feasible for {:?}", &self.best_pattern)
+        let mut evaluation = Vec::with_capacity(self.scenario_manager.branchers.len());
+
+        let results = self.scenario_manager.solve_all(&self.best_pattern, true, self.scenario_workers);
+        for (bidx, result) in results {
+            let b = &self.scenario_manager.branchers[bidx];
+            match result {
+                Ok((_, pattern)) => {
+                    println!("Feasible for {:?}", &self.best_pattern);
+                    evaluation.push((bidx, true, 0, Some(pattern)));
                 },
 
                 Err(SolveError::VehiclesInfeasible(infs)) => {
@@ -848,9 +994,11 @@ impl<'a> SolutionApproachVariable<'a> {
 
                     if count_infs + external_infeasibility_penalty > benevolent_accept_limit_count {
                         println!("Infeasible count = {} (+{})", infs.len(), external_infeasibility_penalty);
+                        evaluation.push((bidx, false, count_infs, None));
                         infeasible_scenarios.push((infs, b.get_vehicles().len(), bidx));
                     } else {
                         println!("Benevolent Feasible ( inf count = {})", infs.len());
+                        evaluation.push((bidx, true, count_infs, None));
                     }
                 },
                 Err(SolveError::Generic(msg)) => panic!("{}", msg),
@@ -859,6 +1007,7 @@ impl<'a> SolutionApproachVariable<'a> {
                 Err(SolveError::NoQuickResult) => panic!("{}", "InvalidError")
             }
         }
+        self.last_evaluation = evaluation;
 
         let num_active = self.scenario_manager.num_active();
         let quorum_required = (num_active as f32 * (self.quorum_accept_percent as f32 / 100.0)).round() as usize;
@@ -871,6 +1020,7 @@ impl<'a> SolutionApproachVariable<'a> {
         infeasible_scenarios.sort_by_key(|(inf, _, _)| inf.len());
 
         self.scenario_manager.new_generation();
+        self.statistics.scenario_generations += 1;
 
         for (inf, num_vehicles, idx) in infeasible_scenarios.into_iter().take(self.max_activate_per_generation) {
             let num_vehicles_base_for_benevolent = if self.total_num_vehicles > 0 {
@@ -923,21 +1073,26 @@ impl<'a> SolutionApproachVariable<'a> {
 
 
                 // then we must map
-                let new_brancher = Brancher::new(
+                let mut new_brancher = Brancher::new(
                     self.site_array.clone(),
                     brancher_vehicles,
                     self.site_conf_factory.empty(),
-                    self.scenario_manager.branchers[0].env,
-                    self.scenario_manager.branchers[0].env_integer,
+                    self.scenario_manager.branchers[0].env.fork().unwrap_or_else(|e| panic!("{}", e)),
+                    self.scenario_manager.branchers[0].env_integer.fork().unwrap_or_else(|e| panic!("{}", e)),
                     self.allowed_infeasible,
                     true,
                     should_stop.clone(),
-                    PatternPool::new_with_store(columns)
+                    PatternPool::new_with_store(columns),
+                    self.horizon,
+                    self.pricing_workers,
+                    self.stabilization
                 );
+                new_brancher.record_iterations(self.record_iterations);
 
 
                 println!("Activated {} with {} of {} vehicles", idx, new_brancher.get_vehicles().len(),from_vehicles.len());
                 self.scenario_manager.add_brancher_and_activate(new_brancher);
+                self.brancher_scenarios.push(self.brancher_scenarios[idx]);
 
 
 
@@ -953,17 +1108,7 @@ impl<'a> SolutionApproachVariable<'a> {
 
     fn get_pattern_cost(&self, pattern : &SiteConf) -> u32 {
         pattern.iter().zip(self.site_array.iter()).map(|(&size,site)| {
-            if size == 0 {
-                0_u32
-            } else {
-                u32::from(
-
-                    if size == SITE_LOW_LEVEL { site.cost_2}
-                    else if size == SITE_HIGH_LEVEL { site.cost_4}
-                    else { unreachable!()}
-
-                )
-            }
+            conf_cost(site, size)
         }).sum()
     }
 
@@ -995,11 +1140,9 @@ impl<'a> SolutionApproachVariable<'a> {
 
 
         let results = self.scenario_manager
-            .get_active_branchers()
-            .map(|(_idx,br)| {
-                br.replace_site_sizes(current_pattern.clone());
-                br.solve(false, false)
-            });
+            .solve_active_until_quorum(&current_pattern, quorum_required, self.scenario_workers)
+            .into_iter()
+            .map(|(_idx, result)| result);
 
         let mut oracle_ok  = 0;
         let mut oracle_denied = 0;
@@ -1028,15 +1171,7 @@ impl<'a> SolutionApproachVariable<'a> {
 
             if oracle_ok >= quorum_required {
                 let pattern_cost: u32 = current_pattern.iter().zip(self.site_array.iter()).map(|(&size, site)| {
-                    if size == 0 {
-                        0_u32
-                    } else {
-                        u32::from(
-                            if size == SITE_LOW_LEVEL { site.cost_2}
-                            else if size == SITE_HIGH_LEVEL { site.cost_4}
-                            else { unreachable!()}
-                        )
-                    }
+                    conf_cost(site, size)
                 }).sum();
 
 
@@ -1068,10 +1203,10 @@ impl<'a> SolutionApproachVariable<'a> {
                     size_sizes : &[u8],
                     runtime_cutting_plane_master: Duration,
                     delta_pattern_cost : u32,
-                    has_fixed_level : Option<u8>
+                    has_fixed_level : Option<&[u8]>
     ) -> Vec<Cut> {
 
-        let original_cut = Cut::forbid_site_size_array(size_sizes, has_fixed_level);
+        let original_cut = Cut::forbid_site_size_array(size_sizes, &self.site_array, has_fixed_level);
 
 
 
@@ -1107,13 +1242,7 @@ impl<'a> SolutionApproachVariable<'a> {
                 let mut working_size_set: Vec<u8> = Vec::from(size_sizes.clone());
 
 
-                let mut indexes_still_openable : Vec<usize> = working_size_set.iter().enumerate().filter_map(|(idx,size)| {
-                    if (*size < if let Some(to_level) = has_fixed_level { to_level} else { SITE_HIGH_LEVEL  } ) {
-                        Some(idx)
-                    } else {
-                        None
-                    }
-                }).collect();
+                let mut indexes_still_openable : Vec<usize> = openable_site_indexes(&working_size_set, &self.site_array, has_fixed_level);
 
                 if indexes_still_openable.is_empty() {
                     break
@@ -1148,34 +1277,25 @@ impl<'a> SolutionApproachVariable<'a> {
                             // if we are still in fixed size mode,
                             // we just flip from 0 to the size level
                             // no other move will be suggested
-                            if let Some(to_level) = has_fixed_level {
+                            if let Some(levels) = has_fixed_level {
+                                let to_level = levels[site_idx];
                                 *size = match *size {
                                     0 => to_level,
                                     _ => unreachable!("size was {} not fixed {}", size, to_level)
                                 }
                             }  else {
-                                // otherwise we will apply the increase from 0 to low, low to high
-                                *size = match *size {
-                                    0 =>  SITE_LOW_LEVEL,
-                                    SITE_LOW_LEVEL => SITE_HIGH_LEVEL,
-                                    _ => unreachable!()
-                                }
+                                // otherwise we will apply the increase to the next larger level
+                                *size = self.site_array[site_idx].next_level(*size).unwrap();
                             }
                         }
                     }
 
 
 
-                    indexes_still_openable= working_size_set.iter().enumerate().filter_map(|(idx,size)| {
-                        if (*size < if let Some(to_level) = has_fixed_level { to_level} else { SITE_HIGH_LEVEL  } ) {
-                            Some(idx)
-                        } else {
-                            None
-                        }
-                    }).collect();
+                    indexes_still_openable = openable_site_indexes(&working_size_set, &self.site_array, has_fixed_level);
 
 
-                    let cut_perspective = Cut::forbid_site_size_array(&working_size_set, has_fixed_level);
+                    let cut_perspective = Cut::forbid_site_size_array(&working_size_set, &self.site_array, has_fixed_level);
 
 
 
@@ -16,6 +16,10 @@
 #![allow(unreachable_code)]
 
 pub mod fixed_size;
+pub mod lp;
+pub mod planner;
+pub use planner::{Instance, Plan, PlannerConfig, PlanningError, SiteSizing};
+pub use stabilization::Stabilization;
 
 mod pattern_pool;
 mod dag_builder;
@@ -24,12 +28,15 @@ mod branching_filter;
 #[cfg(feature = "perf_statistics")]
 mod metrics;
 mod rcsp;
+mod pricing;
+mod stabilization;
 
 
 extern crate shared;
 extern crate typed_arena;
 extern crate ndarray;
 extern crate petgraph;
+#[cfg(feature = "gurobi")]
 extern crate grb;
 extern crate chrono;
 extern crate rand;
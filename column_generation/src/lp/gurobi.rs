@@ -0,0 +1,211 @@
+use std::mem::ManuallyDrop;
+use std::sync::{Arc, Mutex};
+
+use grb::constr::IneqExpr;
+use grb::expr::LinExpr;
+use grb::{attr, param, ConstrSense, Env, Expr, Model};
+
+use shared::CustomHashMap;
+
+use crate::fixed_size::gurobi_seed;
+use crate::lp::{Constr, LpModel, Sense, SolverError, SolverParams, Status, Var, VarType};
+
+
+/// Gurobi environment shared by the models created from a `SolverEnv`.
+///
+/// Gurobi environments are not thread safe, an environment and its models are used by one thread
+/// at a time, see `SolverEnv`. grb ties every model to its environment through a reference count
+/// that is not atomic, creating, copying and dropping models additionally happens under the lock.
+pub(super) struct SharedEnv(Mutex<Env>);
+
+
+pub(super) fn env(params : &SolverParams) -> Result<Arc<SharedEnv>, SolverError> {
+    let error = |e : grb::Error| SolverError::Backend(e.to_string());
+    let mut env = Env::new(params.log_file.as_deref().unwrap_or("")).map_err(error)?;
+    env.set(param::Threads, params.threads).map_err(error)?;
+    env.set(param::Seed, gurobi_seed(params.seed)).map_err(error)?;
+    env.set(param::LogToConsole, i32::from(params.log_to_console)).map_err(error)?;
+    if params.log_file.is_none() && !params.log_to_console {
+        env.set(param::OutputFlag, 0).map_err(error)?;
+    }
+    if let Some(time_limit) = params.time_limit {
+        env.set(param::TimeLimit, time_limit).map_err(error)?;
+    }
+    if params.barrier {
+        // 2= barrier; test with concurrent has shown that
+        // this usually wins!
+        env.set(param::Method, 2).map_err(error)?;
+    }
+    Ok(Arc::new(SharedEnv(Mutex::new(env))))
+}
+
+
+pub struct GurobiModel {
+    // dropped under the lock of the environment
+    model : ManuallyDrop<Model>,
+    env : Arc<SharedEnv>,
+    vars : Vec<Option<grb::Var>>,
+    constrs : Vec<Option<grb::Constr>>,
+    // objects added since the last update have no index yet and cannot be removed
+    pending : bool,
+}
+
+// SAFETY: the only state shared with other models is the environment, which with all its models
+// is used by one thread at a time, see `SolverEnv`
+unsafe impl Send for GurobiModel {}
+
+impl GurobiModel {
+    pub(super) fn new(name : &str, env : &Arc<SharedEnv>) -> Self {
+        let model = Model::with_env(name, &*env.0.lock().unwrap()).unwrap();
+        GurobiModel {
+            model : ManuallyDrop::new(model),
+            env : env.clone(),
+            vars : Vec::new(),
+            constrs : Vec::new(),
+            pending : false,
+        }
+    }
+
+    fn var(&self, var : &Var) -> grb::Var {
+        self.vars[var.0].expect("Variable was removed")
+    }
+
+    fn constr(&self, constr : &Constr) -> grb::Constr {
+        self.constrs[constr.0].expect("Constraint was removed")
+    }
+
+    fn update_pending(&mut self) {
+        if self.pending {
+            self.model.update().unwrap();
+            self.pending = false;
+        }
+    }
+}
+
+impl LpModel for GurobiModel {
+    fn add_var(&mut self, name : &str, vtype : VarType, obj : f64, lb : f64, ub : f64, column : &[(Constr, f64)]) -> Var {
+        let vtype = match vtype {
+            VarType::Continuous => grb::VarType::Continuous,
+            VarType::Binary => grb::VarType::Binary,
+            VarType::Integer => grb::VarType::Integer,
+        };
+        let column : Vec<(grb::Constr, f64)> = column.iter().map(|(constr, coeff)| (self.constr(constr), *coeff)).collect();
+        let var = self.model.add_var(name, vtype, obj, lb, ub, column).unwrap();
+        self.vars.push(Some(var));
+        self.pending = true;
+        Var(self.vars.len() - 1)
+    }
+
+    fn add_constr(&mut self, name : &str, row : &[(Var, f64)], sense : Sense, rhs : f64) -> Constr {
+        let mut lhs = LinExpr::new();
+        for (var, coeff) in row {
+            lhs.add_term(*coeff, self.var(var));
+        }
+        let constr = self.model.add_constr(name, IneqExpr {
+            lhs : Expr::from(lhs),
+            sense : match sense {
+                Sense::Less => ConstrSense::Less,
+                Sense::Greater => ConstrSense::Greater,
+                Sense::Equal => ConstrSense::Equal,
+            },
+            rhs : Expr::Constant(rhs),
+        }).unwrap();
+        self.constrs.push(Some(constr));
+        self.pending = true;
+        Constr(self.constrs.len() - 1)
+    }
+
+    fn remove_var(&mut self, var : Var) {
+        self.update_pending();
+        let removed = self.var(&var);
+        self.model.remove(removed).unwrap();
+        self.vars[var.0] = None;
+    }
+
+    fn remove_constr(&mut self, constr : Constr) {
+        self.update_pending();
+        let removed = self.constr(&constr);
+        self.model.remove(removed).unwrap();
+        self.constrs[constr.0] = None;
+    }
+
+    fn set_rhs(&mut self, constr : Constr, rhs : f64) {
+        self.update_pending();
+        self.model.set_obj_attr(attr::RHS, &self.constr(&constr), rhs).unwrap();
+    }
+
+    fn set_obj(&mut self, var : Var, obj : f64) {
+        self.update_pending();
+        self.model.set_obj_attr(attr::Obj, &self.var(&var), obj).unwrap();
+    }
+
+    fn set_ub(&mut self, var : Var, ub : f64) {
+        self.update_pending();
+        self.model.set_obj_attr(attr::UB, &self.var(&var), ub).unwrap();
+    }
+
+    fn optimize(&mut self) -> Status {
+        self.model.update().unwrap();
+        self.pending = false;
+        self.model.optimize().unwrap();
+        match self.model.status().unwrap() {
+            grb::Status::Optimal => Status::Optimal,
+            grb::Status::Infeasible | grb::Status::InfOrUnbd => Status::Infeasible,
+            grb::Status::Unbounded => Status::Unbounded,
+            grb::Status::TimeLimit => Status::TimeLimit,
+            _ => Status::Other,
+        }
+    }
+
+    fn obj_val(&self) -> f64 {
+        self.model.get_attr(attr::ObjVal).unwrap()
+    }
+
+    fn obj_bound(&self) -> f64 {
+        // continuous models have no bound attribute
+        self.model.get_attr(attr::ObjBound).unwrap_or_else(|_| self.obj_val())
+    }
+
+    fn runtime(&self) -> f64 {
+        self.model.get_attr(attr::Runtime).unwrap()
+    }
+
+    fn x(&self, vars : &[Var]) -> Vec<f64> {
+        self.model.get_obj_attr_batch(attr::X, vars.iter().map(|var| self.var(var))).unwrap()
+    }
+
+    fn pi(&self, constrs : &[Constr]) -> Vec<f64> {
+        self.model.get_obj_attr_batch(attr::Pi, constrs.iter().map(|constr| self.constr(constr))).unwrap()
+    }
+
+    fn box_clone(&mut self) -> Box<dyn LpModel> {
+        // the clone lists variables and constraints in the same order as the original
+        self.model.update().unwrap();
+        let mut model = {
+            let _env = self.env.0.lock().unwrap();
+            self.model.try_clone().unwrap()
+        };
+        model.update().unwrap();
+
+        let var_map : CustomHashMap<grb::Var, grb::Var> = self.model.get_vars().unwrap().iter().cloned()
+            .zip(model.get_vars().unwrap().iter().cloned()).collect();
+        let constr_map : CustomHashMap<grb::Constr, grb::Constr> = self.model.get_constrs().unwrap().iter().cloned()
+            .zip(model.get_constrs().unwrap().iter().cloned()).collect();
+
+        Box::new(GurobiModel {
+            vars : self.vars.iter().map(|var| var.map(|var| var_map[&var])).collect(),
+            constrs : self.constrs.iter().map(|constr| constr.map(|constr| constr_map[&constr])).collect(),
+            model : ManuallyDrop::new(model),
+            env : self.env.clone(),
+            pending : false,
+        })
+    }
+}
+
+impl Drop for GurobiModel {
+    fn drop(&mut self) {
+        let _env = self.env.0.lock().unwrap();
+        // SAFETY: the model is not used after this
+        unsafe { ManuallyDrop::drop(&mut self.model) }
+    }
+}
@@ -0,0 +1,218 @@
+//! Solver independent interface to the linear and mixed integer programs of the column generation.
+//!
+//! Models are created from a `SolverEnv`, which fixes the backend and its parameters. Variables and
+//! constraints are referred to by handles that stay valid for clones of a model, removed handles are
+//! never reused.
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+mod simplex;
+#[cfg(feature = "gurobi")]
+mod gurobi;
+
+pub use simplex::SimplexModel;
+#[cfg(feature = "gurobi")]
+pub use gurobi::GurobiModel;
+
+pub const INFINITY : f64 = 1e100;
+
+
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub struct Var(usize);
+
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub struct Constr(usize);
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum VarType {
+    Continuous,
+    Binary,
+    Integer,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Sense {
+    Less,
+    Greater,
+    Equal,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Status {
+    Optimal,
+    Infeasible,
+    Unbounded,
+    /// Stopped by the time limit, with or without a feasible solution.
+    TimeLimit,
+    Other,
+}
+
+
+/// Operations of the solvers used by the column generation. Solution values are those of the last
+/// `optimize` and panic if it found no solution. Models can be moved to other threads, e.g. with the
+/// scenario they belong to.
+pub trait LpModel : Send {
+    fn add_var(&mut self, name : &str, vtype : VarType, obj : f64, lb : f64, ub : f64, column : &[(Constr, f64)]) -> Var;
+    fn add_constr(&mut self, name : &str, row : &[(Var, f64)], sense : Sense, rhs : f64) -> Constr;
+    fn remove_var(&mut self, var : Var);
+    fn remove_constr(&mut self, constr : Constr);
+
+    fn set_rhs(&mut self, constr : Constr, rhs : f64);
+    fn set_obj(&mut self, var : Var, obj : f64);
+    fn set_ub(&mut self, var : Var, ub : f64);
+
+    fn optimize(&mut self) -> Status;
+
+    fn obj_val(&self) -> f64;
+    /// Best known bound on the objective, the objective itself for linear programs.
+    fn obj_bound(&self) -> f64;
+    /// Seconds spent in the last `optimize`.
+    fn runtime(&self) -> f64;
+    fn x(&self, vars : &[Var]) -> Vec<f64>;
+    /// Duals of the constraints, the change of the objective per unit increase of the right hand side.
+    fn pi(&self, constrs : &[Constr]) -> Vec<f64>;
+
+    /// Copy of the model with all pending changes, handles of the original are valid for the copy.
+    fn box_clone(&mut self) -> Box<dyn LpModel>;
+}
+
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Backend {
+    Gurobi,
+    /// Dense bounded simplex with branch and bound, needs no licence but is only suited for small instances.
+    Simplex,
+}
+
+impl Default for Backend {
+    fn default() -> Self {
+        if cfg!(feature = "gurobi") { Backend::Gurobi } else { Backend::Simplex }
+    }
+}
+
+impl FromStr for Backend {
+    type Err = SolverError;
+
+    fn from_str(s : &str) -> Result<Self, Self::Err> {
+        match s {
+            "gurobi" => Ok(Backend::Gurobi),
+            "simplex" => Ok(Backend::Simplex),
+            _ => Err(SolverError::UnknownBackend(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Backend::Gurobi => write!(f, "gurobi"),
+            Backend::Simplex => write!(f, "simplex"),
+        }
+    }
+}
+
+
+#[derive(Debug)]
+pub enum SolverError {
+    UnknownBackend(String),
+    /// The backend was not compiled in.
+    Unavailable(Backend),
+    Backend(String),
+}
+
+impl fmt::Display for SolverError {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolverError::UnknownBackend(name) => write!(f, "unknown solver '{}', expected gurobi or simplex", name),
+            SolverError::Unavailable(backend) => write!(f, "solver {} is not available in this build", backend),
+            SolverError::Backend(reason) => write!(f, "solver: {}", reason),
+        }
+    }
+}
+
+impl Error for SolverError {}
+
+
+#[derive(Clone, Debug)]
+pub struct SolverParams {
+    pub threads : i32,
+    pub seed : u64,
+    /// Seconds, unlimited if not set.
+    pub time_limit : Option<f64>,
+    pub log_file : Option<String>,
+    pub log_to_console : bool,
+    /// Prefer the barrier method for linear programs, a hint that only Gurobi follows.
+    pub barrier : bool,
+}
+
+impl Default for SolverParams {
+    fn default() -> Self {
+        SolverParams {
+            threads : 1,
+            seed : 12345,
+            time_limit : None,
+            log_file : None,
+            log_to_console : false,
+            barrier : false,
+        }
+    }
+}
+
+
+/// A backend with its parameters, shared by all models created from it.
+///
+/// Gurobi environments are not thread safe. An environment can be moved to another thread along
+/// with its models, but must not be used from several threads at once, thus it is not `Sync`.
+/// Every thread working on its own models gets its own environment from `fork`.
+pub struct SolverEnv {
+    backend : Backend,
+    params : SolverParams,
+    #[cfg(feature = "gurobi")]
+    gurobi : Option<std::sync::Arc<gurobi::SharedEnv>>,
+}
+
+// SAFETY: the environment and the models created from it are used by one thread at a time
+#[cfg(feature = "gurobi")]
+unsafe impl Send for SolverEnv {}
+
+impl SolverEnv {
+    pub fn new(backend : Backend, params : SolverParams) -> Result<Self, SolverError> {
+        match backend {
+            #[cfg(feature = "gurobi")]
+            Backend::Gurobi => Ok(SolverEnv { backend, gurobi : Some(gurobi::env(&params)?), params }),
+            #[cfg(not(feature = "gurobi"))]
+            Backend::Gurobi => Err(SolverError::Unavailable(backend)),
+            Backend::Simplex => Ok(SolverEnv {
+                backend,
+                params,
+                #[cfg(feature = "gurobi")]
+                gurobi : None,
+            }),
+        }
+    }
+
+    /// A new environment with the same backend and parameters.
+    pub fn fork(&self) -> Result<SolverEnv, SolverError> {
+        SolverEnv::new(self.backend, self.params.clone())
+    }
+
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    pub fn params(&self) -> &SolverParams {
+        &self.params
+    }
+
+    pub fn model(&self, name : &str) -> Box<dyn LpModel> {
+        match self.backend {
+            #[cfg(feature = "gurobi")]
+            Backend::Gurobi => Box::new(GurobiModel::new(name, self.gurobi.as_ref().unwrap())),
+            Backend::Simplex => Box::new(SimplexModel::new(self.params.clone())),
+            #[allow(unreachable_patterns)]
+            _ => unreachable!(),
+        }
+    }
+}
@@ -0,0 +1,538 @@
+use std::time::Instant;
+
+use crate::lp::{Constr, LpModel, Sense, SolverParams, Status, Var, VarType, INFINITY};
+
+const FEASIBILITY_TOL : f64 = 1e-9;
+const OPTIMALITY_TOL : f64 = 1e-9;
+const PIVOT_TOL : f64 = 1e-9;
+const INTEGRALITY_TOL : f64 = 1e-6;
+// degenerate pivots in a row after which the entering variable is chosen by Bland's rule
+const DEGENERATE_BEFORE_BLAND : usize = 50;
+
+
+#[derive(Clone)]
+struct Column {
+    vtype : VarType,
+    obj : f64,
+    lb : f64,
+    ub : f64,
+    coeffs : Vec<(usize, f64)>,
+    removed : bool,
+}
+
+#[derive(Clone)]
+struct Row {
+    sense : Sense,
+    rhs : f64,
+    removed : bool,
+}
+
+#[derive(Clone)]
+struct Solution {
+    objective : f64,
+    bound : f64,
+    x : Vec<f64>,
+    pi : Vec<f64>,
+}
+
+enum LpResult {
+    Optimal(Solution),
+    Infeasible,
+    Unbounded,
+    /// The time limit ran out.
+    Stopped,
+    /// The simplex ran into its iteration limit, e.g. cycling on a degenerate problem.
+    IterationLimit,
+}
+
+
+/// Dense bounded primal simplex, rebuilt from scratch on every solve, with a depth first branch and
+/// bound for binary and integer variables. Variables need finite lower bounds.
+#[derive(Clone)]
+pub struct SimplexModel {
+    params : SolverParams,
+    columns : Vec<Column>,
+    rows : Vec<Row>,
+    solution : Option<Solution>,
+    runtime : f64,
+}
+
+impl SimplexModel {
+    pub fn new(params : SolverParams) -> Self {
+        SimplexModel {
+            params,
+            columns : Vec::new(),
+            rows : Vec::new(),
+            solution : None,
+            runtime : 0.0,
+        }
+    }
+
+    fn solution(&self) -> &Solution {
+        self.solution.as_ref().expect("No solution available")
+    }
+
+    fn branch_and_bound(&self, lower : Vec<f64>, upper : Vec<f64>, deadline : Option<Instant>) -> (Status, Option<Solution>) {
+        let integers : Vec<usize> = self.columns.iter().enumerate()
+            .filter(|(_, column)| !column.removed && column.vtype != VarType::Continuous)
+            .map(|(index, _)| index)
+            .collect();
+
+        let mut incumbent : Option<Solution> = None;
+        // nodes with the bound of the relaxation of their parent
+        let mut open = vec![(lower, upper, -INFINITY)];
+        let mut root = true;
+        while let Some((lower, upper, node_bound)) = open.pop() {
+            if deadline.is_some_and(|deadline| Instant::now() > deadline) {
+                return (Status::TimeLimit, stopped(incumbent, node_bound, &open));
+            }
+            let solution = match solve_lp(&self.columns, &self.rows, &lower, &upper, deadline) {
+                LpResult::Optimal(solution) => solution,
+                LpResult::Infeasible => {
+                    if root { return (Status::Infeasible, None) }
+                    continue
+                }
+                LpResult::Unbounded => return (Status::Unbounded, None),
+                LpResult::Stopped => return (Status::TimeLimit, stopped(incumbent, node_bound, &open)),
+                LpResult::IterationLimit => return (Status::Other, stopped(incumbent, node_bound, &open)),
+            };
+            root = false;
+            if incumbent.as_ref().is_some_and(|best| solution.objective >= best.objective - OPTIMALITY_TOL) {
+                continue
+            }
+
+            // branch on the most fractional variable
+            let fractional = integers.iter()
+                .map(|&index| (index, solution.x[index] - solution.x[index].floor()))
+                .filter(|(_, fraction)| *fraction > INTEGRALITY_TOL && *fraction < 1.0 - INTEGRALITY_TOL)
+                .max_by(|(_, a), (_, b)| (0.5 - (a - 0.5).abs()).total_cmp(&(0.5 - (b - 0.5).abs())));
+
+            match fractional {
+                None => {
+                    let mut solution = solution;
+                    for &index in &integers {
+                        solution.x[index] = solution.x[index].round();
+                    }
+                    incumbent = Some(solution);
+                }
+                Some((index, _)) => {
+                    let value = solution.x[index];
+                    let mut down = (lower.clone(), upper.clone(), solution.objective);
+                    down.1[index] = value.floor();
+                    let mut up = (lower, upper, solution.objective);
+                    up.0[index] = value.ceil();
+                    // the up branch is explored first
+                    open.push(down);
+                    open.push(up);
+                }
+            }
+        }
+
+        match incumbent {
+            Some(mut solution) => {
+                solution.bound = solution.objective;
+                (Status::Optimal, Some(solution))
+            }
+            None => (Status::Infeasible, None),
+        }
+    }
+}
+
+/// The incumbent of a search stopped early, bounded by the nodes left to explore.
+fn stopped(incumbent : Option<Solution>, node_bound : f64, open : &[(Vec<f64>, Vec<f64>, f64)]) -> Option<Solution> {
+    incumbent.map(|mut solution| {
+        let open_bound = open.iter().map(|(_, _, bound)| *bound).fold(node_bound, f64::min);
+        solution.bound = open_bound.min(solution.objective);
+        solution
+    })
+}
+
+impl LpModel for SimplexModel {
+    fn add_var(&mut self, _name : &str, vtype : VarType, obj : f64, lb : f64, ub : f64, column : &[(Constr, f64)]) -> Var {
+        let (lb, ub) = match vtype {
+            VarType::Binary => (lb.max(0.0), ub.min(1.0)),
+            _ => (lb, ub),
+        };
+        assert!(lb > -INFINITY, "The simplex backend needs finite lower bounds");
+        self.columns.push(Column {
+            vtype,
+            obj,
+            lb,
+            ub,
+            coeffs : column.iter().map(|(constr, coeff)| (constr.0, *coeff)).collect(),
+            removed : false,
+        });
+        Var(self.columns.len() - 1)
+    }
+
+    fn add_constr(&mut self, _name : &str, row : &[(Var, f64)], sense : Sense, rhs : f64) -> Constr {
+        self.rows.push(Row { sense, rhs, removed : false });
+        let index = self.rows.len() - 1;
+        for (var, coeff) in row {
+            self.columns[var.0].coeffs.push((index, *coeff));
+        }
+        Constr(index)
+    }
+
+    fn remove_var(&mut self, var : Var) {
+        let column = &mut self.columns[var.0];
+        column.removed = true;
+        column.coeffs = Vec::new();
+    }
+
+    fn remove_constr(&mut self, constr : Constr) {
+        self.rows[constr.0].removed = true;
+        for column in self.columns.iter_mut() {
+            column.coeffs.retain(|(row, _)| *row != constr.0);
+        }
+    }
+
+    fn set_rhs(&mut self, constr : Constr, rhs : f64) {
+        self.rows[constr.0].rhs = rhs;
+    }
+
+    fn set_obj(&mut self, var : Var, obj : f64) {
+        self.columns[var.0].obj = obj;
+    }
+
+    fn set_ub(&mut self, var : Var, ub : f64) {
+        self.columns[var.0].ub = ub;
+    }
+
+    fn optimize(&mut self) -> Status {
+        let start = Instant::now();
+        let deadline = self.params.time_limit.map(|seconds| start + std::time::Duration::from_secs_f64(seconds));
+        let lower : Vec<f64> = self.columns.iter().map(|column| column.lb).collect();
+        let upper : Vec<f64> = self.columns.iter().map(|column| column.ub).collect();
+
+        let (status, solution) = self.branch_and_bound(lower, upper, deadline);
+        self.solution = solution;
+        self.runtime = start.elapsed().as_secs_f64();
+        status
+    }
+
+    fn obj_val(&self) -> f64 {
+        self.solution().objective
+    }
+
+    fn obj_bound(&self) -> f64 {
+        self.solution().bound
+    }
+
+    fn runtime(&self) -> f64 {
+        self.runtime
+    }
+
+    fn x(&self, vars : &[Var]) -> Vec<f64> {
+        let solution = self.solution();
+        vars.iter().map(|var| solution.x[var.0]).collect()
+    }
+
+    fn pi(&self, constrs : &[Constr]) -> Vec<f64> {
+        let solution = self.solution();
+        constrs.iter().map(|constr| solution.pi[constr.0]).collect()
+    }
+
+    fn box_clone(&mut self) -> Box<dyn LpModel> {
+        Box::new(self.clone())
+    }
+}
+
+
+#[derive(Copy, Clone, PartialEq)]
+enum At {
+    Lower,
+    Upper,
+    Basic,
+}
+
+/// Tableau of `min c'y, Ay = b, 0 <= y <= u` with `b >= 0`. Columns are the shifted structural
+/// variables, one slack per inequality and one artificial per row without a starting slack.
+struct Tableau {
+    rows : usize,
+    cols : usize,
+    t : Vec<f64>,
+    beta : Vec<f64>,
+    basis : Vec<usize>,
+    at : Vec<At>,
+    upper : Vec<f64>,
+    cost : Vec<f64>,
+    reduced : Vec<f64>,
+}
+
+impl Tableau {
+    fn entry(&self, row : usize, col : usize) -> f64 {
+        self.t[row * self.cols + col]
+    }
+
+    fn value(&self, col : usize) -> f64 {
+        match self.at[col] {
+            At::Lower => 0.0,
+            At::Upper => self.upper[col],
+            At::Basic => self.beta[self.basis.iter().position(|&basic| basic == col).unwrap()],
+        }
+    }
+
+    fn price(&mut self) {
+        for col in 0..self.cols {
+            self.reduced[col] = self.cost[col] - (0..self.rows).map(|row| self.cost[self.basis[row]] * self.entry(row, col)).sum::<f64>();
+        }
+    }
+
+    fn pivot(&mut self, pivot_row : usize, pivot_col : usize) {
+        let cols = self.cols;
+        let pivot = self.t[pivot_row * cols + pivot_col];
+        for col in 0..cols {
+            self.t[pivot_row * cols + col] /= pivot;
+        }
+        let (before, rest) = self.t.split_at_mut(pivot_row * cols);
+        let (pivot_line, after) = rest.split_at_mut(cols);
+        for line in before.chunks_mut(cols).chain(after.chunks_mut(cols)) {
+            let factor = line[pivot_col];
+            if factor != 0.0 {
+                for (value, pivot_value) in line.iter_mut().zip(pivot_line.iter()) {
+                    *value -= factor * pivot_value;
+                }
+            }
+        }
+        let factor = self.reduced[pivot_col];
+        for (value, pivot_value) in self.reduced.iter_mut().zip(pivot_line.iter()) {
+            *value -= factor * pivot_value;
+        }
+        self.at[self.basis[pivot_row]] = At::Lower;
+        self.basis[pivot_row] = pivot_col;
+        self.at[pivot_col] = At::Basic;
+    }
+
+    /// Runs the simplex on the current costs until optimality.
+    fn iterate(&mut self, deadline : Option<Instant>) -> Result<(), LpResult> {
+        let max_iterations = 50_000 + 50 * (self.rows + self.cols);
+        let mut degenerate = 0;
+        for iteration in 0..max_iterations {
+            if iteration % 100 == 0 && deadline.is_some_and(|deadline| Instant::now() > deadline) {
+                return Err(LpResult::Stopped);
+            }
+            let bland = degenerate >= DEGENERATE_BEFORE_BLAND;
+
+            // entering column and the direction it moves in
+            let mut entering : Option<(usize, f64)> = None;
+            let mut best = OPTIMALITY_TOL;
+            for col in 0..self.cols {
+                if self.upper[col] <= FEASIBILITY_TOL {
+                    continue
+                }
+                let score = match self.at[col] {
+                    At::Lower => -self.reduced[col],
+                    At::Upper => self.reduced[col],
+                    At::Basic => continue,
+                };
+                if score > best {
+                    entering = Some((col, if self.at[col] == At::Lower { 1.0 } else { -1.0 }));
+                    if bland { break }
+                    best = score;
+                }
+            }
+            let (col, direction) = match entering {
+                Some(entering) => entering,
+                None => return Ok(()),
+            };
+
+            // ratio test, a bound flip of the entering column is the first candidate
+            let mut step = self.upper[col];
+            let mut leaving : Option<(usize, At)> = None;
+            let mut leaving_pivot = 0.0;
+            for row in 0..self.rows {
+                let rate = direction * self.entry(row, col);
+                let (limit, bound) = if rate > PIVOT_TOL {
+                    (self.beta[row].max(0.0) / rate, At::Lower)
+                } else if rate < -PIVOT_TOL && self.upper[self.basis[row]] < INFINITY {
+                    ((self.upper[self.basis[row]] - self.beta[row]).max(0.0) / -rate, At::Upper)
+                } else {
+                    continue
+                };
+                let better = match leaving {
+                    _ if limit < step - FEASIBILITY_TOL => true,
+                    _ if limit > step + FEASIBILITY_TOL => false,
+                    None => true,
+                    Some((current, _)) => if bland { self.basis[row] < self.basis[current] } else { rate.abs() > leaving_pivot },
+                };
+                if better {
+                    step = limit;
+                    leaving = Some((row, bound));
+                    leaving_pivot = rate.abs();
+                }
+            }
+            if step >= INFINITY {
+                return Err(LpResult::Unbounded);
+            }
+            degenerate = if step <= FEASIBILITY_TOL { degenerate + 1 } else { 0 };
+
+            for row in 0..self.rows {
+                self.beta[row] -= step * direction * self.entry(row, col);
+            }
+            let entering_value = if direction > 0.0 { step } else { self.upper[col] - step };
+            match leaving {
+                None => {
+                    self.at[col] = if direction > 0.0 { At::Upper } else { At::Lower };
+                }
+                Some((row, bound)) => {
+                    let leaving_col = self.basis[row];
+                    self.pivot(row, col);
+                    self.beta[row] = entering_value;
+                    self.at[leaving_col] = bound;
+                }
+            }
+        }
+        Err(LpResult::IterationLimit)
+    }
+}
+
+
+/// Solves the linear relaxation with the given variable bounds.
+fn solve_lp(columns : &[Column], rows : &[Row], lower : &[f64], upper : &[f64], deadline : Option<Instant>) -> LpResult {
+    let structural : Vec<usize> = (0..columns.len()).filter(|&index| !columns[index].removed).collect();
+    if structural.iter().any(|&index| lower[index] > upper[index] + FEASIBILITY_TOL) {
+        return LpResult::Infeasible;
+    }
+
+    // rows without coefficients only need to hold for zero and are left out of the tableau
+    let mut used = vec![false; rows.len()];
+    for &index in &structural {
+        for (row, coeff) in &columns[index].coeffs {
+            if *coeff != 0.0 && !rows[*row].removed {
+                used[*row] = true;
+            }
+        }
+    }
+    for (_, row) in rows.iter().enumerate().filter(|(index, row)| !row.removed && !used[*index]) {
+        let feasible = match row.sense {
+            Sense::Less => row.rhs >= -FEASIBILITY_TOL,
+            Sense::Greater => row.rhs <= FEASIBILITY_TOL,
+            Sense::Equal => row.rhs.abs() <= FEASIBILITY_TOL,
+        };
+        if !feasible {
+            return LpResult::Infeasible;
+        }
+    }
+    let tableau_rows : Vec<usize> = (0..rows.len()).filter(|&index| used[index]).collect();
+    let mut position = vec![usize::MAX; rows.len()];
+    for (position_in_tableau, &row) in tableau_rows.iter().enumerate() {
+        position[row] = position_in_tableau;
+    }
+
+    // shift variables to a lower bound of zero and flip rows to a non-negative right hand side
+    let m = tableau_rows.len();
+    let mut rhs : Vec<f64> = tableau_rows.iter().map(|&row| rows[row].rhs).collect();
+    for &index in &structural {
+        for (row, coeff) in &columns[index].coeffs {
+            if used[*row] {
+                rhs[position[*row]] -= coeff * lower[index];
+            }
+        }
+    }
+    let sign : Vec<f64> = rhs.iter().map(|value| if *value < 0.0 { -1.0 } else { 1.0 }).collect();
+    let sense : Vec<Sense> = tableau_rows.iter().zip(&sign).map(|(&row, sign)| match (rows[row].sense, *sign < 0.0) {
+        (Sense::Less, true) => Sense::Greater,
+        (Sense::Greater, true) => Sense::Less,
+        (sense, _) => sense,
+    }).collect();
+
+    let n = structural.len();
+    let slacks = sense.iter().filter(|sense| **sense != Sense::Equal).count();
+    let artificials = sense.iter().filter(|sense| **sense != Sense::Less).count();
+    let cols = n + slacks + artificials;
+
+    let mut tableau = Tableau {
+        rows : m,
+        cols,
+        t : vec![0.0; m * cols],
+        beta : rhs.iter().zip(&sign).map(|(value, sign)| value * sign).collect(),
+        basis : vec![0; m],
+        at : vec![At::Lower; cols],
+        upper : vec![INFINITY; cols],
+        cost : vec![0.0; cols],
+        reduced : vec![0.0; cols],
+    };
+    for (col, &index) in structural.iter().enumerate() {
+        tableau.upper[col] = if upper[index] >= INFINITY { INFINITY } else { upper[index] - lower[index] };
+        for (row, coeff) in &columns[index].coeffs {
+            if used[*row] {
+                let row = position[*row];
+                tableau.t[row * cols + col] += coeff * sign[row];
+            }
+        }
+    }
+    // the column holding an identity entry of each row gives its dual
+    let mut identity = vec![0; m];
+    let mut next = n;
+    for row in 0..m {
+        if sense[row] != Sense::Equal {
+            tableau.t[row * cols + next] = if sense[row] == Sense::Less { 1.0 } else { -1.0 };
+            if sense[row] == Sense::Less {
+                identity[row] = next;
+            }
+            next += 1;
+        }
+    }
+    let first_artificial = next;
+    for row in 0..m {
+        if sense[row] != Sense::Less {
+            tableau.t[row * cols + next] = 1.0;
+            identity[row] = next;
+            next += 1;
+        }
+        tableau.basis[row] = identity[row];
+        tableau.at[identity[row]] = At::Basic;
+    }
+
+    // phase one drives the artificials to zero
+    if first_artificial < cols {
+        for col in first_artificial..cols {
+            tableau.cost[col] = 1.0;
+        }
+        tableau.price();
+        if let Err(result) = tableau.iterate(deadline) {
+            return match result {
+                LpResult::Unbounded => LpResult::Infeasible,
+                result => result,
+            };
+        }
+        let infeasibility : f64 = (first_artificial..cols).map(|col| tableau.value(col)).sum();
+        if infeasibility > 1e-6 * (1.0 + tableau.beta.iter().map(|value| value.abs()).sum::<f64>()) {
+            return LpResult::Infeasible;
+        }
+        for col in first_artificial..cols {
+            tableau.upper[col] = 0.0;
+            tableau.cost[col] = 0.0;
+        }
+        // pivot remaining artificials out of the basis where possible, the others are redundant rows
+        for row in 0..m {
+            if tableau.basis[row] >= first_artificial {
+                if let Some(col) = (0..first_artificial).find(|&col| tableau.at[col] != At::Basic && tableau.entry(row, col).abs() > 1e-7) {
+                    let value = tableau.value(col);
+                    tableau.pivot(row, col);
+                    tableau.beta[row] = value;
+                }
+            }
+        }
+    }
+
+    for (col, &index) in structural.iter().enumerate() {
+        tableau.cost[col] = columns[index].obj;
+    }
+    tableau.price();
+    if let Err(result) = tableau.iterate(deadline) {
+        return result;
+    }
+
+    let mut x = vec![0.0; columns.len()];
+    for (col, &index) in structural.iter().enumerate() {
+        x[index] = lower[index] + tableau.value(col).max(0.0);
+    }
+    let mut pi = vec![0.0; rows.len()];
+    for (row, &index) in tableau_rows.iter().enumerate() {
+        pi[index] = -tableau.reduced[identity[row]] * sign[row];
+    }
+    let objective = structural.iter().map(|&index| columns[index].obj * x[index]).sum();
+    LpResult::Optimal(Solution { objective, bound : objective, x, pi })
+}
@@ -4,7 +4,7 @@ use std::io;
 use std::io::{BufRead, BufWriter};
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use shared::{Segment, Site, Period, Vehicle, CustomHashSet};
+use shared::{Segment, Site, SiteId, Period, Vehicle, CustomHashSet};
 
 #[cfg(feature = "perf_statistics")]
 use crate::metrics::*;
@@ -136,7 +136,7 @@ impl <'b> PatternPool {
                     let entry: Pattern = remainder.split(";").map(|entry| {
                         let mut m = entry.split(",");
                         let segment_id = m.next().unwrap().parse::<u32>().unwrap();
-                        let site_id = m.next().unwrap().parse::<u8>().unwrap();
+                        let site_id = m.next().unwrap().parse::<SiteId>().unwrap();
                         let time = m.next().unwrap().parse::<Period>().unwrap();
                         let site = sites.iter().filter(|s| s.id == site_id).next().unwrap();
                         let segment = vehicle.tour.iter().filter(|s| s.id == segment_id).next().unwrap();
@@ -0,0 +1,306 @@
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+
+use indexmap::IndexMap;
+use shared::{Battery, ColumnGenerationIteration, Horizon, InstanceData, InstanceError, InstanceManifest, Segment, Simple, Site, SiteId, SolutionMethod, SolutionReport, Vehicle, VehicleTimeline};
+
+use crate::fixed_size::solution_approach_variable::{RunSettings, SolutionApproachVariable};
+use crate::lp::{Backend, SolverEnv, SolverError, SolverParams};
+use crate::stabilization::Stabilization;
+
+
+/// How the number of chargers of open sites is chosen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SiteSizing {
+    /// Sites are opened at their largest level.
+    Largest,
+    /// Sites are opened at the smallest level of any site.
+    Smallest,
+    /// Starts with the largest level and decreases it level by level down to the smallest one.
+    HighToLow,
+    /// Sites are opened with the given number of chargers, approached from the largest level.
+    Fixed(u8),
+    /// The level of every site is chosen individually.
+    Variable,
+}
+
+
+/// Settings of the planner, `PlannerConfig::default()` matches the defaults of `solution_approach_variable`.
+///
+/// ```ignore
+/// let config = PlannerConfig::default().sizing(SiteSizing::Variable).seed(7);
+/// ```
+#[derive(Debug, Clone)]
+pub struct PlannerConfig {
+    pub sizing : SiteSizing,
+    pub solver : Backend,
+    pub min_num_sites : usize,
+    /// Share of the vehicles that may remain infeasible, only supported for a single scenario.
+    pub percent_infeasible_allowed : f64,
+    /// Threads of the solver for the master problems.
+    pub threads : i32,
+    /// Threads solving the pricing problems of the vehicles within every scenario.
+    pub pricing_workers : usize,
+    /// Threads solving the scenarios of a site configuration concurrently.
+    pub scenario_workers : usize,
+    /// Stabilisation of the duals handed to the pricing problems.
+    pub stabilization : Stabilization,
+    /// Keep every pricing round for `Plan::cg_iterations`, they grow with every branch node.
+    pub record_iterations : bool,
+    /// Percentage of the active scenarios that need to accept a configuration.
+    pub quorum_accept_percent : u8,
+    /// Percentage of infeasible vehicles with which a scenario still counts as feasible.
+    pub benevolent_accept_percent : u8,
+    pub max_activate_per_generation : usize,
+    pub activate_all : bool,
+    /// Activate only the infeasible vehicles of a scenario instead of all of them.
+    pub activate_iis : bool,
+    /// Fleet size to base the benevolent feasibility on instead of the vehicles of a scenario.
+    pub total_num_vehicles : Option<usize>,
+    /// Planning horizon, unless the instance manifest sets one.
+    pub horizon : Option<Horizon>,
+    pub seed : u64,
+    /// Time limit of the quick integer solves in seconds.
+    pub integer_time_limit : f64,
+    pub cuts_input : Option<String>,
+    pub cuts_output : Option<String>,
+    pub charge_processes_output : Option<String>,
+}
+
+impl Default for PlannerConfig {
+    fn default() -> Self {
+        PlannerConfig {
+            sizing : SiteSizing::Largest,
+            solver : Backend::default(),
+            min_num_sites : 5,
+            percent_infeasible_allowed : 0.0,
+            threads : 1,
+            pricing_workers : 1,
+            scenario_workers : 1,
+            stabilization : Stabilization::None,
+            record_iterations : false,
+            quorum_accept_percent : 100,
+            benevolent_accept_percent : 0,
+            max_activate_per_generation : 1,
+            activate_all : false,
+            activate_iis : false,
+            total_num_vehicles : None,
+            horizon : None,
+            seed : 12345,
+            integer_time_limit : 60.0,
+            cuts_input : None,
+            cuts_output : None,
+            charge_processes_output : None,
+        }
+    }
+}
+
+impl PlannerConfig {
+    pub fn sizing(mut self, sizing : SiteSizing) -> Self { self.sizing = sizing; self }
+    pub fn solver(mut self, solver : Backend) -> Self { self.solver = solver; self }
+    pub fn min_num_sites(mut self, min_num_sites : usize) -> Self { self.min_num_sites = min_num_sites; self }
+    pub fn percent_infeasible_allowed(mut self, percent : f64) -> Self { self.percent_infeasible_allowed = percent; self }
+    pub fn threads(mut self, threads : i32) -> Self { self.threads = threads; self }
+    pub fn pricing_workers(mut self, workers : usize) -> Self { self.pricing_workers = workers; self }
+    pub fn scenario_workers(mut self, workers : usize) -> Self { self.scenario_workers = workers; self }
+    pub fn stabilization(mut self, stabilization : Stabilization) -> Self { self.stabilization = stabilization; self }
+    pub fn record_iterations(mut self, record : bool) -> Self { self.record_iterations = record; self }
+    pub fn quorum_accept_percent(mut self, percent : u8) -> Self { self.quorum_accept_percent = percent; self }
+    pub fn benevolent_accept_percent(mut self, percent : u8) -> Self { self.benevolent_accept_percent = percent; self }
+    pub fn max_activate_per_generation(mut self, max : usize) -> Self { self.max_activate_per_generation = max; self }
+    pub fn activate_all(mut self, activate_all : bool) -> Self { self.activate_all = activate_all; self }
+    pub fn activate_iis(mut self, activate_iis : bool) -> Self { self.activate_iis = activate_iis; self }
+    pub fn total_num_vehicles(mut self, total : Option<usize>) -> Self { self.total_num_vehicles = total; self }
+    pub fn horizon(mut self, horizon : Horizon) -> Self { self.horizon = Some(horizon); self }
+    pub fn seed(mut self, seed : u64) -> Self { self.seed = seed; self }
+    pub fn integer_time_limit(mut self, seconds : f64) -> Self { self.integer_time_limit = seconds; self }
+    pub fn cuts_input(mut self, path : &str) -> Self { self.cuts_input = Some(path.to_string()); self }
+    pub fn cuts_output(mut self, path : &str) -> Self { self.cuts_output = Some(path.to_string()); self }
+    pub fn charge_processes_output(mut self, path : &str) -> Self { self.charge_processes_output = Some(path.to_string()); self }
+}
+
+
+#[derive(Debug)]
+pub enum PlanningError {
+    Instance(InstanceError),
+    Solver(SolverError),
+    /// The instance and the settings do not fit together.
+    Invalid(String),
+}
+
+impl fmt::Display for PlanningError {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlanningError::Instance(e) => write!(f, "{}", e),
+            PlanningError::Solver(e) => write!(f, "{}", e),
+            PlanningError::Invalid(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl Error for PlanningError {}
+
+impl From<InstanceError> for PlanningError {
+    fn from(e : InstanceError) -> Self {
+        PlanningError::Instance(e)
+    }
+}
+
+impl From<SolverError> for PlanningError {
+    fn from(e : SolverError) -> Self {
+        PlanningError::Solver(e)
+    }
+}
+
+
+/// The best plan found with what is known about it.
+#[derive(Debug, Clone)]
+pub struct Plan {
+    pub solution : Simple,
+    pub report : SolutionReport,
+    pub timelines : Vec<VehicleTimeline>,
+    /// Pricing rounds of the column generation, to compare the convergence of settings. Empty unless
+    /// `PlannerConfig::record_iterations` is set.
+    pub cg_iterations : Vec<ColumnGenerationIteration>,
+}
+
+
+/// An instance with all its scenarios loaded once for any number of solves. It owns its data and
+/// can be shared between threads.
+pub struct Instance {
+    pub manifest : InstanceManifest,
+    data : InstanceData,
+}
+
+impl Instance {
+    pub fn new(manifest : InstanceManifest) -> Result<Self, InstanceError> {
+        let data = InstanceData::load(&manifest)?;
+        Ok(Instance { manifest, data })
+    }
+
+    pub fn load(manifest_path : &str) -> Result<Self, InstanceError> {
+        Instance::new(InstanceManifest::load(manifest_path)?)
+    }
+
+    pub fn data(&self) -> &InstanceData {
+        &self.data
+    }
+
+    pub fn sites(&self) -> &IndexMap<SiteId, Site> {
+        &self.data.sites
+    }
+
+    pub fn batteries(&self) -> &IndexMap<String, Battery> {
+        &self.data.batteries
+    }
+
+    pub fn horizon(&self, config : &PlannerConfig) -> Horizon {
+        self.manifest.horizon.or(config.horizon).unwrap_or_default()
+    }
+
+    pub fn solve(&self, config : &PlannerConfig) -> Result<Plan, PlanningError> {
+        self.solve_until(config, Arc::new(AtomicBool::new(false)))
+    }
+
+    /// Solves the instance, stopping with the best plan so far once `should_stop` is set.
+    pub fn solve_until(&self, config : &PlannerConfig, should_stop : Arc<AtomicBool>) -> Result<Plan, PlanningError> {
+        let scenarios = &self.data.scenarios;
+        if scenarios.is_empty() {
+            return Err(PlanningError::Invalid("the instance has no scenarios".to_string()));
+        }
+        let scenario_names : Vec<String> = scenarios.iter().map(|scenario| scenario.name.clone()).collect();
+        let input_data : Vec<&[Vehicle]> = scenarios.iter().map(|scenario| scenario.vehicles.as_slice()).collect();
+
+        solve_scenarios(self.sites(), &input_data, &scenario_names, &self.horizon(config), config, should_stop)
+    }
+}
+
+
+/// Runs the column generation on scenarios given as vehicles with their tours.
+fn solve_scenarios(
+    sites : &IndexMap<SiteId, Site>,
+    input_data : &[&[Vehicle]],
+    scenario_names : &[String],
+    horizon : &Horizon,
+    config : &PlannerConfig,
+    should_stop : Arc<AtomicBool>,
+) -> Result<Plan, PlanningError> {
+
+    let num_infeasible_allowed = (config.percent_infeasible_allowed * input_data[0].len() as f64).round() as usize;
+    if num_infeasible_allowed > 0 && input_data.len() != 1 {
+        // only suitable for non robust setting
+        return Err(PlanningError::Invalid("infeasible vehicles can only be allowed for a single scenario".to_string()));
+    }
+
+    log::info!("Working with {} scenarios of {} vehicles", input_data.len(), input_data[0].len());
+
+    let lowest_level = sites.values().flat_map(|site| site.levels.iter().map(|l| l.chargers)).min()
+        .ok_or_else(|| PlanningError::Invalid("the instance has no sites".to_string()))?;
+    let highest_level = sites.values().map(|site| site.max_level()).max().unwrap();
+
+    let (static_target_size, do_high_low, do_variable) = match config.sizing {
+        SiteSizing::Largest => (None, false, false),
+        SiteSizing::Smallest => (Some(lowest_level), false, false),
+        SiteSizing::HighToLow => (None, true, false),
+        // smaller sizes are approached from the largest level
+        SiteSizing::Fixed(size) => (Some(size), size != highest_level, false),
+        SiteSizing::Variable => (None, false, true),
+    };
+
+
+    let env = SolverEnv::new(config.solver, SolverParams { seed : config.seed, ..SolverParams::default() })?;
+
+    // set low time limit; we mainly want the integer solution by branching this function is only for quick wins;
+    let env_integer = SolverEnv::new(config.solver, SolverParams {
+        seed : config.seed,
+        time_limit : Some(config.integer_time_limit),
+        ..SolverParams::default()
+    })?;
+
+
+    let mut seq = SolutionApproachVariable::new(
+        config.min_num_sites, num_infeasible_allowed, sites.clone(), input_data, config.threads, config.pricing_workers, config.scenario_workers, config.stabilization, true,
+        config.quorum_accept_percent, config.benevolent_accept_percent, config.max_activate_per_generation,
+        config.activate_all, config.activate_iis,
+        config.total_num_vehicles.map_or(-1, |total| total as i64), *horizon, config.seed,
+        &env, &env_integer
+    )?;
+
+    seq.record_iterations(config.record_iterations);
+    let solution = seq.run(should_stop, &RunSettings {
+        static_target_size,
+        do_low_high_swap : do_high_low,
+        do_variable_sizing : do_variable,
+        charge_processes_output : config.charge_processes_output.as_deref(),
+        cuts_output : config.cuts_output.as_deref(),
+        cuts_input : config.cuts_input.as_deref(),
+    });
+    log::info!("Column generation completed with cost {}", solution.cost);
+
+    let report = seq.report(&solution, scenario_names)?;
+    let timelines = seq.timelines(&report)?;
+    Ok(Plan { solution, report, timelines, cg_iterations : seq.cg_iterations() })
+}
+
+
+/// Solves a single scenario with the settings.
+///
+/// `SolutionMethod::run` has no way to return an error, thus this panics with the `PlanningError`
+/// if the solver environment cannot be created, e.g. without a Gurobi licence, or the sites and
+/// settings do not fit together. Use `Instance::solve` to handle these errors.
+impl SolutionMethod for PlannerConfig {
+    fn run(
+        &self,
+        sites : &IndexMap<SiteId, Site>,
+        _segments : &IndexMap<u32, Arc<Segment>>,
+        vehicles : &[Vehicle],
+        should_stop : Arc<AtomicBool>,
+    ) -> Simple {
+        let horizon = self.horizon.unwrap_or_default();
+        solve_scenarios(sites, &[vehicles], &["0".to_string()], &horizon, self, should_stop)
+            .unwrap_or_else(|e| panic!("planning failed: {}", e))
+            .solution
+    }
+}
@@ -0,0 +1,50 @@
+//! Worker pool for the pricing problems of the column generation.
+//!
+//! The pricing problems of the vehicles are independent of each other once the duals are set, thus
+//! they are distributed over scoped worker threads. Results are handed back in the order of the
+//! inputs so that merging them into the pattern pool does not depend on the scheduling.
+
+use crossbeam_deque::{Injector, Steal};
+
+
+/// Applies `f` to every item using up to `workers` threads and returns the results in the order of
+/// `items`. With a single worker or a single item everything runs on the calling thread.
+pub(crate) fn map_in_order<T, R, F>(items : &[T], workers : usize, f : F) -> Vec<R>
+    where T : Sync, R : Send, F : Fn(&T) -> R + Sync {
+
+    let workers = workers.min(items.len());
+    if workers <= 1 {
+        return items.iter().map(f).collect();
+    }
+
+    let queue = Injector::new();
+    for index in 0..items.len() {
+        queue.push(index);
+    }
+
+    let mut results : Vec<Option<R>> = (0..items.len()).map(|_| None).collect();
+    crossbeam::thread::scope(|scope| {
+        let handles : Vec<_> = (0..workers).map(|_| {
+            let (queue, f) = (&queue, &f);
+            scope.spawn(move |_| {
+                let mut done = Vec::new();
+                loop {
+                    match queue.steal() {
+                        Steal::Success(index) => done.push((index, f(&items[index]))),
+                        Steal::Retry => continue,
+                        Steal::Empty => break,
+                    }
+                }
+                done
+            })
+        }).collect();
+
+        for handle in handles {
+            for (index, result) in handle.join().unwrap() {
+                results[index] = Some(result);
+            }
+        }
+    }).unwrap();
+
+    results.into_iter().map(|result| result.unwrap()).collect()
+}
@@ -0,0 +1,192 @@
+//! Dual stabilisation of the column generation.
+//!
+//! Wentges smoothing prices the convex combination `alpha * center + (1 - alpha) * master` of the
+//! duals of a stability center and of the restricted master problem. The center is the priced point
+//! with the best Lagrangian bound so far, which makes this the in-out separation of Ben Amor et al.
+//! with the center as in-point. Only columns with negative reduced cost at the master duals are
+//! added. A pricing round at smoothed duals without such a column is a mis-pricing, the duals are
+//! then moved towards the master duals until they coincide, so the column generation only ends on
+//! a pricing round at the master duals.
+
+use ndarray::Array2;
+use shared::Horizon;
+
+use crate::pattern_pool::Pattern;
+
+const SMOOTHING_TOL : f64 = 1e-9;
+
+
+/// How the duals of the restricted master problem are handed to the pricing problems.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Stabilization {
+    /// The master duals are priced as they are.
+    #[default]
+    None,
+    /// Wentges smoothing with the weight of the stability center, in `[0, 1)`.
+    Smoothing(f64),
+}
+
+
+/// A dual solution of the restricted master problem, reduced to the terms the pricing problems use.
+#[derive(Debug, Clone)]
+pub(crate) struct Duals {
+    /// Duals of the capacity and branching constraints of every site and period.
+    pub site_period : Array2<f64>,
+    /// Duals of the convexity constraints in vehicle order.
+    pub convexity : Vec<f64>,
+    /// Dual objective value, the master objective for master duals.
+    pub value : f64,
+}
+
+impl Duals {
+    fn combine(center : &Duals, alpha : f64, master : &Duals) -> Duals {
+        Duals {
+            site_period : &center.site_period * alpha + &master.site_period * (1.0 - alpha),
+            convexity : center.convexity.iter().zip(&master.convexity).map(|(c, m)| alpha * c + (1.0 - alpha) * m).collect(),
+            value : alpha * center.value + (1.0 - alpha) * master.value,
+        }
+    }
+
+    /// Reduced cost of a pattern of the vehicle at `vehicle_index` for these duals.
+    pub fn reduced_cost(&self, vehicle_index : usize, pattern : &Pattern, horizon : &Horizon) -> f64 {
+        let collected_duals : f64 = pattern.iter()
+            .map(|(_, site, period)| self.site_period[[site.index(), horizon.capacity_period(period)]])
+            .sum();
+        0.0 - collected_duals - self.convexity[vehicle_index]
+    }
+}
+
+
+/// Stability center and mis-pricing state of one column generation run.
+pub(crate) struct Stabilizer {
+    alpha : f64,
+    center : Option<(Duals, Option<f64>)>,
+    // mis-pricings since the master was last solved
+    mis_pricings : usize,
+}
+
+impl Stabilizer {
+    pub fn new(stabilization : Stabilization) -> Self {
+        let alpha = match stabilization {
+            Stabilization::None => 0.0,
+            Stabilization::Smoothing(alpha) => {
+                assert!((0.0..1.0).contains(&alpha), "Smoothing weight {} not in [0, 1)", alpha);
+                alpha
+            }
+        };
+        Stabilizer { alpha, center : None, mis_pricings : 0 }
+    }
+
+    /// Called after every solve of the master problem.
+    pub fn new_iteration(&mut self) {
+        self.mis_pricings = 0;
+    }
+
+    /// Weight of the center in the next pricing round, decreased with every mis-pricing.
+    pub fn smoothing(&self) -> f64 {
+        if self.center.is_none() {
+            return 0.0;
+        }
+        let smoothing = self.alpha - self.mis_pricings as f64 * (1.0 - self.alpha);
+        // rounding must not leave a tiny weight, which would take another pricing round
+        if smoothing < SMOOTHING_TOL { 0.0 } else { smoothing }
+    }
+
+    /// The duals to price for the master duals.
+    pub fn duals_to_price(&self, master : &Duals) -> Duals {
+        match &self.center {
+            Some((center, _)) if self.smoothing() > 0.0 => Duals::combine(center, self.smoothing(), master),
+            _ => master.clone(),
+        }
+    }
+
+    /// Records a pricing round at `priced` with the Lagrangian bound found for it, if known. Points
+    /// with a better bound become the center, without a bound the latest point does.
+    pub fn priced(&mut self, priced : Duals, lagrangian_bound : Option<f64>, added_columns : bool) {
+        if self.alpha > 0.0 {
+            let improves = match (&self.center, lagrangian_bound) {
+                (Some((_, Some(best))), Some(bound)) => bound > *best,
+                _ => true,
+            };
+            if improves {
+                self.center = Some((priced, lagrangian_bound));
+            }
+        }
+        if !added_columns {
+            self.mis_pricings += 1;
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn duals(value : f64) -> Duals {
+        Duals { site_period : Array2::from_elem((1, 2), value), convexity : vec![value], value }
+    }
+
+    #[test]
+    fn test_smoothing_decreases_with_mis_pricings() {
+        let mut stabilizer = Stabilizer::new(Stabilization::Smoothing(0.5));
+        // there is no center before the first pricing round
+        assert_eq!(stabilizer.smoothing(), 0.0);
+        assert_eq!(stabilizer.duals_to_price(&duals(1.0)).value, 1.0);
+
+        stabilizer.priced(duals(1.0), Some(1.0), true);
+        assert_eq!(stabilizer.smoothing(), 0.5);
+        // every mis-pricing moves the weight of the center by 1 - alpha towards the master duals
+        stabilizer.priced(duals(1.0), Some(1.0), false);
+        assert_eq!(stabilizer.smoothing(), 0.0);
+        let master = duals(3.0);
+        let priced = stabilizer.duals_to_price(&master);
+        assert_eq!((priced.value, priced.convexity.clone(), priced.site_period), (3.0, vec![3.0], master.site_period.clone()));
+
+        // solving the master again starts over at the full weight
+        stabilizer.new_iteration();
+        assert_eq!(stabilizer.smoothing(), 0.5);
+        assert_eq!(stabilizer.duals_to_price(&master).value, 2.0);
+    }
+
+    #[test]
+    fn test_smoothing_reaches_zero() {
+        let mut stabilizer = Stabilizer::new(Stabilization::Smoothing(0.8));
+        stabilizer.priced(duals(0.0), Some(0.0), true);
+        let mut smoothing = vec![stabilizer.smoothing()];
+        for _ in 0..5 {
+            stabilizer.priced(duals(0.0), None, false);
+            smoothing.push(stabilizer.smoothing());
+        }
+        let expected = [0.8, 0.6, 0.4, 0.2, 0.0, 0.0];
+        assert!(smoothing.iter().zip(expected).all(|(actual, expected)| (actual - expected).abs() < 1e-9), "{:?}", smoothing);
+        // the master duals are priced after exactly four mis-pricings
+        assert_eq!(smoothing[4], 0.0);
+    }
+
+    #[test]
+    fn test_center_keeps_the_best_lagrangian_bound() {
+        let mut stabilizer = Stabilizer::new(Stabilization::Smoothing(0.5));
+        let master = duals(0.0);
+        stabilizer.priced(duals(2.0), Some(10.0), true);
+        assert_eq!(stabilizer.duals_to_price(&master).value, 1.0);
+
+        // a worse bound keeps the center, a better one replaces it
+        stabilizer.priced(duals(4.0), Some(8.0), true);
+        assert_eq!(stabilizer.duals_to_price(&master).value, 1.0);
+        stabilizer.priced(duals(6.0), Some(12.0), true);
+        assert_eq!(stabilizer.duals_to_price(&master).value, 3.0);
+
+        // without a bound the latest point becomes the center
+        stabilizer.priced(duals(8.0), None, true);
+        assert_eq!(stabilizer.duals_to_price(&master).value, 4.0);
+    }
+
+    #[test]
+    fn test_no_stabilization() {
+        let mut stabilizer = Stabilizer::new(Stabilization::None);
+        stabilizer.priced(duals(2.0), Some(10.0), true);
+        assert_eq!(stabilizer.smoothing(), 0.0);
+        assert_eq!(stabilizer.duals_to_price(&duals(1.0)).value, 1.0);
+    }
+}
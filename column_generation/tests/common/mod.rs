@@ -0,0 +1,39 @@
+//! Helpers shared by the integration tests.
+#![allow(dead_code)]
+
+use column_generation::Instance;
+use shared::{generate_instance, GeneratorConfig, Shift};
+use tempfile::TempDir;
+
+/// Three vehicles and six sites with a four hour shift of short trips.
+pub fn small_config() -> GeneratorConfig {
+    GeneratorConfig {
+        num_sites: 6,
+        num_vehicles: 3,
+        shifts: vec![Shift { start_minute: 8 * 60, length_minutes: 4 * 60 }],
+        trip_km: (2.0, 6.0),
+        ..GeneratorConfig::default()
+    }
+}
+
+/// A generated instance of three vehicles and six sites with `num_scenarios` scenarios.
+pub fn small_instance(num_scenarios : usize) -> Instance {
+    generated_instance(&small_config(), num_scenarios)
+}
+
+/// An instance generated from `config` with `num_scenarios` scenarios of the same vehicles.
+pub fn generated_instance(config : &GeneratorConfig, num_scenarios : usize) -> Instance {
+    let instance_dir = TempDir::new().unwrap();
+    let dir = instance_dir.path();
+    generate_instance(config).unwrap().write_to_dir(dir).unwrap();
+
+    // further scenarios repeat the generated vehicles, from copies as duplicate files are skipped
+    let mut manifest = std::fs::read_to_string(dir.join("instance.toml")).unwrap();
+    for scenario in 1..num_scenarios {
+        std::fs::copy(dir.join("vehicles.csv"), dir.join(format!("vehicles_{}.csv", scenario))).unwrap();
+        manifest += &format!("\n[[scenarios]]\nname = \"repeated_{0}\"\nvehicles = \"vehicles_{0}.csv\"\ntrips = \"trips.csv\"\n", scenario);
+    }
+    std::fs::write(dir.join("instance.toml"), manifest).unwrap();
+    // the instance owns its data, the files are removed with the directory
+    Instance::load(dir.join("instance.toml").to_str().unwrap()).unwrap()
+}
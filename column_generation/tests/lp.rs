@@ -0,0 +1,128 @@
+use column_generation::lp::{Backend, LpModel, Sense, SolverEnv, SolverParams, Status, VarType, INFINITY};
+
+fn simplex() -> Box<dyn LpModel> {
+    SolverEnv::new(Backend::Simplex, SolverParams::default()).unwrap().model("test")
+}
+
+#[test]
+fn test_simplex_duals() {
+    // max 3x + 5y, x <= 4, 2y <= 12, 3x + 2y <= 18
+    let mut model = simplex();
+    let c1 = model.add_constr("c1", &[], Sense::Less, 4.0);
+    let c2 = model.add_constr("c2", &[], Sense::Less, 12.0);
+    let c3 = model.add_constr("c3", &[], Sense::Less, 18.0);
+    let x = model.add_var("x", VarType::Continuous, -3.0, 0.0, INFINITY, &[(c1, 1.0), (c3, 3.0)]);
+    let y = model.add_var("y", VarType::Continuous, -5.0, 0.0, INFINITY, &[(c2, 2.0), (c3, 2.0)]);
+    assert_eq!(model.optimize(), Status::Optimal);
+    assert!((model.obj_val() + 36.0).abs() < 1e-9);
+    let x_values = model.x(&[x, y]);
+    assert!((x_values[0] - 2.0).abs() < 1e-9 && (x_values[1] - 6.0).abs() < 1e-9);
+    let duals = model.pi(&[c1, c2, c3]);
+    assert!(duals[0].abs() < 1e-9 && (duals[1] + 1.5).abs() < 1e-9 && (duals[2] + 1.0).abs() < 1e-9);
+
+    // relaxing the binding constraint by its dual, the clone keeps the handles
+    let mut relaxed = model.box_clone();
+    relaxed.set_rhs(c3, 19.0);
+    assert_eq!(relaxed.optimize(), Status::Optimal);
+    assert!((relaxed.obj_val() + 37.0).abs() < 1e-9);
+    assert!((model.obj_val() + 36.0).abs() < 1e-9);
+
+    // without y only x is left, bounded by c1
+    model.remove_var(y);
+    assert_eq!(model.optimize(), Status::Optimal);
+    assert!((model.obj_val() + 12.0).abs() < 1e-9);
+    model.remove_constr(c1);
+    assert_eq!(model.optimize(), Status::Optimal);
+    assert!((model.obj_val() + 18.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_simplex_equality_and_bounds() {
+    // min x + y, x + y >= 2, x - y = 0, 0.5 <= x <= 5
+    let mut model = simplex();
+    let x = model.add_var("x", VarType::Continuous, 1.0, 0.5, 5.0, &[]);
+    let y = model.add_var("y", VarType::Continuous, 1.0, 0.0, INFINITY, &[]);
+    let cover = model.add_constr("cover", &[(x, 1.0), (y, 1.0)], Sense::Greater, 2.0);
+    let equal = model.add_constr("equal", &[(x, 1.0), (y, -1.0)], Sense::Equal, 0.0);
+    assert_eq!(model.optimize(), Status::Optimal);
+    assert!((model.obj_val() - 2.0).abs() < 1e-9);
+    let duals = model.pi(&[cover, equal]);
+    assert!((duals[0] - 1.0).abs() < 1e-9 && duals[1].abs() < 1e-9);
+
+    model.set_ub(x, 0.8);
+    assert_eq!(model.optimize(), Status::Infeasible);
+}
+
+#[test]
+fn test_simplex_branch_and_bound() {
+    // max 5a + 4b + 3c over binaries with three knapsack rows
+    let mut model = simplex();
+    let rows : Vec<_> = [5.0, 11.0, 8.0].iter().map(|rhs| model.add_constr("row", &[], Sense::Less, *rhs)).collect();
+    let vars : Vec<_> = [(5.0, [2.0, 4.0, 3.0]), (4.0, [3.0, 1.0, 4.0]), (3.0, [1.0, 2.0, 2.0])].iter()
+        .map(|(value, weights)| {
+            let column : Vec<_> = rows.iter().cloned().zip(weights.iter().cloned()).collect();
+            model.add_var("item", VarType::Binary, -value, 0.0, 1.0, &column)
+        })
+        .collect();
+    assert_eq!(model.optimize(), Status::Optimal);
+    assert!((model.obj_val() + 9.0).abs() < 1e-9);
+    assert_eq!(model.x(&vars), vec![1.0, 1.0, 0.0]);
+    assert_eq!(model.obj_bound(), model.obj_val());
+}
+
+#[test]
+fn test_simplex_degenerate_cycling() {
+    // Beale's example, on which the textbook simplex cycles without an anti-cycling rule
+    let mut model = simplex();
+    let rows : Vec<_> = [0.0, 0.0, 1.0].iter().map(|rhs| model.add_constr("row", &[], Sense::Less, *rhs)).collect();
+    let columns = [
+        (-0.75, [0.25, 0.5, 0.0]),
+        (150.0, [-60.0, -90.0, 0.0]),
+        (-0.02, [-0.04, -0.02, 1.0]),
+        (6.0, [9.0, 3.0, 0.0]),
+    ];
+    let vars : Vec<_> = columns.iter()
+        .map(|(obj, coeffs)| {
+            let column : Vec<_> = rows.iter().cloned().zip(coeffs.iter().cloned()).collect();
+            model.add_var("x", VarType::Continuous, *obj, 0.0, INFINITY, &column)
+        })
+        .collect();
+    assert_eq!(model.optimize(), Status::Optimal);
+    assert!((model.obj_val() + 0.05).abs() < 1e-9);
+    let x = model.x(&vars);
+    assert!((x[0] - 0.04).abs() < 1e-9 && (x[2] - 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_simplex_unbounded() {
+    // min -x, x - y <= 1
+    let mut model = simplex();
+    let x = model.add_var("x", VarType::Continuous, -1.0, 0.0, INFINITY, &[]);
+    let y = model.add_var("y", VarType::Continuous, 0.0, 0.0, INFINITY, &[]);
+    model.add_constr("row", &[(x, 1.0), (y, -1.0)], Sense::Less, 1.0);
+    assert_eq!(model.optimize(), Status::Unbounded);
+}
+
+#[test]
+fn test_simplex_infeasible_integer_program() {
+    // 2x = 1 has the solution 0.5 in the relaxation, but none in integers
+    let mut model = simplex();
+    let x = model.add_var("x", VarType::Integer, 1.0, 0.0, 3.0, &[]);
+    model.add_constr("half", &[(x, 2.0)], Sense::Equal, 1.0);
+    assert_eq!(model.optimize(), Status::Infeasible);
+
+    // without the integrality the same model is solved
+    let mut relaxed = simplex();
+    let x = relaxed.add_var("x", VarType::Continuous, 1.0, 0.0, 3.0, &[]);
+    relaxed.add_constr("half", &[(x, 2.0)], Sense::Equal, 1.0);
+    assert_eq!(relaxed.optimize(), Status::Optimal);
+    assert!((relaxed.obj_val() - 0.5).abs() < 1e-9);
+}
+
+#[test]
+fn test_simplex_time_limit() {
+    let mut model = SolverEnv::new(Backend::Simplex, SolverParams { time_limit : Some(0.0), ..SolverParams::default() }).unwrap().model("test");
+    let x = model.add_var("x", VarType::Binary, -1.0, 0.0, 1.0, &[]);
+    model.add_constr("row", &[(x, 1.0)], Sense::Less, 1.0);
+    assert_eq!(model.optimize(), Status::TimeLimit);
+}
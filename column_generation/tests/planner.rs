@@ -0,0 +1,150 @@
+mod common;
+
+use column_generation::lp::Backend;
+use column_generation::{PlannerConfig, PlanningError, Stabilization};
+use common::{generated_instance, small_config, small_instance};
+use shared::{GeneratorConfig, Horizon, OccupancySchedule, Shift};
+
+#[test]
+fn test_solve_with_simplex() {
+    let instance = small_instance(1);
+    let plan = instance.solve(&PlannerConfig::default().solver(Backend::Simplex).min_num_sites(1)).unwrap();
+    // the licence-free backend solves a small instance end to end
+    assert!(plan.report.scenarios.iter().all(|scenario| scenario.feasible && scenario.infeasible_vehicles == 0));
+    assert!(!plan.report.open_sites.is_empty());
+    assert_eq!(plan.report.cost, plan.report.open_sites.iter().map(|site| u64::from(site.cost)).sum::<u64>());
+    assert!(plan.report.lower_bound <= plan.report.cost as f64 + 1e-6);
+    assert_eq!(plan.timelines.len(), 3);
+}
+
+#[test]
+fn test_parallel_pricing_is_deterministic() {
+    let instance = small_instance(1);
+    let config = PlannerConfig::default().solver(Backend::Simplex).min_num_sites(1);
+    let sequential = instance.solve(&config.clone().pricing_workers(1)).unwrap();
+    let parallel = instance.solve(&config.pricing_workers(4)).unwrap();
+
+    // columns are merged in vehicle order, thus the workers lead to the same plan
+    assert_eq!(sequential.solution.cost, parallel.solution.cost);
+    assert_eq!(sequential.solution.sites_open, parallel.solution.sites_open);
+    assert_eq!(serde_json::to_value(&sequential.report.scenarios).unwrap(), serde_json::to_value(&parallel.report.scenarios).unwrap());
+}
+
+#[test]
+fn test_parallel_scenarios() {
+    let instance = small_instance(3);
+    let config = PlannerConfig::default().solver(Backend::Simplex).min_num_sites(1).activate_all(true);
+    let sequential = instance.solve(&config.clone().scenario_workers(1)).unwrap();
+    let parallel = instance.solve(&config.scenario_workers(3)).unwrap();
+
+    assert_eq!(parallel.report.scenarios.len(), 3);
+    assert!(parallel.report.scenarios.iter().all(|scenario| scenario.active && scenario.feasible));
+    assert_eq!(sequential.solution.cost, parallel.solution.cost);
+    assert_eq!(sequential.solution.sites_open, parallel.solution.sites_open);
+}
+
+#[test]
+fn test_solve_on_worker_thread() {
+    let instance = std::sync::Arc::new(small_instance(1));
+    let config = PlannerConfig::default().solver(Backend::Simplex).min_num_sites(1);
+    let expected = instance.solve(&config).unwrap();
+
+    // the instance owns its data, thus it can be moved into threads and solved there
+    let worker = {
+        let (instance, config) = (instance.clone(), config.clone());
+        std::thread::spawn(move || instance.solve(&config).unwrap())
+    };
+    let plan = worker.join().unwrap();
+    assert_eq!(plan.solution.cost, expected.solution.cost);
+    assert_eq!(plan.solution.sites_open, expected.solution.sites_open);
+}
+
+#[test]
+fn test_dual_smoothing() {
+    let instance = small_instance(1);
+    let config = PlannerConfig::default().solver(Backend::Simplex).min_num_sites(1).record_iterations(true);
+    let plain = instance.solve(&config.clone()).unwrap();
+    let smoothed = instance.solve(&config.clone().stabilization(Stabilization::Smoothing(0.8))).unwrap();
+
+    // smoothing only changes the columns generated on the way, not the optimum of the relaxations
+    assert_eq!(plain.solution.cost, smoothed.solution.cost);
+    assert_eq!(plain.solution.sites_open, smoothed.solution.sites_open);
+    assert!(plain.cg_iterations.iter().all(|iteration| iteration.smoothing == 0.0 && !iteration.mis_priced));
+    assert!(smoothed.cg_iterations.iter().any(|iteration| iteration.smoothing > 0.0));
+    assert_eq!(smoothed.report.statistics.pricing_rounds, smoothed.cg_iterations.len());
+    assert_eq!(smoothed.report.statistics.mis_pricings, smoothed.cg_iterations.iter().filter(|iteration| iteration.mis_priced).count());
+
+    // unless requested the rounds are only counted
+    let unrecorded = instance.solve(&config.stabilization(Stabilization::Smoothing(0.8)).record_iterations(false)).unwrap();
+    assert!(unrecorded.cg_iterations.is_empty());
+    assert_eq!(unrecorded.report.statistics.pricing_rounds, smoothed.report.statistics.pricing_rounds);
+    assert_eq!(unrecorded.report.statistics.mis_pricings, smoothed.report.statistics.mis_pricings);
+}
+
+#[test]
+fn test_outputs_are_written_when_set() {
+    let instance = small_instance(1);
+    let dir = tempfile::TempDir::new().unwrap();
+    let cuts_path = dir.path().join("cuts.txt");
+    let charge_processes = dir.path().join("charge_processes.csv");
+    let config = PlannerConfig::default()
+        .solver(Backend::Simplex)
+        .min_num_sites(1)
+        .cuts_output(cuts_path.to_str().unwrap())
+        .charge_processes_output(charge_processes.to_str().unwrap());
+    let plan = instance.solve(&config).unwrap();
+
+    // the first line of the cuts is the cost they were found for
+    let cuts = std::fs::read_to_string(&cuts_path).unwrap();
+    assert_eq!(cuts.lines().next().unwrap().parse::<u64>().unwrap(), plan.solution.cost as u64);
+    assert!(charge_processes.exists());
+
+    // the cuts are read back in by a later solve, which writes nothing without outputs
+    let mut again = PlannerConfig::default().solver(Backend::Simplex).min_num_sites(1).cuts_input(cuts_path.to_str().unwrap());
+    again.cuts_output = None;
+    let reloaded = instance.solve(&again).unwrap();
+    assert_eq!(reloaded.solution.cost, plan.solution.cost);
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+}
+
+#[test]
+fn test_two_day_horizon() {
+    let horizon = Horizon::new(15, 2 * 1440).unwrap();
+    // the shift lies on the second day, past the periods of a single day, with breaks long enough
+    // to reach a site at the coarser periods
+    let config = GeneratorConfig {
+        shifts: vec![Shift { start_minute: 1440 + 8 * 60, length_minutes: 4 * 60 }],
+        idle_minutes: (60, 120),
+        horizon,
+        ..small_config()
+    };
+    let instance = generated_instance(&config, 1);
+    assert_eq!(instance.horizon(&PlannerConfig::default()), horizon);
+
+    let plan = instance.solve(&PlannerConfig::default().solver(Backend::Simplex).min_num_sites(1)).unwrap();
+    let scenario = &plan.report.scenarios[0];
+    assert!(scenario.feasible);
+    let visits: Vec<_> = scenario.charge_plans.iter().flat_map(|plan| plan.visits.iter().map(move |visit| (plan.vehicle, visit))).collect();
+    assert!(!visits.is_empty());
+    assert!(visits.iter().all(|(_, visit)| visit.period >= 96 && usize::from(visit.period) < horizon.periods));
+
+    // the visits are counted in the rows of their own period, not in the rows of the first day
+    let schedule = OccupancySchedule::new(scenario, &plan.report.open_sites, &horizon);
+    for site in &schedule.sites {
+        assert_eq!(site.vehicles.len(), horizon.periods);
+        assert!(site.vehicles[..96].iter().all(Vec::is_empty));
+        assert!(site.peak() <= usize::from(site.chargers));
+    }
+    for (vehicle, visit) in visits {
+        let site = schedule.sites.iter().find(|site| site.site == visit.site).unwrap();
+        assert_eq!(horizon.capacity_period(&visit.period), usize::from(visit.period));
+        assert!(site.vehicles[horizon.capacity_period(&visit.period)].contains(&vehicle));
+    }
+}
+
+#[test]
+fn test_invalid_settings() {
+    let instance = small_instance(3);
+    let config = PlannerConfig::default().solver(Backend::Simplex).min_num_sites(1).percent_infeasible_allowed(0.5);
+    assert!(matches!(instance.solve(&config), Err(PlanningError::Invalid(_))));
+}
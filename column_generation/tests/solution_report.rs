@@ -0,0 +1,44 @@
+mod common;
+
+use column_generation::lp::Backend;
+use column_generation::PlannerConfig;
+use common::small_instance;
+use shared::relative_gap;
+
+#[test]
+fn test_report_of_solved_instance() {
+    let instance = small_instance(2);
+    let plan = instance.solve(&PlannerConfig::default().solver(Backend::Simplex).min_num_sites(1).activate_all(true)).unwrap();
+    let report = &plan.report;
+
+    assert_eq!(report.cost, plan.solution.cost);
+    assert!(report.lower_bound > 0.0 && report.lower_bound <= report.cost as f64 + 1e-6);
+    assert_eq!(report.gap, relative_gap(report.cost as f64, report.lower_bound));
+
+    // open sites carry the cost of their level
+    assert_eq!(report.open_sites.len(), plan.solution.sites_open.iter().filter(|(size, _)| *size > 0).count());
+    for open_site in &report.open_sites {
+        assert_eq!(instance.sites()[&open_site.id].level_cost(open_site.chargers), Some(open_site.cost));
+    }
+
+    assert_eq!(report.scenarios.iter().map(|scenario| scenario.name.as_str()).collect::<Vec<&str>>(), vec!["generated", "repeated_1"]);
+    assert_eq!(report.active_scenarios, 2);
+    for (result, scenario) in report.scenarios.iter().zip(&instance.data().scenarios) {
+        assert!(result.active && result.feasible);
+        assert_eq!(result.infeasible_vehicles, 0);
+        assert_eq!(result.charge_plans.len(), scenario.vehicles.len());
+
+        // every charging visit is at an open site, during a free segment of the vehicle that can reach it then
+        for charge_plan in &result.charge_plans {
+            let vehicle = scenario.vehicles.iter().find(|vehicle| vehicle.id == charge_plan.vehicle).unwrap();
+            assert_eq!(vehicle.original_id, charge_plan.original_id);
+            for visit in &charge_plan.visits {
+                assert!(report.open_sites.iter().any(|site| site.id == visit.site));
+                let segment = vehicle.tour.iter().find(|segment| segment.id == visit.segment).unwrap();
+                assert!(segment.is_free);
+                let reachable = segment.reachable_sites.iter().find(|reachable| reachable.site.id == visit.site).unwrap();
+                assert!(reachable.arrival_time <= visit.period && visit.period <= reachable.departure_time);
+            }
+        }
+    }
+}
@@ -1,6 +1,9 @@
-use crate::{get_reader};
-
+use crate::charging_curve::ChargingCurve;
+use crate::instance_error::CsvContext;
+use crate::{get_reader, InstanceError};
 
+use indexmap::IndexMap;
+use std::path::Path;
 
 use toml;
 
@@ -11,8 +14,9 @@ pub struct Battery {
     pub min_charge: f64,
     pub initial_charge: f64,
     pub min_final_charge: f64,
-    pub soc_to_time: [f64; 4],
-    pub time_to_soc: [f64; 4],
+    pub charging_curve: ChargingCurve,
+    /// Name of the battery profile, empty for a battery not loaded as a profile.
+    pub profile: String,
 }
 
 
@@ -22,50 +26,123 @@ use std::io::Read;
 use toml::Value;
 
 impl Battery {
-    pub fn load(path: &str) -> Battery {
-        let mut config_toml = String::new();
-
-        let mut file = get_reader(path);
-
-        file.read_to_string(&mut config_toml).unwrap();
-        let parsed_config = config_toml.parse::<Value>().unwrap();
-        let soc_min = parsed_config["SOC_min"].as_float().unwrap();
-        let soc_max = parsed_config["SOC_max"].as_float().unwrap();
-        let soc_initial = parsed_config["SOC_initial"].as_float().unwrap();
-        let soc_final = parsed_config["SOC_final"].as_float().unwrap();
-        let range_in_km = parsed_config["range_in_km"].as_integer().unwrap() as f64;
-        let charging_speed = parsed_config["charging_speed"].as_float().unwrap();
-        let battery_size = parsed_config["battery_size"].as_float().unwrap();
-
-        let time_to_soc_vec: Vec<f64> = parsed_config["time_to_soc"]
-            .as_array()
-            .unwrap()
-            .iter()
-            .map(|e| e.as_float().unwrap())
-            .collect();
-        let soc_to_time_vec: Vec<f64> = parsed_config["soc_to_time"]
-            .as_array()
-            .unwrap()
-            .iter()
-            .map(|e| e.as_float().unwrap())
-            .collect();
-
-        let mut time_to_soc = [0.0; 4];
-        time_to_soc.copy_from_slice(&time_to_soc_vec[..4]);
-        let mut soc_to_time = [0.0; 4];
-        soc_to_time.copy_from_slice(&soc_to_time_vec[..4]);
-
-        Battery::new(
+    pub fn load(path: &str) -> Result<Battery, InstanceError> {
+        Battery::from_config(path, "", &read_config(path)?)
+    }
+
+    /// Loads battery profiles by name. A file describing a single battery becomes a profile named
+    /// after its file stem, a file with one table per battery contributes every table as a profile,
+    /// in alphabetical order.
+    pub fn load_profiles(paths: &[&str]) -> Result<IndexMap<String, Battery>, InstanceError> {
+        let mut profiles = IndexMap::new();
+        for &path in paths {
+            let config = read_config(path)?;
+            let table = config
+                .as_table()
+                .ok_or_else(|| InstanceError::config(path, "", "expected a table"))?;
+
+            let is_single_battery = table.contains_key("SOC_min") || !table.values().all(|v| v.is_table());
+            let batteries = if is_single_battery {
+                let name = Path::new(path)
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .unwrap_or(path)
+                    .trim_end_matches(".toml");
+                vec![(name.to_string(), Battery::from_config(path, "", &config)?)]
+            } else {
+                table
+                    .iter()
+                    .map(|(name, profile)| {
+                        Ok((name.clone(), Battery::from_config(path, &format!("{}.", name), profile)?))
+                    })
+                    .collect::<Result<Vec<(String, Battery)>, InstanceError>>()?
+            };
+
+            for (name, mut battery) in batteries {
+                battery.profile = name.clone();
+                if profiles.insert(name.clone(), battery).is_some() {
+                    return Err(InstanceError::config(path, &name, "duplicate battery profile"));
+                }
+            }
+        }
+        Ok(profiles)
+    }
+
+    /// Reads a battery from `parsed_config`, reporting invalid keys prefixed with `prefix`.
+    fn from_config(path: &str, prefix: &str, parsed_config: &Value) -> Result<Battery, InstanceError> {
+        let error = |key: &str, reason: &str| InstanceError::config(path, &format!("{}{}", prefix, key), reason);
+
+        let float = |key: &str| {
+            parsed_config
+                .get(key)
+                .ok_or_else(|| error(key, "missing"))?
+                .as_float()
+                .ok_or_else(|| error(key, "expected a float"))
+        };
+        let coefficients = |key: &str| -> Result<[f64; 4], InstanceError> {
+            let values = parsed_config
+                .get(key)
+                .ok_or_else(|| error(key, "missing"))?
+                .as_array()
+                .ok_or_else(|| error(key, "expected an array"))?
+                .iter()
+                .map(|e| e.as_float().ok_or_else(|| error(key, "expected an array of floats")))
+                .collect::<Result<Vec<f64>, InstanceError>>()?;
+            if values.len() < 4 {
+                return Err(error(key, "expected 4 coefficients"));
+            }
+            let mut result = [0.0; 4];
+            result.copy_from_slice(&values[..4]);
+            Ok(result)
+        };
+
+        let soc_min = float("SOC_min")?;
+        let soc_max = float("SOC_max")?;
+        let soc_initial = float("SOC_initial")?;
+        let soc_final = float("SOC_final")?;
+        let range_in_km = parsed_config
+            .get("range_in_km")
+            .ok_or_else(|| error("range_in_km", "missing"))?
+            .as_integer()
+            .ok_or_else(|| error("range_in_km", "expected an integer"))?
+            as f64;
+
+        // a tabulated curve given inline or as a csv file takes precedence over the polynomials
+        let charging_curve = if let Some(points) = parsed_config.get("charging_curve") {
+            let invalid_points = || error("charging_curve", "expected an array of [minutes, soc] pairs");
+            let points = points
+                .as_array()
+                .ok_or_else(invalid_points)?
+                .iter()
+                .map(|point| {
+                    match point.as_array().map(|p| p.iter().map(|v| v.as_float()).collect::<Vec<_>>()).as_deref() {
+                        Some([Some(minutes), Some(soc)]) => Ok((*minutes, *soc)),
+                        _ => Err(invalid_points()),
+                    }
+                })
+                .collect::<Result<Vec<(f64, f64)>, InstanceError>>()?;
+            ChargingCurve::tabulated(points).ok_or_else(|| error("charging_curve", INVALID_CURVE))?
+        } else if let Some(file) = parsed_config.get("charging_curve_file") {
+            let file = file
+                .as_str()
+                .ok_or_else(|| error("charging_curve_file", "expected a path"))?;
+            // relative paths are resolved against the battery file
+            let curve_path = Path::new(path).parent().unwrap_or_else(|| Path::new("")).join(file);
+            let curve_path = curve_path.to_str().unwrap();
+            ChargingCurve::tabulated(load_curve_points(curve_path)?)
+                .ok_or_else(|| InstanceError::config(curve_path, "", INVALID_CURVE))?
+        } else {
+            ChargingCurve::polynomial(coefficients("soc_to_time")?, coefficients("time_to_soc")?)
+        };
+
+        Ok(Battery::with_charging_curve(
             soc_min,
             soc_max,
             soc_initial,
             soc_final,
             range_in_km,
-            charging_speed,
-            battery_size,
-            time_to_soc,
-            soc_to_time,
-        )
+            charging_curve,
+        ))
     }
 
     pub fn new(
@@ -78,15 +155,33 @@ impl Battery {
         _battery_size: f64,
         time_to_soc: [f64; 4],
         soc_to_time: [f64; 4],
+    ) -> Self {
+        Battery::with_charging_curve(
+            soc_min,
+            soc_max,
+            soc_initial,
+            soc_final,
+            range_in_km,
+            ChargingCurve::polynomial(soc_to_time, time_to_soc),
+        )
+    }
+
+    pub fn with_charging_curve(
+        soc_min: f64,
+        soc_max: f64,
+        soc_initial: f64,
+        soc_final: f64,
+        range_in_km: f64,
+        charging_curve: ChargingCurve,
     ) -> Self {
         Battery {
             initial_charge: soc_initial,
-            range_in_km: range_in_km,
+            range_in_km,
             min_final_charge: soc_final,
             max_charge: soc_max,
             min_charge: soc_min,
-            time_to_soc,
-            soc_to_time,
+            charging_curve,
+            profile: String::new(),
         }
     }
 
@@ -97,17 +192,9 @@ impl Battery {
 
 
     pub fn get_new_soc_after_charging(&self, current_soc: f64, duration_minutes: u8) -> f64 {
-        let time_index = (((self.soc_to_time[0])
-            .mul_add(current_soc, self.soc_to_time[1]))
-            .mul_add(current_soc, self.soc_to_time[2]))
-            .mul_add(current_soc, self.soc_to_time[3]);
-
-        let new_index = time_index + (duration_minutes as f64);
-
-        let new_soc = (((self.time_to_soc[0])
-            .mul_add(new_index, self.time_to_soc[1]))
-            .mul_add(new_index, self.time_to_soc[2]))
-            .mul_add(new_index, self.time_to_soc[3]);
+        let new_soc = self
+            .charging_curve
+            .soc_after_charging(current_soc, f64::from(duration_minutes));
 
         let new_soc = new_soc
             .min(self.max_charge)
@@ -119,3 +206,36 @@ impl Battery {
         new_soc
     }
 }
+
+const INVALID_CURVE: &str = "charging curve needs at least two points with strictly increasing minutes and soc";
+
+fn read_config(path: &str) -> Result<Value, InstanceError> {
+    let mut config_toml = String::new();
+
+    let mut file = get_reader(path)?;
+
+    file.read_to_string(&mut config_toml)
+        .map_err(|e| InstanceError::io(path, e))?;
+    config_toml
+        .parse::<Value>()
+        .map_err(|e| InstanceError::config(path, "", e.to_string()))
+}
+
+/// Reads `(minutes, soc)` points of a charging curve from a csv file.
+fn load_curve_points(path: &str) -> Result<Vec<(f64, f64)>, InstanceError> {
+    let mut rdr = csv::Reader::from_reader(get_reader(path)?);
+    let ctx = CsvContext::new(path, &mut rdr)?;
+
+    let minutes_column = ctx.column("minutes")?;
+    let soc_column = ctx.column("soc")?;
+
+    let mut points = Vec::new();
+    for result in rdr.records() {
+        let record = ctx.record(result)?;
+        points.push((
+            ctx.parse_field::<f64>(&record, minutes_column)?,
+            ctx.parse_field::<f64>(&record, soc_column)?,
+        ));
+    }
+    Ok(points)
+}
@@ -0,0 +1,71 @@
+use piecewise_linear::PiecewiseLinearFunction;
+use std::convert::TryFrom;
+
+/// Relation between the state of charge and the minutes needed to reach it by charging.
+#[derive(Debug, Clone)]
+pub enum ChargingCurve {
+    /// Cubic polynomials fitted in both directions, coefficients highest order first.
+    Polynomial {
+        soc_to_time: [f64; 4],
+        time_to_soc: [f64; 4],
+    },
+    /// Tabulated curve, linearly interpolated between its points and
+    /// constant beyond the first and last one.
+    PiecewiseLinear {
+        soc_to_time: PiecewiseLinearFunction<f64>,
+        time_to_soc: PiecewiseLinearFunction<f64>,
+    },
+}
+
+impl ChargingCurve {
+    pub fn polynomial(soc_to_time: [f64; 4], time_to_soc: [f64; 4]) -> Self {
+        ChargingCurve::Polynomial {
+            soc_to_time,
+            time_to_soc,
+        }
+    }
+
+    /// Builds a tabulated curve from `(minutes, soc)` points, e.g. taken from a datasheet.
+    /// Returns `None` unless there are at least two points and both minutes and soc are strictly increasing.
+    pub fn tabulated(points: Vec<(f64, f64)>) -> Option<Self> {
+        let soc_to_time =
+            PiecewiseLinearFunction::try_from(points.iter().map(|&(t, s)| (s, t)).collect::<Vec<_>>())
+                .ok()?;
+        let time_to_soc = PiecewiseLinearFunction::try_from(points).ok()?;
+        Some(ChargingCurve::PiecewiseLinear {
+            soc_to_time,
+            time_to_soc,
+        })
+    }
+
+    /// Position on the curve, in minutes, of a battery at `soc`.
+    pub fn time_at_soc(&self, soc: f64) -> f64 {
+        match self {
+            ChargingCurve::Polynomial { soc_to_time, .. } => horner(soc_to_time, soc),
+            ChargingCurve::PiecewiseLinear { soc_to_time, .. } => clamped(soc_to_time, soc),
+        }
+    }
+
+    pub fn soc_at_time(&self, minutes: f64) -> f64 {
+        match self {
+            ChargingCurve::Polynomial { time_to_soc, .. } => horner(time_to_soc, minutes),
+            ChargingCurve::PiecewiseLinear { time_to_soc, .. } => clamped(time_to_soc, minutes),
+        }
+    }
+
+    /// State of charge after charging for `duration_minutes` starting at `current_soc`.
+    /// The result is not limited to the usable range of a battery.
+    pub fn soc_after_charging(&self, current_soc: f64, duration_minutes: f64) -> f64 {
+        self.soc_at_time(self.time_at_soc(current_soc) + duration_minutes)
+    }
+}
+
+fn horner(coefficients: &[f64; 4], x: f64) -> f64 {
+    ((coefficients[0].mul_add(x, coefficients[1])).mul_add(x, coefficients[2]))
+        .mul_add(x, coefficients[3])
+}
+
+fn clamped(function: &PiecewiseLinearFunction<f64>, x: f64) -> f64 {
+    let (min, max) = function.domain();
+    function.y_at_x(x.max(min).min(max)).unwrap()
+}
@@ -0,0 +1,312 @@
+use crate::location::{Location, EARTH_RADIUS_METERS};
+use crate::reachability::format_potential_sites;
+use crate::site::{Site, SiteLevel};
+use crate::{Horizon, Period, Reachability, SiteId};
+
+use csv::Writer;
+use indexmap::IndexMap;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Working hours of a share of the fleet, in minutes since the start of the horizon.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Shift {
+    pub start_minute: u32,
+    pub length_minutes: u32,
+}
+
+/// Parameters of a synthetic instance. Trips alternate between customer trips and free
+/// time in which vehicles may charge, inside the shift of their vehicle.
+#[derive(Debug, Clone)]
+pub struct GeneratorConfig {
+    pub center: Location,
+    pub city_radius_km: f64,
+    pub num_sites: usize,
+    pub num_vehicles: usize,
+    /// Vehicles are assigned to the shifts in turn.
+    pub shifts: Vec<Shift>,
+    /// Range of customer trip lengths in km.
+    pub trip_km: (f64, f64),
+    /// Range of the free time between customer trips in minutes.
+    pub idle_minutes: (u32, u32),
+    pub horizon: Horizon,
+    pub seed: u64,
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        GeneratorConfig {
+            center: Location::new(52.52, 13.405),
+            city_radius_km: 10.0,
+            num_sites: 20,
+            num_vehicles: 10,
+            // an early and a late shift of ten hours
+            shifts: vec![
+                Shift { start_minute: 6 * 60, length_minutes: 10 * 60 },
+                Shift { start_minute: 14 * 60, length_minutes: 10 * 60 },
+            ],
+            trip_km: (2.0, 15.0),
+            idle_minutes: (10, 60),
+            horizon: Horizon::default(),
+            seed: 12345,
+        }
+    }
+}
+
+impl GeneratorConfig {
+    /// Fails for empty or inverted ranges and for shifts without a period within the horizon.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.shifts.is_empty() {
+            return Err("at least one shift is required".to_string());
+        }
+        if !(self.trip_km.0 > 0.0 && self.trip_km.0 <= self.trip_km.1) {
+            return Err(format!("trip lengths {}-{} km are not a positive range", self.trip_km.0, self.trip_km.1));
+        }
+        if self.idle_minutes.0 == 0 || self.idle_minutes.0 > self.idle_minutes.1 {
+            return Err(format!(
+                "idle times {}-{} minutes are not a positive range",
+                self.idle_minutes.0, self.idle_minutes.1
+            ));
+        }
+        for shift in &self.shifts {
+            let (start, end) = self.shift_periods(shift);
+            if start >= end {
+                return Err(format!(
+                    "shift of {} minutes from minute {} has no period within the horizon",
+                    shift.length_minutes, shift.start_minute
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// First and last period of a shift, clamped to the horizon.
+    fn shift_periods(&self, shift: &Shift) -> (u32, u32) {
+        let minutes_per_period = u32::from(self.horizon.minutes_per_period);
+        let last_period = u32::from(self.horizon.end()) - 1;
+        let start = shift.start_minute / minutes_per_period;
+        let end = ((shift.start_minute + shift.length_minutes) / minutes_per_period).min(last_period);
+        (start, end)
+    }
+}
+
+struct GeneratedTrip {
+    id: u32,
+    is_free: bool,
+    start_time: Period,
+    stop_time: Period,
+    start: Location,
+    stop: Location,
+}
+
+/// An instance produced by [`generate_instance`], as written to the instance files.
+pub struct GeneratedInstance {
+    pub sites: IndexMap<SiteId, Site>,
+    trips: Vec<GeneratedTrip>,
+    tours: Vec<Vec<u32>>,
+    reachability: Reachability,
+    horizon: Horizon,
+}
+
+pub fn generate_instance(config: &GeneratorConfig) -> Result<GeneratedInstance, String> {
+    config.validate()?;
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let reachability = Reachability::new(&config.horizon);
+    let minutes_per_period = f64::from(config.horizon.minutes_per_period);
+
+    let sites = (0..config.num_sites)
+        .map(|index| {
+            let id = index as SiteId;
+            let site = Site {
+                id,
+                index,
+                location: random_location(&mut rng, &config.center, config.city_radius_km),
+                capacity: 4,
+                levels: vec![
+                    SiteLevel { chargers: 2, cost: rng.gen_range(60..100) * 100 },
+                    SiteLevel { chargers: 4, cost: rng.gen_range(100..140) * 100 },
+                ],
+            };
+            (id, site)
+        })
+        .collect();
+
+    let mut trips: Vec<GeneratedTrip> = Vec::new();
+    let mut tours = Vec::with_capacity(config.num_vehicles);
+    for vehicle in 0..config.num_vehicles {
+        let shift = config.shifts[vehicle % config.shifts.len()];
+        let (shift_start, shift_end) = config.shift_periods(&shift);
+        let (shift_start, shift_end) = (shift_start as Period, shift_end as Period);
+
+        let mut tour = Vec::new();
+        let mut position = random_location(&mut rng, &config.center, config.city_radius_km);
+        let mut time = shift_start;
+        let mut is_free = false;
+        while time < shift_end {
+            let (stop, periods) = if is_free {
+                let minutes = rng.gen_range(config.idle_minutes.0..=config.idle_minutes.1);
+                let next_pickup = random_location(&mut rng, &config.center, config.city_radius_km);
+                (next_pickup, (f64::from(minutes) / minutes_per_period).ceil().max(1.0) as Period)
+            } else {
+                let km = rng.gen_range(config.trip_km.0..=config.trip_km.1);
+                let stop = random_location_at(&mut rng, &position, km, &config.center, config.city_radius_km);
+                let seconds = reachability.travel(&position, &stop).duration_seconds;
+                (stop, (seconds / 60.0 / minutes_per_period).ceil().max(1.0) as Period)
+            };
+            let stop_time = (time + periods - 1).min(shift_end);
+            let id = trips.len() as u32;
+            trips.push(GeneratedTrip {
+                id,
+                is_free,
+                start_time: time,
+                stop_time,
+                start: position,
+                stop: stop.clone(),
+            });
+            tour.push(id);
+            position = stop;
+            time = stop_time + 1;
+            is_free = !is_free;
+        }
+        tours.push(tour);
+    }
+
+    Ok(GeneratedInstance {
+        sites,
+        trips,
+        tours,
+        reachability,
+        horizon: config.horizon,
+    })
+}
+
+/// A uniformly distributed location within `radius_km` of `center`.
+fn random_location(rng: &mut StdRng, center: &Location, radius_km: f64) -> Location {
+    let distance = radius_km * 1000.0 * rng.gen::<f64>().sqrt();
+    offset(center, distance, rng.gen_range(0.0..std::f64::consts::TAU))
+}
+
+/// A location about `km` from `from` in a random direction, kept within the city.
+fn random_location_at(rng: &mut StdRng, from: &Location, km: f64, center: &Location, radius_km: f64) -> Location {
+    for _ in 0..20 {
+        let candidate = offset(from, km * 1000.0, rng.gen_range(0.0..std::f64::consts::TAU));
+        if center.haversine_distance(&candidate) <= radius_km * 1000.0 {
+            return candidate;
+        }
+    }
+    random_location(rng, center, radius_km)
+}
+
+fn offset(from: &Location, meters: f64, bearing: f64) -> Location {
+    let angle = meters / EARTH_RADIUS_METERS;
+    let lat = f64::from(from.lat).to_radians();
+    let lon = f64::from(from.lon).to_radians();
+    let new_lat = (lat.sin() * angle.cos() + lat.cos() * angle.sin() * bearing.cos()).asin();
+    let new_lon = lon + (bearing.sin() * angle.sin() * lat.cos()).atan2(angle.cos() - lat.sin() * new_lat.sin());
+    // about a meter is precise enough and keeps the files readable
+    let round = |deg: f64| ((deg * 1e5).round() / 1e5) as f32;
+    Location::new(round(new_lat.to_degrees()), round(new_lon.to_degrees()))
+}
+
+impl GeneratedInstance {
+    pub fn write_sites<W: io::Write>(&self, output: W) -> csv::Result<()> {
+        let mut wtr = Writer::from_writer(output);
+        wtr.write_record(["id", "capacity", "levels", "cost_2", "cost_4", "location"])?;
+        for site in self.sites.values() {
+            wtr.write_record(&[
+                format!("s{}", site.id),
+                site.capacity.to_string(),
+                format!("[{}]", site.levels.iter().map(|l| l.chargers.to_string()).collect::<Vec<String>>().join(", ")),
+                site.level_cost(2).map(|cost| cost.to_string()).unwrap_or_default(),
+                site.level_cost(4).map(|cost| cost.to_string()).unwrap_or_default(),
+                format_location(&site.location),
+            ])?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+
+    pub fn write_trips<W: io::Write>(&self, output: W) -> csv::Result<()> {
+        let mut wtr = Writer::from_writer(output);
+        wtr.write_record(["id", "isFree", "startPeriod", "endPeriod", "osmDistance", "startPoint", "endPoint", "potentialSites"])?;
+        for trip in &self.trips {
+            let distance = self.reachability.travel(&trip.start, &trip.stop).distance_meters.round() as u32;
+            // only free trips may be used to charge
+            let reachable = if trip.is_free {
+                self.reachability
+                    .reachable_sites(&self.sites, &trip.start, &trip.stop, trip.start_time, trip.stop_time, distance)
+            } else {
+                Vec::new()
+            };
+            wtr.write_record(&[
+                format!("t{}", trip.id),
+                if trip.is_free { "True" } else { "False" }.to_string(),
+                trip.start_time.to_string(),
+                trip.stop_time.to_string(),
+                format!("{}.0", distance),
+                format_location(&trip.start),
+                format_location(&trip.stop),
+                format_potential_sites(&reachable),
+            ])?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+
+    pub fn write_vehicles<W: io::Write>(&self, output: W) -> csv::Result<()> {
+        let mut wtr = Writer::from_writer(output);
+        wtr.write_record(["id", "trips"])?;
+        for (index, tour) in self.tours.iter().enumerate() {
+            wtr.write_record(&[
+                format!("v{}", index),
+                format!("[{}]", tour.iter().map(|t| format!("t{}", t)).collect::<Vec<String>>().join(", ")),
+            ])?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+
+    /// Writes sites.csv, trips.csv, vehicles.csv, battery.toml and an instance.toml manifest listing them.
+    pub fn write_to_dir(&self, dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        self.write_sites(fs::File::create(dir.join("sites.csv"))?)?;
+        self.write_trips(fs::File::create(dir.join("trips.csv"))?)?;
+        self.write_vehicles(fs::File::create(dir.join("vehicles.csv"))?)?;
+        fs::write(
+            dir.join("battery.toml"),
+            "SOC_min = 0.05
+SOC_max = 0.95
+SOC_initial = 0.50
+SOC_final = 0.50
+range_in_km = 250
+# 50 kW into 40 kWh, slowing down above 80%
+charging_curve = [[0.0, 0.0], [38.4, 0.8], [60.0, 1.0]]
+",
+        )?;
+        fs::write(
+            dir.join("instance.toml"),
+            format!(
+                "sites = \"sites.csv\"
+battery = \"battery.toml\"
+period_length = {}
+horizon = {}
+
+[[scenarios]]
+name = \"generated\"
+vehicles = \"vehicles.csv\"
+trips = \"trips.csv\"
+",
+                self.horizon.minutes_per_period,
+                self.horizon.periods * usize::from(self.horizon.minutes_per_period)
+            ),
+        )?;
+        Ok(())
+    }
+}
+
+fn format_location(location: &Location) -> String {
+    format!("[{}, {}]", location.lat, location.lon)
+}
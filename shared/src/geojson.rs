@@ -0,0 +1,113 @@
+use crate::location::Location;
+use crate::occupancy::OccupancySchedule;
+use crate::segment::Segment;
+use crate::site::Site;
+use crate::solution::SolutionReport;
+use crate::SiteId;
+
+use indexmap::IndexMap;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+// GeoJSON positions are longitude first
+fn position(location: &Location) -> Value {
+    json!([location.lon, location.lat])
+}
+
+/// A GeoJSON FeatureCollection with a point for every candidate site and, if `segments` are given,
+/// a line for every charging stop of the charge plans from the start of its segment via the site to its end.
+///
+/// Sites carry their open level and its cost and, over the given schedules, the number of charging stops,
+/// the highest peak utilisation and the mean idle share. Closed sites have no utilisation.
+pub fn solution_geojson(
+    sites: &IndexMap<SiteId, Site>,
+    report: &SolutionReport,
+    schedules: &[OccupancySchedule],
+    segments: Option<&IndexMap<u32, Arc<Segment>>>,
+) -> Value {
+    let mut features: Vec<Value> = sites
+        .values()
+        .map(|site| {
+            let open = report.open_sites.iter().find(|open| open.id == site.id);
+            let occupancies: Vec<_> = schedules
+                .iter()
+                .flat_map(|schedule| schedule.sites.iter().filter(|occupancy| occupancy.site == site.id))
+                .collect();
+            let mut properties = json!({
+                "kind": "site",
+                "id": format!("s{}", site.id),
+                "capacity": site.capacity,
+                "levels": site.levels.iter().map(|level| level.chargers).collect::<Vec<u8>>(),
+                "open": open.is_some(),
+                "chargers": open.map_or(0, |open| open.chargers),
+                "cost": open.map_or(0, |open| open.cost),
+            });
+            if !occupancies.is_empty() {
+                properties["stops"] = json!(occupancies.iter().map(|occupancy| occupancy.slots.len()).sum::<usize>());
+                properties["peak_occupied"] = json!(occupancies.iter().map(|occupancy| occupancy.peak()).max());
+                properties["peak_utilisation"] =
+                    json!(occupancies.iter().map(|occupancy| occupancy.peak_utilisation()).fold(0.0, f64::max));
+                properties["idle_share"] =
+                    json!(occupancies.iter().map(|occupancy| occupancy.idle_share()).sum::<f64>() / occupancies.len() as f64);
+            }
+            json!({
+                "type": "Feature",
+                "geometry": { "type": "Point", "coordinates": position(&site.location) },
+                "properties": properties,
+            })
+        })
+        .collect();
+
+    if let Some(segments) = segments {
+        for scenario in &report.scenarios {
+            for plan in &scenario.charge_plans {
+                let mut visits: Vec<_> = plan.visits.iter().collect();
+                visits.sort_by_key(|visit| (visit.segment, visit.period));
+                // one line per stop, which covers all periods charged during a segment
+                let mut last_segment = None;
+                for visit in visits {
+                    if last_segment == Some(visit.segment) {
+                        continue;
+                    }
+                    last_segment = Some(visit.segment);
+                    let segment = match segments.get(&visit.segment) {
+                        Some(segment) => segment,
+                        None => continue,
+                    };
+                    let reachable = match segment.reachable_sites.iter().find(|reachable| reachable.site.id == visit.site) {
+                        Some(reachable) => reachable,
+                        None => continue,
+                    };
+                    let charged_periods = plan.visits.iter().filter(|other| other.segment == visit.segment).count();
+                    features.push(json!({
+                        "type": "Feature",
+                        "geometry": {
+                            "type": "LineString",
+                            "coordinates": [
+                                position(&segment.start_location),
+                                position(&reachable.site.resolve(sites).location),
+                                position(&segment.stop_location),
+                            ],
+                        },
+                        "properties": {
+                            "kind": "detour",
+                            "scenario": scenario.name,
+                            "vehicle": plan.vehicle,
+                            "original_id": plan.original_id,
+                            "segment": segment.id,
+                            "site": format!("s{}", visit.site),
+                            "start_period": visit.period,
+                            "charged_periods": charged_periods,
+                            "detour_km": (f64::from(reachable.distance_to + reachable.distance_from) - f64::from(segment.distance)) / 1000.0,
+                        },
+                    }));
+                }
+            }
+        }
+    }
+
+    json!({
+        "type": "FeatureCollection",
+        "features": features,
+    })
+}
@@ -0,0 +1,61 @@
+use crate::Period;
+
+/// Discretisation of the planning horizon into periods of equal length.
+///
+/// Capacity is tracked per period of the horizon. Periods past its end, e.g. of trips
+/// that run over midnight, wrap around to its start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Horizon {
+    pub minutes_per_period: u8,
+    pub periods: usize,
+}
+
+impl Default for Horizon {
+    /// One day of five minute periods.
+    fn default() -> Self {
+        Horizon {
+            minutes_per_period: 5,
+            periods: 288,
+        }
+    }
+}
+
+impl Horizon {
+    /// Builds a horizon spanning `horizon_minutes` in periods of `minutes_per_period`.
+    /// Fails unless the horizon is a positive multiple of the period length and
+    /// all of its periods fit into a `Period`.
+    pub fn new(minutes_per_period: u8, horizon_minutes: u32) -> Result<Self, String> {
+        if minutes_per_period == 0 {
+            return Err("period length must be positive".to_string());
+        }
+        if horizon_minutes == 0 || !horizon_minutes.is_multiple_of(u32::from(minutes_per_period)) {
+            return Err(format!(
+                "horizon of {} minutes is not a positive multiple of the period length of {} minutes",
+                horizon_minutes, minutes_per_period
+            ));
+        }
+        let periods = (horizon_minutes / u32::from(minutes_per_period)) as usize;
+        if periods > Period::MAX as usize / 2 {
+            return Err(format!("horizon of {} periods is too long", periods));
+        }
+        Ok(Horizon {
+            minutes_per_period,
+            periods,
+        })
+    }
+
+    /// First period after the horizon.
+    pub fn end(&self) -> Period {
+        self.periods as Period
+    }
+
+    /// Index of the capacity constraint that `period` is charged against.
+    pub fn capacity_period(&self, period: &Period) -> usize {
+        usize::from(*period) % self.periods
+    }
+
+    /// Number of whole periods that fit into `minutes`.
+    pub fn periods_in(&self, minutes: u16) -> Period {
+        minutes / Period::from(self.minutes_per_period)
+    }
+}
@@ -0,0 +1,130 @@
+use crate::InstanceError;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Bump whenever the layout of a cached type changes, older caches are then rebuilt.
+const CACHE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, PartialEq)]
+struct CacheHeader {
+    version: u32,
+    kind: String,
+    source_sha256: String,
+}
+
+/// Where the caches of instance files go, if caching is enabled.
+enum CacheLocation {
+    /// `trips.csv.gz` is cached in `trips.csv.gz.<kind>.cache`.
+    NextToSource,
+    /// Caches are named `<sha256>.<kind>.cache` in the directory.
+    Dir(PathBuf),
+}
+
+/// Caching is opt-in: `INSTANCE_CACHE_DIR` caches in that directory, `INSTANCE_CACHE=on` next
+/// to the instance files. Without either, instance files are always parsed.
+fn cache_location() -> Option<CacheLocation> {
+    if let Some(dir) = env::var_os("INSTANCE_CACHE_DIR") {
+        return Some(CacheLocation::Dir(PathBuf::from(dir)));
+    }
+    match env::var("INSTANCE_CACHE") {
+        Ok(value) if value == "on" => Some(CacheLocation::NextToSource),
+        _ => None,
+    }
+}
+
+/// Parsed contents of an instance file, cached in a binary file if caching is enabled, see
+/// [`cache_location`].
+///
+/// `parse` is only run if there is no cache whose recorded checksum matches the current
+/// contents of `path`, its result is written back to the cache. Caching is best effort:
+/// an unreadable or unwritable cache falls back to parsing.
+pub(crate) fn cached<T, F>(path: &str, kind: &str, parse: F) -> Result<T, InstanceError>
+where
+    T: Serialize + DeserializeOwned,
+    F: FnOnce() -> Result<T, InstanceError>,
+{
+    match cache_location() {
+        Some(location) => cached_in(&location, path, kind, sha256_of(path)?, parse),
+        None => parse(),
+    }
+}
+
+/// Like [`cached`], but also returns the sha256 checksum of `path`, which is computed once for
+/// both the cache and the caller.
+pub(crate) fn cached_with_sha256<T, F>(path: &str, kind: &str, parse: F) -> Result<(T, String), InstanceError>
+where
+    T: Serialize + DeserializeOwned,
+    F: FnOnce() -> Result<T, InstanceError>,
+{
+    let source_sha256 = sha256_of(path)?;
+    let content = match cache_location() {
+        Some(location) => cached_in(&location, path, kind, source_sha256.clone(), parse)?,
+        None => parse()?,
+    };
+    Ok((content, source_sha256))
+}
+
+fn cached_in<T, F>(location: &CacheLocation, path: &str, kind: &str, source_sha256: String, parse: F) -> Result<T, InstanceError>
+where
+    T: Serialize + DeserializeOwned,
+    F: FnOnce() -> Result<T, InstanceError>,
+{
+    let header = CacheHeader {
+        version: CACHE_VERSION,
+        kind: kind.to_string(),
+        source_sha256,
+    };
+    let cache_path = cache_path(location, path, &header);
+
+    if let Some(content) = read_cache(&cache_path, &header) {
+        return Ok(content);
+    }
+
+    let content = parse()?;
+    write_cache(&cache_path, &header, &content);
+    Ok(content)
+}
+
+pub(crate) fn sha256_of(path: &str) -> Result<String, InstanceError> {
+    let content = fs::read(path).map_err(|e| InstanceError::io(path, e))?;
+    Ok(Sha256::digest(&content)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+fn cache_path(location: &CacheLocation, path: &str, header: &CacheHeader) -> PathBuf {
+    match location {
+        CacheLocation::Dir(dir) => dir.join(format!("{}.{}.cache", header.source_sha256, header.kind)),
+        CacheLocation::NextToSource => PathBuf::from(format!("{}.{}.cache", path, header.kind)),
+    }
+}
+
+fn read_cache<T: DeserializeOwned>(cache_path: &Path, header: &CacheHeader) -> Option<T> {
+    let mut reader = BufReader::new(File::open(cache_path).ok()?);
+    let cached_header: CacheHeader = bincode::deserialize_from(&mut reader).ok()?;
+    if &cached_header != header {
+        return None;
+    }
+    bincode::deserialize_from(&mut reader).ok()
+}
+
+fn write_cache<T: Serialize>(cache_path: &Path, header: &CacheHeader, content: &T) {
+    // write to a temporary file first, concurrent runs must never see a partial cache
+    let tmp_path = cache_path.with_extension(format!("cache.{}", std::process::id()));
+    let written = File::create(&tmp_path).ok().and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        bincode::serialize_into(&mut writer, header).ok()?;
+        bincode::serialize_into(&mut writer, content).ok()?;
+        writer.flush().ok()
+    });
+    if written.is_none() || fs::rename(&tmp_path, cache_path).is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+}
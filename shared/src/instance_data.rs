@@ -0,0 +1,75 @@
+use crate::battery::Battery;
+use crate::manifest::{InstanceManifest, Scenario};
+use crate::segment::Segment;
+use crate::site::Site;
+use crate::vehicle::Vehicle;
+use crate::{InstanceError, SiteId};
+
+use indexmap::IndexMap;
+use std::sync::Arc;
+
+/// The vehicles of one scenario of an instance.
+#[derive(Debug, Clone)]
+pub struct ScenarioData {
+    pub name: String,
+    pub vehicles: Vec<Vehicle>,
+}
+
+/// An instance held in memory. Trips refer to sites by [`SiteRef`](crate::SiteRef) and tours share their
+/// segments, so the data owns everything it refers to and can be sent to other threads or kept
+/// by a long-running process.
+#[derive(Debug, Clone)]
+pub struct InstanceData {
+    pub sites: IndexMap<SiteId, Site>,
+    pub batteries: IndexMap<String, Battery>,
+    /// Trips of all scenarios added so far, by id.
+    pub segments: IndexMap<u32, Arc<Segment>>,
+    pub scenarios: Vec<ScenarioData>,
+}
+
+impl InstanceData {
+    /// Sites and battery profiles of the manifest without any scenario.
+    pub fn new(manifest: &InstanceManifest) -> Result<Self, InstanceError> {
+        Ok(InstanceData {
+            sites: manifest.load_sites()?,
+            batteries: manifest.load_batteries()?,
+            segments: IndexMap::new(),
+            scenarios: Vec::new(),
+        })
+    }
+
+    /// Sites, battery profiles and all scenarios of the manifest. Scenarios repeating the files of
+    /// an earlier one are skipped.
+    pub fn load(manifest: &InstanceManifest) -> Result<Self, InstanceError> {
+        let mut data = InstanceData::new(manifest)?;
+        for (index, scenario) in manifest.scenarios.iter().enumerate() {
+            let repeated = manifest.scenarios[..index]
+                .iter()
+                .any(|other| other.vehicles.path == scenario.vehicles.path && other.trips.path == scenario.trips.path);
+            if !repeated {
+                data.add_scenario(scenario)?;
+            }
+        }
+        Ok(data)
+    }
+
+    /// Loads the trips and vehicles of a scenario. Tours are resolved against the trips of all
+    /// scenarios added so far, a trip given again replaces the earlier one for later scenarios only.
+    pub fn add_scenario(&mut self, scenario: &Scenario) -> Result<&ScenarioData, InstanceError> {
+        self.segments.extend(Segment::load(&self.sites, &scenario.trips.path)?);
+        let vehicles = Vehicle::load(&self.segments, &scenario.vehicles.path, &self.batteries)?;
+        self.scenarios.push(ScenarioData {
+            name: scenario.name.clone(),
+            vehicles,
+        });
+        Ok(self.scenarios.last().unwrap())
+    }
+
+    /// The scenario called `name`, or the first one if no name is given.
+    pub fn scenario(&self, name: Option<&str>) -> Option<&ScenarioData> {
+        match name {
+            Some(name) => self.scenarios.iter().find(|scenario| scenario.name == name),
+            None => self.scenarios.first(),
+        }
+    }
+}
@@ -0,0 +1,329 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+use csv::StringRecord;
+
+use crate::location::Location;
+
+/// Error raised while reading an instance file.
+///
+/// Every variant carries the path of the offending file and, where available,
+/// the line and column so that a broken input can be located without a debugger,
+/// e.g. `trips.csv:1832 potentialSites: unknown site s117`.
+#[derive(Debug)]
+pub enum InstanceError {
+    Io {
+        path: String,
+        source: io::Error,
+    },
+    Csv {
+        path: String,
+        line: Option<u64>,
+        message: String,
+    },
+    MissingColumn {
+        path: String,
+        column: String,
+    },
+    InvalidValue {
+        path: String,
+        line: u64,
+        column: String,
+        value: String,
+        reason: String,
+    },
+    UnknownReference {
+        path: String,
+        line: u64,
+        column: String,
+        kind: &'static str,
+        value: String,
+    },
+    InvalidConfig {
+        path: String,
+        key: String,
+        reason: String,
+    },
+}
+
+impl InstanceError {
+    pub fn path(&self) -> &str {
+        match self {
+            InstanceError::Io { path, .. }
+            | InstanceError::Csv { path, .. }
+            | InstanceError::MissingColumn { path, .. }
+            | InstanceError::InvalidValue { path, .. }
+            | InstanceError::UnknownReference { path, .. }
+            | InstanceError::InvalidConfig { path, .. } => path,
+        }
+    }
+
+    pub(crate) fn io(path: &str, source: io::Error) -> Self {
+        InstanceError::Io {
+            path: path.to_string(),
+            source,
+        }
+    }
+
+    pub(crate) fn csv(path: &str, source: csv::Error) -> Self {
+        InstanceError::Csv {
+            path: path.to_string(),
+            line: source.position().map(|p| p.line()),
+            message: source.to_string(),
+        }
+    }
+
+    pub(crate) fn config(path: &str, key: &str, reason: impl Into<String>) -> Self {
+        InstanceError::InvalidConfig {
+            path: path.to_string(),
+            key: key.to_string(),
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for InstanceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InstanceError::Io { path, source } => write!(f, "{}: {}", path, source),
+            InstanceError::Csv {
+                path,
+                line: Some(line),
+                message,
+            } => write!(f, "{}:{}: {}", path, line, message),
+            InstanceError::Csv {
+                path,
+                line: None,
+                message,
+            } => write!(f, "{}: {}", path, message),
+            InstanceError::MissingColumn { path, column } => {
+                write!(f, "{}: missing column {}", path, column)
+            }
+            InstanceError::InvalidValue {
+                path,
+                line,
+                column,
+                value,
+                reason,
+            } => write!(
+                f,
+                "{}:{} {}: invalid value '{}' ({})",
+                path, line, column, value, reason
+            ),
+            InstanceError::UnknownReference {
+                path,
+                line,
+                column,
+                kind,
+                value,
+            } => write!(f, "{}:{} {}: unknown {} {}", path, line, column, kind, value),
+            InstanceError::InvalidConfig { path, key, reason } if key.is_empty() => {
+                write!(f, "{}: {}", path, reason)
+            }
+            InstanceError::InvalidConfig { path, key, reason } => {
+                write!(f, "{} {}: {}", path, key, reason)
+            }
+        }
+    }
+}
+
+impl Error for InstanceError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            InstanceError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Column lookup and typed field access for one csv input file.
+pub(crate) struct CsvContext<'p> {
+    path: &'p str,
+    headers: StringRecord,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct Column {
+    index: usize,
+}
+
+impl<'p> CsvContext<'p> {
+    pub(crate) fn new<R: io::Read>(
+        path: &'p str,
+        rdr: &mut csv::Reader<R>,
+    ) -> Result<Self, InstanceError> {
+        let headers = rdr
+            .headers()
+            .map_err(|e| InstanceError::csv(path, e))?
+            .clone();
+        Ok(CsvContext { path, headers })
+    }
+
+    pub(crate) fn column(&self, name: &str) -> Result<Column, InstanceError> {
+        self.optional_column(name)
+            .ok_or_else(|| InstanceError::MissingColumn {
+                path: self.path.to_string(),
+                column: name.to_string(),
+            })
+    }
+
+    pub(crate) fn optional_column(&self, name: &str) -> Option<Column> {
+        self.headers
+            .iter()
+            .position(|x| x == name)
+            .map(|index| Column { index })
+    }
+
+    /// All columns whose name starts with `prefix`, along with the remainder of their name.
+    pub(crate) fn columns_with_prefix(&self, prefix: &str) -> Vec<(String, Column)> {
+        self.headers
+            .iter()
+            .enumerate()
+            .filter_map(|(index, name)| {
+                name.strip_prefix(prefix)
+                    .map(|suffix| (suffix.to_string(), Column { index }))
+            })
+            .collect()
+    }
+
+    pub(crate) fn headers(&self) -> &StringRecord {
+        &self.headers
+    }
+
+    pub(crate) fn path(&self) -> &str {
+        self.path
+    }
+
+    /// A copy of `record` with the field of `column` replaced by `value`.
+    pub(crate) fn replace(&self, record: &StringRecord, column: Column, value: &str) -> StringRecord {
+        record
+            .iter()
+            .enumerate()
+            .map(|(index, field)| if index == column.index { value } else { field })
+            .collect()
+    }
+
+    fn column_name(&self, column: Column) -> String {
+        self.headers.get(column.index).unwrap_or_default().to_string()
+    }
+
+    pub(crate) fn record(
+        &self,
+        result: Result<StringRecord, csv::Error>,
+    ) -> Result<StringRecord, InstanceError> {
+        result.map_err(|e| InstanceError::csv(self.path, e))
+    }
+
+    pub(crate) fn line(record: &StringRecord) -> u64 {
+        record.position().map(|p| p.line()).unwrap_or(0)
+    }
+
+    pub(crate) fn get<'r>(
+        &self,
+        record: &'r StringRecord,
+        column: Column,
+    ) -> Result<&'r str, InstanceError> {
+        record
+            .get(column.index)
+            .ok_or_else(|| self.invalid(record, column, "", "field is missing"))
+    }
+
+    /// Parses `value`, which was taken from `column` of `record`, reporting failures against that field.
+    pub(crate) fn parse<T>(
+        &self,
+        record: &StringRecord,
+        column: Column,
+        value: &str,
+    ) -> Result<T, InstanceError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        value
+            .trim()
+            .parse::<T>()
+            .map_err(|e| self.invalid(record, column, value, &e.to_string()))
+    }
+
+    pub(crate) fn parse_field<T>(
+        &self,
+        record: &StringRecord,
+        column: Column,
+    ) -> Result<T, InstanceError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let value = self.get(record, column)?;
+        self.parse(record, column, value)
+    }
+
+    /// Reads a bracketed, comma separated list such as `[1, 2, 4]`.
+    pub(crate) fn parse_list<T>(
+        &self,
+        record: &StringRecord,
+        column: Column,
+    ) -> Result<Vec<T>, InstanceError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let value = self.get(record, column)?;
+        let patterns: &[_] = &['[', ']', ','];
+        value
+            .trim()
+            .trim_matches(patterns)
+            .split(',')
+            .filter(|x| !x.trim().is_empty())
+            .map(|x| self.parse::<T>(record, column, x))
+            .collect()
+    }
+
+    /// Reads a `[lat, lon]` pair.
+    pub(crate) fn parse_location(
+        &self,
+        record: &StringRecord,
+        column: Column,
+    ) -> Result<Location, InstanceError> {
+        let location_points = self.parse_list::<f32>(record, column)?;
+        if location_points.len() != 2 {
+            let value = self.get(record, column)?;
+            return Err(self.invalid(record, column, value, "expected [lat, lon]"));
+        }
+        Ok(Location::new(location_points[0], location_points[1]))
+    }
+
+    pub(crate) fn invalid(
+        &self,
+        record: &StringRecord,
+        column: Column,
+        value: &str,
+        reason: &str,
+    ) -> InstanceError {
+        InstanceError::InvalidValue {
+            path: self.path.to_string(),
+            line: Self::line(record),
+            column: self.column_name(column),
+            value: value.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+
+    pub(crate) fn unknown(
+        &self,
+        record: &StringRecord,
+        column: Column,
+        kind: &'static str,
+        value: String,
+    ) -> InstanceError {
+        InstanceError::UnknownReference {
+            path: self.path.to_string(),
+            line: Self::line(record),
+            column: self.column_name(column),
+            kind,
+            value,
+        }
+    }
+}
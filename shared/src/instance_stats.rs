@@ -0,0 +1,121 @@
+use crate::horizon::Horizon;
+use crate::vehicle::Vehicle;
+use crate::SiteId;
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Driving and energy figures of one vehicle over its tour.
+#[derive(Debug, Clone, Serialize)]
+pub struct VehicleStats {
+    pub id: u32,
+    pub original_id: u32,
+    pub battery: String,
+    pub trips: usize,
+    pub customer_trips: usize,
+    pub driven_km: f64,
+    /// Kilometers the vehicle can drive on a full battery.
+    pub range_km: f64,
+    /// Kilometers it can drive from its initial charge down to its minimum final charge.
+    pub range_without_charging_km: f64,
+    /// Driven distance as a share of the range on a full battery.
+    pub energy_demand: f64,
+    pub feasible_without_charging: bool,
+}
+
+/// Summary of a scenario as computed by [`instance_stats`].
+#[derive(Debug, Clone, Serialize)]
+pub struct InstanceStats {
+    pub scenario: String,
+    pub fleet_size: usize,
+    pub segments: usize,
+    pub free_segments: usize,
+    pub free_segment_share: f64,
+    pub total_driven_km: f64,
+    pub total_energy_demand: f64,
+    pub vehicles_feasible_without_charging: usize,
+    /// Number of free segments by their number of potential sites.
+    pub reachable_sites_per_free_segment: BTreeMap<usize, usize>,
+    /// Number of free segments that can be at a site, per site and period of the horizon.
+    pub site_reachability: BTreeMap<SiteId, Vec<u32>>,
+    pub vehicles: Vec<VehicleStats>,
+}
+
+impl InstanceStats {
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap()
+    }
+}
+
+/// Computes the statistics of the segments driven by `vehicles`, ignoring trips that no
+/// vehicle serves.
+pub fn instance_stats(scenario: &str, vehicles: &[Vehicle], horizon: &Horizon) -> InstanceStats {
+    let mut reachable_sites_per_free_segment = BTreeMap::new();
+    let mut site_reachability: BTreeMap<SiteId, Vec<u32>> = BTreeMap::new();
+    let mut segments = 0;
+    let mut free_segments = 0;
+
+    let vehicles: Vec<VehicleStats> = vehicles
+        .iter()
+        .map(|vehicle| {
+            let mut soc = vehicle.battery_initial_soc();
+            let mut lowest_soc = soc;
+            let mut driven_meters: u64 = 0;
+            for segment in &vehicle.tour {
+                soc = vehicle.get_new_soc_after_distance(soc, segment.distance);
+                lowest_soc = lowest_soc.min(soc);
+                driven_meters += u64::from(segment.distance);
+
+                segments += 1;
+                if !segment.is_free {
+                    continue;
+                }
+                free_segments += 1;
+                *reachable_sites_per_free_segment
+                    .entry(segment.reachable_sites.len())
+                    .or_insert(0) += 1;
+                for visit in &segment.reachable_sites {
+                    let counts = site_reachability
+                        .entry(visit.site.id)
+                        .or_insert_with(|| vec![0; horizon.periods]);
+                    // a visit longer than the horizon still counts once per period
+                    let periods = (visit.arrival_time..=visit.departure_time).take(horizon.periods);
+                    for period in periods {
+                        counts[horizon.capacity_period(&period)] += 1;
+                    }
+                }
+            }
+
+            let range_km = vehicle.battery.range_in_km;
+            let driven_km = driven_meters as f64 / 1000.0;
+            VehicleStats {
+                id: vehicle.id,
+                original_id: vehicle.original_id,
+                battery: vehicle.battery.profile.clone(),
+                trips: vehicle.tour.len(),
+                customer_trips: vehicle.tour.iter().filter(|segment| !segment.is_free).count(),
+                driven_km,
+                range_km,
+                range_without_charging_km: (vehicle.battery_initial_soc() - vehicle.battery_min_final_soc()).max(0.0)
+                    * range_km,
+                energy_demand: driven_km / range_km,
+                feasible_without_charging: lowest_soc >= vehicle.battery_min_soc()
+                    && soc >= vehicle.battery_min_final_soc(),
+            }
+        })
+        .collect();
+
+    InstanceStats {
+        scenario: scenario.to_string(),
+        fleet_size: vehicles.len(),
+        segments,
+        free_segments,
+        free_segment_share: if segments > 0 { free_segments as f64 / segments as f64 } else { 0.0 },
+        total_driven_km: vehicles.iter().map(|vehicle| vehicle.driven_km).sum(),
+        total_energy_demand: vehicles.iter().map(|vehicle| vehicle.energy_demand).sum(),
+        vehicles_feasible_without_charging: vehicles.iter().filter(|vehicle| vehicle.feasible_without_charging).count(),
+        reachable_sites_per_free_segment,
+        site_reachability,
+        vehicles,
+    }
+}
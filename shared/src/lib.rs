@@ -3,8 +3,40 @@
 mod battery;
 pub use battery::Battery;
 
+mod charging_curve;
+pub use charging_curve::ChargingCurve;
+
+mod generator;
+pub use generator::{generate_instance, GeneratedInstance, GeneratorConfig, Shift};
+
+mod geojson;
+pub use geojson::solution_geojson;
+
+mod horizon;
+pub use horizon::Horizon;
+
+mod instance_cache;
+
+mod instance_data;
+pub use instance_data::{InstanceData, ScenarioData};
+
+mod instance_stats;
+pub use instance_stats::{instance_stats, InstanceStats, VehicleStats};
+
+mod instance_error;
+pub use instance_error::InstanceError;
+
+mod manifest;
+pub use manifest::{InstanceFile, InstanceManifest, Scenario};
+
+mod occupancy;
+pub use occupancy::{ChargerSlot, OccupancySchedule, SiteOccupancy};
+
 mod location;
-pub use location::Location;
+pub use location::{BoundingBox, Location, EARTH_RADIUS_METERS};
+
+mod reachability;
+pub use reachability::{Reachability, Travel, TravelMatrix};
 
 mod reachable_site;
 pub use reachable_site::ReachableSite;
@@ -12,11 +44,11 @@ pub use reachable_site::ReachableSite;
 mod segment;
 pub use segment::Segment;
 
-mod segment_index;
-pub use segment_index::SegmentIndex;
+mod spatial_index;
+pub use spatial_index::SiteSpatialIndex;
 
 mod site;
-pub use site::Site;
+pub use site::{Site, SiteLevel, SiteRef};
 mod vehicle;
 
 pub use vehicle::Vehicle;
@@ -30,12 +62,16 @@ mod solution_method;
 pub use solution_method::SolutionMethod;
 
 mod solution;
-pub use solution::Simple;
+pub use solution::{relative_gap, ChargePlan, ChargeVisit, ColumnGenerationIteration, OpenSite, ScenarioResult, Simple, SolutionReport, SolveStatistics};
+
+mod timeline;
+pub use timeline::{TimelineEntry, TimelineKind, VehicleTimeline};
+
+mod validation;
+pub use validation::{validate_instance, IssueKind, ValidationIssue, ValidationReport};
 
 #[cfg(feature = "perf_statistics")]
 mod print_metrics;
-mod vehicle_index;
-pub use vehicle_index::VehicleIndex;
 
 use flate2::read::GzDecoder;
 #[cfg(feature = "perf_statistics")]
@@ -48,18 +84,14 @@ extern crate indexmap;
 extern crate regex;
 
 pub type Period = u16;
-pub const MAX_PERIOD: usize = 288;
-pub const MIN_PER_PERIOD: u8 = 5;
-
-pub fn charge_time_to_capacity_charge_time(charge_time: &Period) -> usize {
-    usize::from(charge_time % (24 * 60 / MIN_PER_PERIOD as u16))
-}
-
-pub fn get_reader(path: &str) -> Box<dyn Read> {
+/// Identifier of a candidate site as given in the instance files.
+pub type SiteId = u16;
+pub fn get_reader(path: &str) -> Result<Box<dyn Read>, InstanceError> {
+    let file = File::open(path).map_err(|e| InstanceError::io(path, e))?;
     if path.ends_with(".gz") {
-        Box::new(GzDecoder::new(File::open(path).unwrap()))
+        Ok(Box::new(GzDecoder::new(file)))
     } else {
-        Box::new(File::open(path).unwrap())
+        Ok(Box::new(file))
     }
 }
 
@@ -4,7 +4,12 @@ use std;
 use std::f32;
 use std::fmt;
 
-#[derive(Clone)]
+use serde::{Deserialize, Serialize};
+
+/// Mean earth radius in meters.
+pub const EARTH_RADIUS_METERS: f64 = 6_371_008.8;
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Location {
     pub lat: f32,
     pub lon: f32,
@@ -22,6 +27,39 @@ impl Location {
     pub fn rad2deg(rad: f32) -> f32 {
         rad * (180.0 / f32::consts::PI)
     }
+
+    fn lat_lon_rad(&self) -> (f64, f64) {
+        (
+            f64::from(Location::deg2rad(self.lat)),
+            f64::from(Location::deg2rad(self.lon)),
+        )
+    }
+
+    /// Great-circle distance in meters.
+    pub fn haversine_distance(&self, other: &Location) -> f64 {
+        let (lat1, lon1) = self.lat_lon_rad();
+        let (lat2, lon2) = other.lat_lon_rad();
+        let a = ((lat2 - lat1) / 2.0).sin().powi(2)
+            + lat1.cos() * lat2.cos() * ((lon2 - lon1) / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS_METERS * a.sqrt().min(1.0).asin()
+    }
+
+    /// Distance in meters on an equirectangular projection around the midpoint of both locations.
+    /// Cheaper than [`Location::haversine_distance`] and close to it within a city.
+    pub fn projected_distance(&self, other: &Location) -> f64 {
+        let (lat1, lon1) = self.lat_lon_rad();
+        let (lat2, lon2) = other.lat_lon_rad();
+        let x = (lon2 - lon1) * ((lat1 + lat2) / 2.0).cos();
+        let y = lat2 - lat1;
+        EARTH_RADIUS_METERS * (x * x + y * y).sqrt()
+    }
+
+    /// Position on the unit sphere. Straight-line distances between these points
+    /// are ordered like great-circle distances.
+    pub(crate) fn unit_vector(&self) -> [f64; 3] {
+        let (lat, lon) = self.lat_lon_rad();
+        [lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin()]
+    }
 }
 
 impl fmt::Debug for Location {
@@ -29,3 +67,61 @@ impl fmt::Debug for Location {
         write!(f, "Location {{ lat: {}, lon: {} }}", self.lat, self.lon)
     }
 }
+
+/// Smallest latitude and longitude range containing a set of locations.
+/// Does not handle ranges across the antimeridian.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min_lat: f32,
+    pub min_lon: f32,
+    pub max_lat: f32,
+    pub max_lon: f32,
+}
+
+impl BoundingBox {
+    /// `None` if there are no locations.
+    pub fn around<'l>(locations: impl IntoIterator<Item = &'l Location>) -> Option<BoundingBox> {
+        locations.into_iter().fold(None, |bounds, location| {
+            Some(match bounds {
+                None => BoundingBox {
+                    min_lat: location.lat,
+                    min_lon: location.lon,
+                    max_lat: location.lat,
+                    max_lon: location.lon,
+                },
+                Some(b) => BoundingBox {
+                    min_lat: b.min_lat.min(location.lat),
+                    min_lon: b.min_lon.min(location.lon),
+                    max_lat: b.max_lat.max(location.lat),
+                    max_lon: b.max_lon.max(location.lon),
+                },
+            })
+        })
+    }
+
+    pub fn contains(&self, location: &Location) -> bool {
+        (self.min_lat..=self.max_lat).contains(&location.lat)
+            && (self.min_lon..=self.max_lon).contains(&location.lon)
+    }
+
+    pub fn center(&self) -> Location {
+        Location::new(
+            (self.min_lat + self.max_lat) / 2.0,
+            (self.min_lon + self.max_lon) / 2.0,
+        )
+    }
+
+    /// Extends the box by `meters` on every side.
+    pub fn expand(&self, meters: f64) -> BoundingBox {
+        let lat_delta = Location::rad2deg((meters / EARTH_RADIUS_METERS) as f32);
+        // a degree of longitude shrinks towards the poles, use the widest latitude of the box
+        let widest = self.min_lat.abs().max(self.max_lat.abs()) + lat_delta;
+        let lon_delta = lat_delta / Location::deg2rad(widest.min(89.0)).cos();
+        BoundingBox {
+            min_lat: (self.min_lat - lat_delta).max(-90.0),
+            min_lon: self.min_lon - lon_delta,
+            max_lat: (self.max_lat + lat_delta).min(90.0),
+            max_lon: self.max_lon + lon_delta,
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use crate::battery::Battery;
+use crate::instance_cache::sha256_of;
+use crate::site::Site;
+use crate::{get_reader, Horizon, InstanceError, SiteId};
+
+use indexmap::IndexMap;
+use std::io::Read;
+use std::path::Path;
+use toml::Value;
+
+/// An input file of an instance, optionally pinned to the sha256 checksum of its contents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstanceFile {
+    pub path: String,
+    pub sha256: Option<String>,
+}
+
+impl InstanceFile {
+    pub fn new(path: &str) -> Self {
+        InstanceFile {
+            path: path.to_string(),
+            sha256: None,
+        }
+    }
+
+    /// Fails if the file has a checksum that does not match its contents.
+    pub fn verify(&self) -> Result<(), InstanceError> {
+        if let Some(expected) = &self.sha256 {
+            let actual = sha256_of(&self.path)?;
+            if !actual.eq_ignore_ascii_case(expected) {
+                return Err(InstanceError::config(
+                    &self.path,
+                    "",
+                    format!("sha256 checksum {} does not match the expected {}", actual, expected),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A pair of vehicles and the trips they drive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scenario {
+    pub name: String,
+    pub vehicles: InstanceFile,
+    pub trips: InstanceFile,
+}
+
+/// All input files of an instance, e.g.
+///
+/// ```toml
+/// sites = "sites.csv.gz"
+/// battery = ["small.toml", { path = "large.toml", sha256 = "9f86d0..." }]
+/// period_length = 5
+/// horizon = 1440
+///
+/// [[scenarios]]
+/// name = "seed"
+/// vehicles = "vehicles_0.csv"
+/// trips = { path = "trips_0.csv.gz", sha256 = "60303a..." }
+/// ```
+///
+/// Relative paths are resolved against the manifest. `period_length` and `horizon` are
+/// given in minutes and are optional.
+#[derive(Debug, Clone)]
+pub struct InstanceManifest {
+    pub sites: InstanceFile,
+    pub batteries: Vec<InstanceFile>,
+    pub horizon: Option<Horizon>,
+    pub scenarios: Vec<Scenario>,
+}
+
+impl InstanceManifest {
+    /// Reads a manifest and verifies the checksums of all files listed in it.
+    pub fn load(path: &str) -> Result<InstanceManifest, InstanceError> {
+        let mut content = String::new();
+        get_reader(path)?
+            .read_to_string(&mut content)
+            .map_err(|e| InstanceError::io(path, e))?;
+        let config = content
+            .parse::<Value>()
+            .map_err(|e| InstanceError::config(path, "", e.to_string()))?;
+
+        let error = |key: &str, reason: &str| InstanceError::config(path, key, reason);
+        let base = Path::new(path).parent().unwrap_or_else(|| Path::new(""));
+
+        let file = |key: &str, value: &Value| -> Result<InstanceFile, InstanceError> {
+            let (file, sha256) = match value {
+                Value::String(file) => (file.as_str(), None),
+                Value::Table(table) => (
+                    table
+                        .get("path")
+                        .and_then(|p| p.as_str())
+                        .ok_or_else(|| error(key, "expected a path"))?,
+                    match table.get("sha256") {
+                        Some(sha256) => Some(
+                            sha256
+                                .as_str()
+                                .ok_or_else(|| error(key, "expected the sha256 checksum as string"))?
+                                .to_string(),
+                        ),
+                        None => None,
+                    },
+                ),
+                _ => return Err(error(key, "expected a path or a table with path and sha256")),
+            };
+            Ok(InstanceFile {
+                path: base.join(file).to_str().unwrap().to_string(),
+                sha256,
+            })
+        };
+        let required = |key: &str| config.get(key).ok_or_else(|| error(key, "missing"));
+
+        let sites = file("sites", required("sites")?)?;
+
+        let batteries = match required("battery")? {
+            Value::Array(batteries) => batteries
+                .iter()
+                .map(|battery| file("battery", battery))
+                .collect::<Result<Vec<InstanceFile>, InstanceError>>()?,
+            battery => vec![file("battery", battery)?],
+        };
+
+        let minutes = |key: &str| -> Result<Option<i64>, InstanceError> {
+            config
+                .get(key)
+                .map(|v| v.as_integer().filter(|&m| m > 0).ok_or_else(|| error(key, "expected a positive number of minutes")))
+                .transpose()
+        };
+        let horizon = match (minutes("period_length")?, minutes("horizon")?) {
+            (None, None) => None,
+            (period_length, horizon) => {
+                let default = Horizon::default();
+                let period_length = u8::try_from(period_length.unwrap_or(i64::from(default.minutes_per_period)))
+                    .map_err(|_| error("period_length", "too long"))?;
+                let horizon = u32::try_from(horizon.unwrap_or(default.periods as i64 * i64::from(default.minutes_per_period)))
+                    .map_err(|_| error("horizon", "too long"))?;
+                Some(Horizon::new(period_length, horizon).map_err(|e| error("horizon", &e))?)
+            }
+        };
+
+        let scenarios = required("scenarios")?
+            .as_array()
+            .ok_or_else(|| error("scenarios", "expected an array of tables"))?
+            .iter()
+            .enumerate()
+            .map(|(index, scenario)| {
+                let key = |name: &str| format!("scenarios[{}].{}", index, name);
+                let entry = |name: &str| scenario.get(name).ok_or_else(|| error(&key(name), "missing"));
+                Ok(Scenario {
+                    name: match scenario.get("name") {
+                        Some(name) => name
+                            .as_str()
+                            .ok_or_else(|| error(&key("name"), "expected a string"))?
+                            .to_string(),
+                        None => index.to_string(),
+                    },
+                    vehicles: file(&key("vehicles"), entry("vehicles")?)?,
+                    trips: file(&key("trips"), entry("trips")?)?,
+                })
+            })
+            .collect::<Result<Vec<Scenario>, InstanceError>>()?;
+        if scenarios.is_empty() {
+            return Err(error("scenarios", "at least one scenario is required"));
+        }
+
+        let manifest = InstanceManifest {
+            sites,
+            batteries,
+            horizon,
+            scenarios,
+        };
+        manifest.verify()?;
+        Ok(manifest)
+    }
+
+    /// Builds a manifest from separate paths, with scenarios given as pairs of vehicles and trips.
+    pub fn from_paths(sites: &str, batteries: &[&str], scenarios: &[(&str, &str)]) -> InstanceManifest {
+        InstanceManifest {
+            sites: InstanceFile::new(sites),
+            batteries: batteries.iter().map(|battery| InstanceFile::new(battery)).collect(),
+            horizon: None,
+            scenarios: scenarios
+                .iter()
+                .map(|&(vehicles, trips)| Scenario {
+                    name: vehicles.to_string(),
+                    vehicles: InstanceFile::new(vehicles),
+                    trips: InstanceFile::new(trips),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn verify(&self) -> Result<(), InstanceError> {
+        self.sites.verify()?;
+        for battery in &self.batteries {
+            battery.verify()?;
+        }
+        for scenario in &self.scenarios {
+            scenario.vehicles.verify()?;
+            scenario.trips.verify()?;
+        }
+        Ok(())
+    }
+
+    /// The scenario called `name`, or the first one if no name is given.
+    pub fn scenario(&self, name: Option<&str>) -> Option<&Scenario> {
+        match name {
+            Some(name) => self.scenarios.iter().find(|scenario| scenario.name == name),
+            None => self.scenarios.first(),
+        }
+    }
+
+    /// Pairs of vehicles and trips paths of all scenarios.
+    pub fn scenario_paths(&self) -> Vec<(&str, &str)> {
+        self.scenarios
+            .iter()
+            .map(|scenario| (scenario.vehicles.path.as_str(), scenario.trips.path.as_str()))
+            .collect()
+    }
+
+    pub fn load_sites(&self) -> Result<IndexMap<SiteId, Site>, InstanceError> {
+        Site::load(&self.sites.path)
+    }
+
+    pub fn load_batteries(&self) -> Result<IndexMap<String, Battery>, InstanceError> {
+        Battery::load_profiles(&self.batteries.iter().map(|battery| battery.path.as_str()).collect::<Vec<&str>>())
+    }
+}
@@ -0,0 +1,255 @@
+use crate::horizon::Horizon;
+use crate::solution::{OpenSite, ScenarioResult};
+use crate::{Period, SiteId};
+
+use csv::Writer;
+use std::fmt::Write as _;
+use std::io;
+
+/// A vehicle using one charger of a site from `start_period` until before `end_period`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChargerSlot {
+    pub charger: usize,
+    pub vehicle: u32,
+    pub segment: u32,
+    pub start_period: Period,
+    pub end_period: Period,
+}
+
+#[derive(Debug, Clone)]
+pub struct SiteOccupancy {
+    pub site: SiteId,
+    pub chargers: u8,
+    /// Vehicles charging in every period of the horizon, later periods wrap around as in the capacity constraints.
+    pub vehicles: Vec<Vec<u32>>,
+    pub slots: Vec<ChargerSlot>,
+}
+
+impl SiteOccupancy {
+    pub fn occupied(&self, period: usize) -> usize {
+        self.vehicles[period].len()
+    }
+
+    pub fn peak(&self) -> usize {
+        self.vehicles.iter().map(Vec::len).max().unwrap_or(0)
+    }
+
+    pub fn peak_utilisation(&self) -> f64 {
+        self.peak() as f64 / f64::from(self.chargers)
+    }
+
+    /// Share of the charger periods of the horizon in which no vehicle charges.
+    pub fn idle_share(&self) -> f64 {
+        let used: usize = self.vehicles.iter().map(Vec::len).sum();
+        1.0 - used as f64 / (f64::from(self.chargers) * self.vehicles.len() as f64)
+    }
+}
+
+/// Occupancy of the open sites by the charge plans of a scenario.
+#[derive(Debug, Clone)]
+pub struct OccupancySchedule {
+    pub scenario: String,
+    pub sites: Vec<SiteOccupancy>,
+}
+
+impl OccupancySchedule {
+    /// Stops are assigned to the chargers of their site first come, first served. A stop that finds
+    /// all chargers busy, which the capacity constraints rule out, gets a charger beyond the last one.
+    pub fn new(scenario: &ScenarioResult, open_sites: &[OpenSite], horizon: &Horizon) -> Self {
+        let mut sites: Vec<SiteOccupancy> = open_sites
+            .iter()
+            .map(|site| SiteOccupancy {
+                site: site.id,
+                chargers: site.chargers,
+                vehicles: vec![Vec::new(); horizon.periods],
+                slots: Vec::new(),
+            })
+            .collect();
+
+        // consecutive periods of a vehicle at a site during a segment are one stop
+        let mut stops: Vec<(usize, u32, u32, Period, Period)> = Vec::new();
+        for plan in &scenario.charge_plans {
+            let mut visits: Vec<_> = plan.visits.iter().collect();
+            visits.sort_by_key(|visit| (visit.segment, visit.period));
+            for visit in visits {
+                let index = match sites.iter().position(|site| site.site == visit.site) {
+                    Some(index) => index,
+                    None => continue,
+                };
+                sites[index].vehicles[horizon.capacity_period(&visit.period)].push(plan.vehicle);
+                match stops.last_mut() {
+                    Some((site, vehicle, segment, _, end))
+                        if *site == index && *vehicle == plan.vehicle && *segment == visit.segment && *end == visit.period =>
+                    {
+                        *end += 1
+                    }
+                    _ => stops.push((index, plan.vehicle, visit.segment, visit.period, visit.period + 1)),
+                }
+            }
+        }
+
+        // chargers are busy in the wrapped periods, as the capacity constraints count them
+        stops.sort_by_key(|&(site, vehicle, _, start, _)| (site, horizon.capacity_period(&start), vehicle));
+        let mut busy: Vec<Vec<Vec<bool>>> = vec![Vec::new(); sites.len()];
+        for (index, vehicle, segment, start_period, end_period) in stops {
+            let periods: Vec<usize> = (start_period..end_period).map(|period| horizon.capacity_period(&period)).collect();
+            let chargers = &mut busy[index];
+            let charger = chargers
+                .iter()
+                .position(|charger| periods.iter().all(|&period| !charger[period]))
+                .unwrap_or_else(|| {
+                    chargers.push(vec![false; horizon.periods]);
+                    chargers.len() - 1
+                });
+            for &period in &periods {
+                chargers[charger][period] = true;
+            }
+            sites[index].slots.push(ChargerSlot {
+                charger,
+                vehicle,
+                segment,
+                start_period,
+                end_period,
+            });
+        }
+
+        OccupancySchedule {
+            scenario: scenario.name.clone(),
+            sites,
+        }
+    }
+
+    /// Writes one row per site and period with the number of occupied chargers and the charging vehicles.
+    pub fn write_occupancy_csv<W: io::Write>(schedules: &[OccupancySchedule], output: W) -> csv::Result<()> {
+        let mut wtr = Writer::from_writer(output);
+        wtr.write_record(["scenario", "site", "period", "chargers", "occupied", "vehicles"])?;
+        for schedule in schedules {
+            for site in &schedule.sites {
+                for (period, vehicles) in site.vehicles.iter().enumerate() {
+                    wtr.write_record(&[
+                        schedule.scenario.clone(),
+                        format!("s{}", site.site),
+                        period.to_string(),
+                        site.chargers.to_string(),
+                        vehicles.len().to_string(),
+                        vehicles.iter().map(u32::to_string).collect::<Vec<String>>().join(";"),
+                    ])?;
+                }
+            }
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+
+    /// Writes one row per charging stop with the charger it is assigned to.
+    pub fn write_slots_csv<W: io::Write>(schedules: &[OccupancySchedule], output: W) -> csv::Result<()> {
+        let mut wtr = Writer::from_writer(output);
+        wtr.write_record(["scenario", "site", "charger", "vehicle", "segment", "start_period", "end_period"])?;
+        for schedule in schedules {
+            for site in &schedule.sites {
+                for slot in &site.slots {
+                    wtr.write_record(&[
+                        schedule.scenario.clone(),
+                        format!("s{}", site.site),
+                        slot.charger.to_string(),
+                        slot.vehicle.to_string(),
+                        slot.segment.to_string(),
+                        slot.start_period.to_string(),
+                        slot.end_period.to_string(),
+                    ])?;
+                }
+            }
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+
+    /// Writes one row per site with its peak utilisation and idle share.
+    pub fn write_summary_csv<W: io::Write>(schedules: &[OccupancySchedule], output: W) -> csv::Result<()> {
+        let mut wtr = Writer::from_writer(output);
+        wtr.write_record(["scenario", "site", "chargers", "stops", "peak_occupied", "peak_utilisation", "idle_share"])?;
+        for schedule in schedules {
+            for site in &schedule.sites {
+                wtr.write_record(&[
+                    schedule.scenario.clone(),
+                    format!("s{}", site.site),
+                    site.chargers.to_string(),
+                    site.slots.len().to_string(),
+                    site.peak().to_string(),
+                    site.peak_utilisation().to_string(),
+                    site.idle_share().to_string(),
+                ])?;
+            }
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+
+    /// File name of the chart of the scenario, characters other than letters, digits, `-` and `_` become `_`.
+    pub fn svg_file_name(&self) -> String {
+        let scenario: String = self
+            .scenario
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+        format!("occupancy_{}.svg", scenario)
+    }
+
+    /// A Gantt chart with one row per charger of every site and one bar per charging stop.
+    pub fn to_svg(&self, horizon: &Horizon) -> String {
+        const PERIOD_WIDTH: usize = 4;
+        const ROW_HEIGHT: usize = 14;
+        const LABEL_WIDTH: usize = 60;
+
+        let rows: Vec<(&SiteOccupancy, usize)> = self
+            .sites
+            .iter()
+            .flat_map(|site| {
+                let chargers = site.slots.iter().map(|slot| slot.charger + 1).max().unwrap_or(0).max(usize::from(site.chargers));
+                (0..chargers).map(move |charger| (site, charger))
+            })
+            .collect();
+        let width = LABEL_WIDTH + PERIOD_WIDTH * horizon.periods;
+        let height = ROW_HEIGHT * (rows.len() + 1);
+
+        let mut svg = String::new();
+        let _ = writeln!(svg, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" font-family="sans-serif" font-size="10">"#, width, height);
+        let _ = writeln!(svg, "<title>Charger occupancy of scenario {}</title>", self.scenario);
+        // a tick every hour
+        let periods_per_hour = (60 / usize::from(horizon.minutes_per_period)).max(1);
+        for period in (0..=horizon.periods).step_by(periods_per_hour) {
+            let x = LABEL_WIDTH + period * PERIOD_WIDTH;
+            let _ = writeln!(svg, r##"<line x1="{x}" y1="0" x2="{x}" y2="{}" stroke="#ddd"/>"##, height, x = x);
+            let _ = writeln!(svg, r#"<text x="{}" y="{}">{}</text>"#, x + 2, height - 3, period * usize::from(horizon.minutes_per_period) / 60);
+        }
+        for (row, (site, charger)) in rows.iter().enumerate() {
+            let y = row * ROW_HEIGHT;
+            let _ = writeln!(svg, r#"<text x="2" y="{}">s{} #{}</text>"#, y + ROW_HEIGHT - 3, site.site, charger);
+            for slot in site.slots.iter().filter(|slot| slot.charger == *charger) {
+                // stops past the end of the horizon wrap around to its start
+                let start = horizon.capacity_period(&slot.start_period);
+                let length = usize::from(slot.end_period - slot.start_period);
+                let overbooked = *charger >= usize::from(site.chargers);
+                for (from, to) in [(start, (start + length).min(horizon.periods)), (0, (start + length).saturating_sub(horizon.periods))] {
+                    if to > from {
+                        let _ = writeln!(
+                            svg,
+                            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"><title>vehicle {}, segment {}, periods {}-{}</title></rect>"#,
+                            LABEL_WIDTH + from * PERIOD_WIDTH,
+                            y + 2,
+                            (to - from) * PERIOD_WIDTH,
+                            ROW_HEIGHT - 4,
+                            if overbooked { "#d62728" } else { "#1f77b4" },
+                            slot.vehicle,
+                            slot.segment,
+                            slot.start_period,
+                            slot.end_period
+                        );
+                    }
+                }
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
@@ -0,0 +1,251 @@
+use crate::instance_error::{CsvContext, InstanceError};
+use crate::location::Location;
+use crate::reachable_site::ReachableSite;
+use crate::segment::Segment;
+use crate::site::Site;
+use crate::{get_reader, Horizon, Period, SiteId};
+
+use csv::Writer;
+use indexmap::IndexMap;
+use std::collections::HashMap;
+use std::io;
+use std::sync::Arc;
+
+/// Driving time and distance from one point to another.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Travel {
+    pub duration_seconds: f64,
+    pub distance_meters: f64,
+}
+
+// locations are matched exactly as they are written in the instance files
+type LocationKey = (u32, u32);
+
+fn location_key(location: &Location) -> LocationKey {
+    (location.lat.to_bits(), location.lon.to_bits())
+}
+
+/// Precomputed driving times and distances between pairs of points, e.g. from an OSRM table query.
+#[derive(Debug, Clone, Default)]
+pub struct TravelMatrix {
+    entries: HashMap<(LocationKey, LocationKey), Travel>,
+}
+
+impl TravelMatrix {
+    /// Reads a csv file with the columns `from`, `to` as `[lat, lon]`, `duration` in seconds
+    /// and `distance` in meters.
+    pub fn load(path: &str) -> Result<TravelMatrix, InstanceError> {
+        let mut rdr = csv::Reader::from_reader(get_reader(path)?);
+        let ctx = CsvContext::new(path, &mut rdr)?;
+
+        let from_column = ctx.column("from")?;
+        let to_column = ctx.column("to")?;
+        let duration_column = ctx.column("duration")?;
+        let distance_column = ctx.column("distance")?;
+
+        let mut entries = HashMap::new();
+        for result in rdr.records() {
+            let record = ctx.record(result)?;
+            let from = ctx.parse_location(&record, from_column)?;
+            let to = ctx.parse_location(&record, to_column)?;
+            let travel = Travel {
+                duration_seconds: ctx.parse_field(&record, duration_column)?,
+                distance_meters: ctx.parse_field(&record, distance_column)?,
+            };
+            if travel.duration_seconds < 0.0 || travel.distance_meters < 0.0 {
+                return Err(ctx.invalid(&record, duration_column, "", "duration and distance must not be negative"));
+            }
+            entries.insert((location_key(&from), location_key(&to)), travel);
+        }
+
+        Ok(TravelMatrix { entries })
+    }
+
+    pub fn get(&self, from: &Location, to: &Location) -> Option<Travel> {
+        self.entries.get(&(location_key(from), location_key(to))).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Derives the potential sites of trips, as the `potentialSites` column of the trips file lists them.
+///
+/// A vehicle may leave for a site at the start of a trip and has to be back at the end of the trip.
+/// Travel comes from the matrix if it has the pair of points, otherwise it is estimated from the
+/// great-circle distance times `detour_factor` at `speed_kmh`.
+#[derive(Debug, Clone)]
+pub struct Reachability {
+    pub matrix: Option<TravelMatrix>,
+    pub detour_factor: f64,
+    pub speed_kmh: f64,
+    /// Longest extra distance in meters to drive via the site instead of the trip itself.
+    pub max_detour_meters: Option<u32>,
+    /// Fewest periods between the arrival at a site and the departure from it. A vehicle arriving
+    /// in the period it has to leave cannot charge, so at least one period is always required.
+    pub min_charging_periods: Period,
+    /// Period grid of the visits. Visits past its end wrap around to its start once.
+    pub horizon: Horizon,
+}
+
+impl Reachability {
+    /// Straight line estimates on the period grid of `horizon`, keeping every site with at least one period to charge.
+    pub fn new(horizon: &Horizon) -> Self {
+        Reachability {
+            matrix: None,
+            detour_factor: 1.3,
+            speed_kmh: 30.0,
+            max_detour_meters: None,
+            min_charging_periods: 1,
+            horizon: *horizon,
+        }
+    }
+
+    pub fn travel(&self, from: &Location, to: &Location) -> Travel {
+        if let Some(travel) = self.matrix.as_ref().and_then(|matrix| matrix.get(from, to)) {
+            return travel;
+        }
+        let distance_meters = from.haversine_distance(to) * self.detour_factor;
+        Travel {
+            duration_seconds: distance_meters / (self.speed_kmh / 3.6),
+            distance_meters,
+        }
+    }
+
+    fn periods(&self, travel: &Travel) -> i64 {
+        (travel.duration_seconds / 60.0 / f64::from(self.horizon.minutes_per_period)).round() as i64
+    }
+
+    /// Sites that can be visited during a trip from `start` in period `start_time` to `stop` in
+    /// period `stop_time`, closest first.
+    pub fn reachable_sites(
+        &self,
+        sites: &IndexMap<SiteId, Site>,
+        start: &Location,
+        stop: &Location,
+        start_time: Period,
+        stop_time: Period,
+        distance: u32,
+    ) -> Vec<ReachableSite> {
+        let mut reachable: Vec<ReachableSite> = sites
+            .values()
+            .filter_map(|site| {
+                let to = self.travel(start, &site.location);
+                let from = self.travel(&site.location, stop);
+                let arrival_time = i64::from(start_time) + self.periods(&to);
+                let departure_time = i64::from(stop_time) - self.periods(&from);
+                let distance_to = to.distance_meters.round() as u32;
+                let distance_from = from.distance_meters.round() as u32;
+
+                if departure_time - arrival_time < i64::from(self.min_charging_periods.max(1))
+                    || arrival_time >= 2 * i64::from(self.horizon.end())
+                {
+                    return None;
+                }
+                if let Some(max_detour) = self.max_detour_meters {
+                    if u64::from(distance_to) + u64::from(distance_from) > u64::from(distance) + u64::from(max_detour) {
+                        return None;
+                    }
+                }
+
+                Some(ReachableSite {
+                    site: site.into(),
+                    arrival_time: arrival_time as Period,
+                    departure_time: departure_time as Period,
+                    distance_to,
+                    distance_from,
+                })
+            })
+            .collect();
+        reachable.sort_by_key(|visit| visit.distance_to);
+        reachable
+    }
+
+    /// Replaces the potential sites of all segments, customer trips get none. Segments shared with
+    /// tours are copied on write.
+    pub fn assign(&self, sites: &IndexMap<SiteId, Site>, segments: &mut IndexMap<u32, Arc<Segment>>) {
+        for segment in segments.values_mut() {
+            let segment = Arc::make_mut(segment);
+            if !segment.is_free {
+                segment.reachable_sites = Vec::new();
+                continue;
+            }
+            segment.reachable_sites = self.reachable_sites(
+                sites,
+                &segment.start_location,
+                &segment.stop_location,
+                segment.start_time,
+                segment.stop_time,
+                segment.distance,
+            );
+        }
+    }
+
+    /// Copies a trips file to `output` with its `potentialSites` column computed for `sites`, empty
+    /// for customer trips. The potential sites of the input are ignored and may refer to sites that
+    /// no longer exist.
+    pub fn rewrite_trips<W: io::Write>(
+        &self,
+        sites: &IndexMap<SiteId, Site>,
+        trips_path: &str,
+        output: W,
+    ) -> Result<(), InstanceError> {
+        let mut rdr = csv::Reader::from_reader(get_reader(trips_path)?);
+        let ctx = CsvContext::new(trips_path, &mut rdr)?;
+
+        let is_free_column = ctx.column("isFree")?;
+        let start_time_column = ctx.column("startPeriod")?;
+        let end_time_column = ctx.column("endPeriod")?;
+        let distance_column = ctx.column("osmDistance")?;
+        let start_point_column = ctx.column("startPoint")?;
+        let end_point_column = ctx.column("endPoint")?;
+        let potential_sites_column = ctx.column("potentialSites")?;
+
+        let write_error = |e: csv::Error| InstanceError::Csv {
+            path: "<output>".to_string(),
+            line: None,
+            message: e.to_string(),
+        };
+        let mut wtr = Writer::from_writer(output);
+        wtr.write_record(ctx.headers()).map_err(write_error)?;
+
+        for result in rdr.records() {
+            let record = ctx.record(result)?;
+            if !ctx.get(&record, is_free_column)?.eq_ignore_ascii_case("true") {
+                wtr.write_record(&ctx.replace(&record, potential_sites_column, "")).map_err(write_error)?;
+                continue;
+            }
+            let reachable = self.reachable_sites(
+                sites,
+                &ctx.parse_location(&record, start_point_column)?,
+                &ctx.parse_location(&record, end_point_column)?,
+                ctx.parse_field(&record, start_time_column)?,
+                ctx.parse_field(&record, end_time_column)?,
+                ctx.parse_field::<f32>(&record, distance_column)?.round() as u32,
+            );
+            wtr.write_record(&ctx.replace(&record, potential_sites_column, &format_potential_sites(&reachable)))
+                .map_err(write_error)?;
+        }
+        wtr.flush().map_err(|e| write_error(e.into()))?;
+        Ok(())
+    }
+}
+
+/// Formats visits as the `potentialSites` column of a trips file.
+pub(crate) fn format_potential_sites(visits: &[ReachableSite]) -> String {
+    visits
+        .iter()
+        .map(|visit| {
+            format!(
+                "s{}[{}|{}|{}|{}]",
+                visit.site.id, visit.arrival_time, visit.distance_to, visit.departure_time, visit.distance_from
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(";")
+}
@@ -1,9 +1,9 @@
-use crate::site::Site;
+use crate::site::SiteRef;
 use crate::Period;
 
 #[derive(Debug,Clone,Copy)]
-pub struct ReachableSite<'a> {
-    pub site: &'a Site,
+pub struct ReachableSite {
+    pub site: SiteRef,
     pub arrival_time: Period,
     pub departure_time: Period,
     pub distance_to: u32,
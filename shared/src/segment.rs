@@ -1,18 +1,23 @@
 use std::cmp::Ordering;
 
 use std::hash::{Hash, Hasher};
+use std::sync::Arc;
 
 use crate::location::Location;
 use crate::reachable_site::ReachableSite;
 use crate::site::Site;
-use crate::{get_reader, Period};
+use crate::instance_cache;
+use crate::instance_error::{Column, CsvContext, InstanceError};
+use crate::{get_reader, Period, SiteId};
 
+use csv::StringRecord;
 use indexmap::IndexMap;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 
 
 #[derive(Debug,Clone)]
-pub struct Segment<'a> {
+pub struct Segment {
     pub id: u32,
 
     pub start_location: Location,
@@ -24,148 +29,206 @@ pub struct Segment<'a> {
 
     pub is_free: bool,
 
-    pub reachable_sites: Vec<ReachableSite<'a>>,
+    pub reachable_sites: Vec<ReachableSite>,
 }
 
-impl<'a> Segment<'a> {
-    pub fn load(taxi_sites: &'a IndexMap<u8, Site>, path: &str) -> IndexMap<u32, Segment<'a>> {
-        let mut trips = IndexMap::default();
-        let mut rdr = csv::Reader::from_reader(get_reader(path));
-        let header_row = rdr.headers().unwrap();
-
-        // get the ids for the relevant columns!
-        let trip_id_column = header_row.iter().position(|x| x == "id").unwrap();
-        let is_free_column = header_row.iter().position(|x| x == "isFree").unwrap();
-        let start_time_column = header_row.iter().position(|x| x == "startPeriod").unwrap();
-        let end_time_column = header_row.iter().position(|x| x == "endPeriod").unwrap();
-        let distance_column = header_row.iter().position(|x| x == "osmDistance").unwrap();
-        let start_point_column = header_row.iter().position(|x| x == "startPoint").unwrap();
-        let end_point_column = header_row.iter().position(|x| x == "endPoint").unwrap();
-        let potential_sites_column = header_row
-            .iter()
-            .position(|x| x == "potentialSites")
-            .unwrap();
-
-        // regex to extract the string of potential sites into sensible object
-        let potential_site_regex =
-            Regex::new(r"^s(\d*)\[(\d*)\|([\d\.]*)\|(\d*)\|([\d\.]*)\]").unwrap();
-
-        for result in rdr.records() {
-            let record = result.unwrap();
-
-            let trip_id = record
-                .get(trip_id_column)
-                .unwrap()
-                .trim_start_matches('t')
-                .parse::<u32>()
-                .expect("Could not parse segment id");
-            let is_free = record.get(is_free_column).unwrap().to_ascii_lowercase() == "true";
-            let start_time = record
-                .get(start_time_column)
-                .unwrap()
-                .parse::<Period>()
-                .expect("Could not parse start time period of segment");
-            let stop_time = record
-                .get(end_time_column)
-                .unwrap()
-                .parse::<Period>()
-                .expect("Could not parse end time period of segment");
-
-            debug_assert!(
-                stop_time >= start_time,
-                "Stop Time is smaller than start in trip {}",
-                trip_id
-            );
-
-            let distance = record
-                .get(distance_column)
-                .unwrap()
-                .parse::<f32>()
-                .expect("Could not parse distance")
-                .round() as u32;
-
-            let start_point = record.get(start_point_column).unwrap();
-            let end_point = record.get(end_point_column).unwrap();
-
-            let potential_sites = record.get(potential_sites_column).unwrap();
-
-            let potential_sites: Vec<ReachableSite> = potential_sites
-                .split(';')
-                .filter(|x| x != &"")
-                .map(|x| x.trim_matches(' '))
-                .map(|x| {
-                    let captures = potential_site_regex.captures_iter(x).next();
-                    match captures {
-                        Some(capture) => {
-                            let site_id: u8 = capture[1]
-                                .parse()
-                                .expect("Could not parse potential site id");
-                            let arrival_period: Period = capture[2]
-                                .parse()
-                                .expect("Could not parse potential arrival period");
-                            let arrival_distance = capture[3]
-                                .parse::<f32>()
-                                .expect("Could not parse potential arrival distance")
-                                .round() as u32;
-                            let departure_period: Period = capture[4]
-                                .parse()
-                                .expect("Could not parse potential departure period");
-                            let departure_distance = capture[5]
-                                .parse::<f32>()
-                                .expect("Could not parse potential departure distance")
-                                .round()
-                                as u32;
-
-                            ReachableSite {
-                                site: taxi_sites.get(&site_id).unwrap(),
-                                arrival_time: arrival_period,
-                                departure_time: departure_period,
-                                distance_to: arrival_distance,
-                                distance_from: departure_distance,
-                            }
-                        }
-                        None => panic!("Invalid Site: {}", x),
-                    }
-                })
-                .collect();
-
-            let patterns: &[_] = &['[', ']', ','];
-            let location_points: Vec<_> = start_point
-                .trim_matches(patterns)
-                .split(',')
-                .map(|x| x.trim_matches(' ').parse::<f32>().unwrap())
-                .collect();
-
-            let start_location = Location::new(location_points[0], location_points[1]);
-
-            let location_points: Vec<_> = end_point
-                .trim_matches(patterns)
-                .split(',')
-                .map(|x| x.trim_matches(' ').parse::<f32>().unwrap())
-                .collect();
-            let stop_location = Location::new(location_points[0], location_points[1]);
-
-            let trip = Segment {
+/// A visit to a potential site as written in the `potentialSites` column, before the site id is resolved.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct ReachableSiteRow {
+    pub site_id: SiteId,
+    pub arrival_time: Period,
+    pub departure_time: Period,
+    pub distance_to: u32,
+    pub distance_from: u32,
+}
+
+/// One parsed row of a trips file together with its position for error reporting.
+/// Rows read from the instance cache come without their record.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct SegmentRow {
+    #[serde(skip)]
+    pub record: StringRecord,
+    pub id: u32,
+    pub start_location: Location,
+    pub stop_location: Location,
+    pub distance: u32,
+    pub start_time: Period,
+    pub stop_time: Period,
+    pub is_free: bool,
+    pub reachable_sites: Vec<ReachableSiteRow>,
+}
+
+/// Reads all rows of a trips file. `on_row` receives every row along with the column
+/// of the potential sites, so that callers can report unresolved site references.
+pub(crate) fn read_segment_rows<F>(path: &str, mut on_row: F) -> Result<(), InstanceError>
+where
+    F: FnMut(&CsvContext, Column, SegmentRow) -> Result<(), InstanceError>,
+{
+    let mut rdr = csv::Reader::from_reader(get_reader(path)?);
+    let ctx = CsvContext::new(path, &mut rdr)?;
+
+    // get the ids for the relevant columns!
+    let trip_id_column = ctx.column("id")?;
+    let is_free_column = ctx.column("isFree")?;
+    let start_time_column = ctx.column("startPeriod")?;
+    let end_time_column = ctx.column("endPeriod")?;
+    let distance_column = ctx.column("osmDistance")?;
+    let start_point_column = ctx.column("startPoint")?;
+    let end_point_column = ctx.column("endPoint")?;
+    let potential_sites_column = ctx.column("potentialSites")?;
+
+    // regex to extract the string of potential sites into sensible object
+    let potential_site_regex =
+        Regex::new(r"^s(\d*)\[(\d*)\|([\d\.]*)\|(\d*)\|([\d\.]*)\]").unwrap();
+
+    for result in rdr.records() {
+        let record = ctx.record(result)?;
+
+        let trip_id = ctx.parse::<u32>(
+            &record,
+            trip_id_column,
+            ctx.get(&record, trip_id_column)?.trim_start_matches('t'),
+        )?;
+        let is_free = ctx.get(&record, is_free_column)?.eq_ignore_ascii_case("true");
+        let start_time = ctx.parse_field::<Period>(&record, start_time_column)?;
+        let stop_time = ctx.parse_field::<Period>(&record, end_time_column)?;
+
+        let distance = ctx.parse_field::<f32>(&record, distance_column)?.round() as u32;
+
+        let start_location = ctx.parse_location(&record, start_point_column)?;
+        let stop_location = ctx.parse_location(&record, end_point_column)?;
+
+        let potential_sites = ctx.get(&record, potential_sites_column)?;
+
+        let reachable_sites = potential_sites
+            .split(';')
+            .filter(|x| x != &"")
+            .map(|x| x.trim_matches(' '))
+            .map(|x| match potential_site_regex.captures(x) {
+                Some(capture) => Ok(ReachableSiteRow {
+                    site_id: ctx.parse(&record, potential_sites_column, &capture[1])?,
+                    arrival_time: ctx.parse(&record, potential_sites_column, &capture[2])?,
+                    distance_to: ctx
+                        .parse::<f32>(&record, potential_sites_column, &capture[3])?
+                        .round() as u32,
+                    departure_time: ctx.parse(&record, potential_sites_column, &capture[4])?,
+                    distance_from: ctx
+                        .parse::<f32>(&record, potential_sites_column, &capture[5])?
+                        .round() as u32,
+                }),
+                None => Err(ctx.invalid(
+                    &record,
+                    potential_sites_column,
+                    x,
+                    "expected s<id>[<arrival>|<distance>|<departure>|<distance>]",
+                )),
+            })
+            .collect::<Result<Vec<ReachableSiteRow>, InstanceError>>()?;
+
+        on_row(
+            &ctx,
+            potential_sites_column,
+            SegmentRow {
+                record,
                 id: trip_id,
-                start_time,
-                stop_time,
-                is_free,
                 start_location,
                 stop_location,
-                reachable_sites: potential_sites,
                 distance,
-            };
+                start_time,
+                stop_time,
+                is_free,
+                reachable_sites,
+            },
+        )?;
+    }
+
+    Ok(())
+}
 
-            trips.insert(trip_id, trip);
+impl Segment {
+    /// Loads the trips of a file, resolving their potential sites against `taxi_sites`. Segments are
+    /// shared by the tours of all vehicles driving them.
+    pub fn load(
+        taxi_sites: &IndexMap<SiteId, Site>,
+        path: &str,
+    ) -> Result<IndexMap<u32, Arc<Segment>>, InstanceError> {
+        let rows: Vec<SegmentRow> = instance_cache::cached(path, "segments", || {
+            let mut rows = Vec::new();
+            read_segment_rows(path, |_ctx, _potential_sites_column, row| {
+                rows.push(row);
+                Ok(())
+            })?;
+            Ok(rows)
+        })?;
+
+        let trips = rows
+            .iter()
+            .map(|row| Segment::from_row(taxi_sites, row).map(|trip| (trip.id, Arc::new(trip))))
+            .collect::<Result<IndexMap<u32, Arc<Segment>>, SiteId>>();
+
+        match trips {
+            Ok(trips) => Ok(trips),
+            // read the file again to report the unknown site with its line
+            Err(_) => Segment::read(taxi_sites, path),
         }
+    }
+
+    fn read(
+        taxi_sites: &IndexMap<SiteId, Site>,
+        path: &str,
+    ) -> Result<IndexMap<u32, Arc<Segment>>, InstanceError> {
+        let mut trips = IndexMap::default();
+
+        read_segment_rows(path, |ctx, potential_sites_column, row| {
+            let trip = Segment::from_row(taxi_sites, &row).map_err(|site_id| {
+                ctx.unknown(&row.record, potential_sites_column, "site", format!("s{}", site_id))
+            })?;
+            trips.insert(row.id, Arc::new(trip));
+            Ok(())
+        })?;
+
+        Ok(trips)
+    }
+
+    /// Resolves the potential sites of a row, fails with the first unknown site id.
+    fn from_row(taxi_sites: &IndexMap<SiteId, Site>, row: &SegmentRow) -> Result<Segment, SiteId> {
+        debug_assert!(
+            row.stop_time >= row.start_time,
+            "Stop Time is smaller than start in trip {}",
+            row.id
+        );
+
+        let reachable_sites = row
+            .reachable_sites
+            .iter()
+            .map(|visit| match taxi_sites.get(&visit.site_id) {
+                Some(site) => Ok(ReachableSite {
+                    site: site.into(),
+                    arrival_time: visit.arrival_time,
+                    departure_time: visit.departure_time,
+                    distance_to: visit.distance_to,
+                    distance_from: visit.distance_from,
+                }),
+                None => Err(visit.site_id),
+            })
+            .collect::<Result<Vec<ReachableSite>, SiteId>>()?;
 
-        trips
+        Ok(Segment {
+            id: row.id,
+            start_time: row.start_time,
+            stop_time: row.stop_time,
+            is_free: row.is_free,
+            start_location: row.start_location.clone(),
+            stop_location: row.stop_location.clone(),
+            reachable_sites,
+            distance: row.distance,
+        })
     }
 }
 
-impl<'a> Eq for Segment<'a> {}
+impl Eq for Segment {}
 
-impl<'a> Hash for Segment<'a> {
+impl Hash for Segment {
     fn hash<H>(&self, state: &mut H)
     where
         H: Hasher,
@@ -175,19 +238,19 @@ impl<'a> Hash for Segment<'a> {
     }
 }
 
-impl<'a> PartialEq for Segment<'a> {
+impl PartialEq for Segment {
     fn eq(&self, other: &Segment) -> bool {
         self.id == other.id
     }
 }
 
-impl<'a> Ord for Segment<'a> {
+impl Ord for Segment {
     fn cmp(&self, other: &Segment) -> Ordering {
         self.id.cmp(&other.id)
     }
 }
 
-impl<'a> PartialOrd for Segment<'a> {
+impl PartialOrd for Segment {
     fn partial_cmp(&self, other: &Segment) -> Option<Ordering> {
         Some(self.cmp(other))
     }
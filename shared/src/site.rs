@@ -3,18 +3,54 @@ use indexmap::IndexMap;
 
 use std::hash::{Hash, Hasher};
 
-use crate::get_reader;
+use crate::{get_reader, SiteId};
+use crate::instance_cache;
+use crate::instance_error::{Column, CsvContext, InstanceError};
 
+use serde::{Deserialize, Serialize};
 
 
-#[derive(Debug, Clone)]
+
+/// Size levels used when the sites file does not list any.
+const LEGACY_LEVELS: [u8; 2] = [2, 4];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Site {
-    pub id: u8,
+    pub id: SiteId,
     pub index: usize,
     pub location: Location,
-    pub cost_4: u8,
-    pub cost_2: u8,
     pub capacity: u8,
+    /// Sizes the site can be opened at, ascending by number of chargers.
+    pub levels: Vec<SiteLevel>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SiteLevel {
+    pub chargers: u8,
+    pub cost: u32,
+}
+
+/// A site referred to by its id and its position in the sites file, resolved against the
+/// sites of the instance with [`SiteRef::resolve`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SiteRef {
+    pub id: SiteId,
+    pub index: usize,
+}
+
+impl SiteRef {
+    pub fn resolve(self, sites: &IndexMap<SiteId, Site>) -> &Site {
+        &sites[&self.id]
+    }
+}
+
+impl From<&Site> for SiteRef {
+    fn from(site: &Site) -> Self {
+        SiteRef {
+            id: site.id,
+            index: site.index,
+        }
+    }
 }
 
 impl Hash for Site {
@@ -22,7 +58,7 @@ impl Hash for Site {
     where
         H: Hasher,
     {
-        state.write_u8(self.id);
+        state.write_u16(self.id);
         state.finish();
     }
 }
@@ -36,71 +72,163 @@ impl PartialEq for Site {
 }
 
 impl Site {
-    pub fn load(path: &str) -> IndexMap<u8, Site> {
-        // read taxi sites
-        let mut rdr = csv::Reader::from_reader(get_reader(path));
-
-        let mut taxi_sites: IndexMap<u8, Site> = IndexMap::default();
-
-        let mut index_counter = 0;
-        //let mut charger_in_use = Vec::new();
+    /// Cost of opening the site with `chargers` chargers, zero if closed and `None` if the site
+    /// has no level of that size.
+    pub fn level_cost(&self, chargers: u8) -> Option<u32> {
+        if chargers == 0 {
+            return Some(0);
+        }
+        self.levels.iter().find(|l| l.chargers == chargers).map(|l| l.cost)
+    }
 
-        let header_row = rdr.headers().unwrap();
+    /// The next larger size level after `chargers`, if any.
+    pub fn next_level(&self, chargers: u8) -> Option<u8> {
+        self.levels
+            .iter()
+            .map(|l| l.chargers)
+            .find(|&c| c > chargers)
+    }
 
-        // get the ids for the relevant columns!
-        let site_id_column = header_row.iter().position(|x| x == "id").unwrap();
-        let capacity_column = header_row.iter().position(|x| x == "capacity").unwrap();
-        let cost_column = header_row.iter().position(|x| x == "cost").unwrap();
-        let location_column = header_row.iter().position(|x| x == "location").unwrap();
+    /// The largest size level that does not exceed `chargers`, zero if there is none.
+    pub fn level_at_most(&self, chargers: u8) -> u8 {
+        self.levels
+            .iter()
+            .rev()
+            .map(|l| l.chargers)
+            .find(|&c| c <= chargers)
+            .unwrap_or(0)
+    }
 
-        for result in rdr.records() {
-            // Notice that we need to provide a type hint for automatic
-            // deserialization.
-            let record = result.unwrap();
+    pub fn max_level(&self) -> u8 {
+        self.levels.last().map(|l| l.chargers).unwrap_or(0)
+    }
 
-            let site_id = record
-                .get(site_id_column)
-                .unwrap()
-                .trim_start_matches('s')
-                .parse::<u8>()
-                .unwrap();
-            let capacity = record.get(capacity_column).unwrap().parse::<u8>().unwrap();
+    pub fn load(path: &str) -> Result<IndexMap<SiteId, Site>, InstanceError> {
+        let sites: Vec<Site> = instance_cache::cached(path, "sites", || {
+            Ok(Site::read(path)?.into_values().collect())
+        })?;
+        Ok(sites.into_iter().map(|site| (site.id, site)).collect())
+    }
 
-            assert!(capacity == 4 || capacity == 2);
+    fn read(path: &str) -> Result<IndexMap<SiteId, Site>, InstanceError> {
+        // read taxi sites
+        let mut rdr = csv::Reader::from_reader(get_reader(path)?);
+        let ctx = CsvContext::new(path, &mut rdr)?;
 
-            let cost = record.get(cost_column).unwrap().parse::<u8>().unwrap();
-            let location = record.get(location_column).unwrap();
+        let mut taxi_sites: IndexMap<SiteId, Site> = IndexMap::default();
 
-            let patterns: &[_] = &['[', ']'];
-            let location_points: Vec<_> = location
-                .trim_matches(patterns)
-                .split(',')
-                .map(|x| x.trim_matches(' ').parse::<f32>().unwrap())
-                .collect();
-            let location = Location::new(location_points[0], location_points[1]);
+        let mut index_counter = 0;
+        //let mut charger_in_use = Vec::new();
 
+        // get the ids for the relevant columns!
+        let site_id_column = ctx.column("id")?;
+        let capacity_column = ctx.column("capacity")?;
+        let location_column = ctx.column("location")?;
+        let levels_column = ctx.optional_column("levels");
+        // base cost for the legacy cost derivation, only needed if a level has no explicit cost
+        let cost_column = ctx.optional_column("cost");
+        // explicit cost per size level, e.g. cost_4 for the level with four chargers
+        let level_cost_columns: Vec<(u8, Column)> = ctx
+            .columns_with_prefix("cost_")
+            .into_iter()
+            .filter_map(|(chargers, column)| chargers.parse::<u8>().ok().map(|c| (c, column)))
+            .collect();
 
-            // legacy cost: Originally assumed that
-            // cost is base and each charge point is + 10%
-            // this restores this assumption
-            let old_charger_cost = (f32::from(cost) * 0.1).round() as u8;
+        for result in rdr.records() {
+            let record = ctx.record(result)?;
+
+            let site_id_value = ctx.get(&record, site_id_column)?;
+            let site_id = ctx.parse::<SiteId>(&record, site_id_column, site_id_value.trim_start_matches('s'))?;
+            let capacity = ctx.parse_field::<u8>(&record, capacity_column)?;
+
+            if capacity == 0 {
+                return Err(ctx.invalid(&record, capacity_column, &capacity.to_string(), "capacity must be positive"));
+            }
+
+            let location = ctx.parse_location(&record, location_column)?;
+
+            let (chargers, legacy_ladder) = match levels_column {
+                Some(column) if !ctx.get(&record, column)?.trim().is_empty() => {
+                    let chargers = ctx.parse_list::<u8>(&record, column)?;
+                    if chargers.is_empty() || chargers[0] == 0 || chargers.windows(2).any(|w| w[0] >= w[1]) {
+                        return Err(ctx.invalid(&record, column, ctx.get(&record, column)?,
+                                               "levels must be positive and strictly increasing"));
+                    }
+                    if chargers[chargers.len() - 1] > capacity {
+                        return Err(ctx.invalid(&record, column, ctx.get(&record, column)?,
+                                               "levels must not exceed the capacity"));
+                    }
+                    (chargers, false)
+                }
+                _ => {
+                    // a site smaller than the full size only offers the legacy levels that fit
+                    let chargers: Vec<u8> = LEGACY_LEVELS.iter().copied().filter(|&c| c <= capacity).collect();
+                    if chargers.is_empty() {
+                        return Err(ctx.invalid(&record, capacity_column, &capacity.to_string(),
+                                               "capacity is below the smallest legacy level"));
+                    }
+                    (chargers, true)
+                }
+            };
 
-            let cost_4 = cost + 4 * old_charger_cost;
-            let cost_2 = (f32::from(cost_4) * 0.67).round() as u8;
+            let mut levels = Vec::with_capacity(chargers.len());
+            for c in chargers {
+                let level_cost_column = level_cost_columns.iter().find(|(chargers, _)| *chargers == c).map(|(_, column)| *column);
+                let explicit_cost = match level_cost_column {
+                    Some(column) if !ctx.get(&record, column)?.trim().is_empty() => {
+                        Some(ctx.parse_field::<u32>(&record, column)?)
+                    }
+                    _ => None,
+                };
+
+                let cost = match (explicit_cost, cost_column, level_cost_column) {
+                    (Some(cost), _, _) => cost,
+                    (None, Some(cost_column), _) => {
+                        legacy_level_cost(ctx.parse_field::<u32>(&record, cost_column)?, c, legacy_ladder)
+                    }
+                    (None, None, Some(column)) => {
+                        return Err(ctx.invalid(&record, column, "", "empty cost and no cost column"));
+                    }
+                    (None, None, None) => {
+                        return Err(InstanceError::MissingColumn {
+                            path: path.to_string(),
+                            column: format!("cost_{}", c),
+                        });
+                    }
+                };
+                levels.push(SiteLevel { chargers: c, cost });
+            }
 
             let site = Site {
                 id: site_id,
                 index: index_counter,
                 location,
-                cost_4,
-                cost_2 ,
                 capacity,
+                levels,
             };
 
-            taxi_sites.insert(site_id, site);
+            if taxi_sites.insert(site_id, site).is_some() {
+                return Err(ctx.invalid(&record, site_id_column, site_id_value, "duplicate site id"));
+            }
             index_counter += 1;
         }
 
-        taxi_sites
+        Ok(taxi_sites)
+    }
+}
+
+/// Cost of a level derived from the single base cost of a site.
+fn legacy_level_cost(cost: u32, chargers: u8, legacy_ladder: bool) -> u32 {
+    // legacy cost: Originally assumed that
+    // cost is base and each charge point is + 10%
+    // this restores this assumption
+    let old_charger_cost = (cost as f32 * 0.1).round() as u32;
+
+    if legacy_ladder && chargers == LEGACY_LEVELS[0] {
+        // the half size is priced at 67% of the full size
+        let cost_4 = cost + u32::from(LEGACY_LEVELS[1]) * old_charger_cost;
+        (cost_4 as f32 * 0.67).round() as u32
+    } else {
+        cost + u32::from(chargers) * old_charger_cost
     }
 }
@@ -1,4 +1,122 @@
+use crate::{Period, SiteId};
+
+use serde::Serialize;
+use std::fs::File;
+use std::io;
+use std::io::BufWriter;
+
+#[derive(Debug, Clone)]
 pub struct Simple {
     pub cost: u64,
     pub sites_open: Vec<(u8, usize)>,
 }
+
+/// A site opened by a solution, at one of its levels.
+#[derive(Debug, Clone, Serialize)]
+pub struct OpenSite {
+    pub id: SiteId,
+    pub chargers: u8,
+    pub cost: u32,
+}
+
+/// A vehicle charging at a site during one of its segments, from the given period on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChargeVisit {
+    pub segment: u32,
+    pub site: SiteId,
+    pub period: Period,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChargePlan {
+    pub vehicle: u32,
+    pub original_id: u32,
+    pub visits: Vec<ChargeVisit>,
+}
+
+/// How the open sites fare in one scenario. Charge plans are only known for feasible scenarios.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScenarioResult {
+    pub name: String,
+    pub active: bool,
+    pub feasible: bool,
+    pub infeasible_vehicles: usize,
+    pub charge_plans: Vec<ChargePlan>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SolveStatistics {
+    /// Number of times the master problem was solved.
+    pub iterations: usize,
+    pub cuts: usize,
+    pub columns: usize,
+    /// Number of times further scenarios were activated.
+    pub scenario_generations: usize,
+    /// Pricing rounds of the column generation over all scenarios and branch nodes.
+    pub pricing_rounds: usize,
+    /// Pricing rounds at stabilised duals that did not yield a column.
+    pub mis_pricings: usize,
+    pub runtime_seconds: f64,
+}
+
+/// A pricing round of the column generation, recorded to compare the convergence of settings.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ColumnGenerationIteration {
+    /// Position of the scenario in the report.
+    pub scenario: usize,
+    /// Column generation run of the scenario, one for every solved branch node.
+    pub run: usize,
+    /// Solves of the master problem within the run, repeated by mis-pricings.
+    pub iteration: usize,
+    pub master_objective: f64,
+    /// Lagrangian bound at the priced duals, unknown if vehicles were not priced.
+    pub lagrangian_bound: Option<f64>,
+    /// Weight of the stability center in the priced duals.
+    pub smoothing: f64,
+    pub columns_added: usize,
+    pub mis_priced: bool,
+}
+
+impl ColumnGenerationIteration {
+    pub fn write_csv<W: io::Write>(iterations: &[ColumnGenerationIteration], output: W) -> csv::Result<()> {
+        let mut wtr = csv::Writer::from_writer(output);
+        for iteration in iterations {
+            wtr.serialize(iteration)?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+}
+
+/// Everything known about a solution, written as a single json file.
+#[derive(Debug, Clone, Serialize)]
+pub struct SolutionReport {
+    pub cost: u64,
+    pub lower_bound: f64,
+    /// Relative gap between `cost` and `lower_bound`.
+    pub gap: f64,
+    pub open_sites: Vec<OpenSite>,
+    pub active_scenarios: usize,
+    pub statistics: SolveStatistics,
+    pub scenarios: Vec<ScenarioResult>,
+}
+
+impl SolutionReport {
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap()
+    }
+
+    pub fn write(&self, path: &str) -> io::Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(writer, self).map_err(io::Error::from)
+    }
+}
+
+/// Relative gap of an upper bound to a lower bound, zero if both are zero.
+pub fn relative_gap(upper_bound: f64, lower_bound: f64) -> f64 {
+    if upper_bound == 0.0 {
+        0.0
+    } else {
+        ((upper_bound - lower_bound) / upper_bound).max(0.0)
+    }
+}
@@ -1,14 +1,14 @@
-use crate::{Segment, Simple, Site, Vehicle};
+use crate::{Segment, Simple, Site, SiteId, Vehicle};
 use indexmap::IndexMap;
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 
 pub trait SolutionMethod {
-    fn run<'a>(
+    fn run(
         &self,
-        sites: &'a IndexMap<u8, Site>,
-        segments: &'a IndexMap<u32, Segment<'a>>,
-        vehicles: &'a [Vehicle<'a>],
+        sites: &IndexMap<SiteId, Site>,
+        segments: &IndexMap<u32, Arc<Segment>>,
+        vehicles: &[Vehicle],
         should_stop: Arc<AtomicBool>,
     ) -> Simple;
 }
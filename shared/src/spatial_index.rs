@@ -0,0 +1,125 @@
+use crate::location::{Location, EARTH_RADIUS_METERS};
+use crate::site::Site;
+use crate::SiteId;
+
+use indexmap::IndexMap;
+use noisy_float::types::{n64, N64};
+use std::collections::BinaryHeap;
+
+/// Nearest neighbour and radius lookups of sites by great-circle distance.
+///
+/// A k-d tree over the positions of the sites on the unit sphere, where straight-line
+/// distances are ordered like great-circle distances, so results are exact.
+pub struct SiteSpatialIndex<'a> {
+    // a balanced tree stored in place: the median of every range splits it on axis depth % 3
+    nodes: Vec<([f64; 3], &'a Site)>,
+}
+
+impl<'a> SiteSpatialIndex<'a> {
+    pub fn new(sites: &'a IndexMap<SiteId, Site>) -> Self {
+        let mut nodes: Vec<([f64; 3], &'a Site)> = sites
+            .values()
+            .map(|site| (site.location.unit_vector(), site))
+            .collect();
+        build(&mut nodes, 0);
+        SiteSpatialIndex { nodes }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// The `k` sites closest to `location` with their distance in meters, closest first.
+    pub fn nearest(&self, location: &Location, k: usize) -> Vec<(&'a Site, f64)> {
+        let target = location.unit_vector();
+        // max heap of the best candidates so far by squared chord length
+        let mut best: BinaryHeap<(N64, usize)> = BinaryHeap::with_capacity(k + 1);
+        if k > 0 {
+            self.search_nearest(&target, 0, self.nodes.len(), 0, k, &mut best);
+        }
+        self.with_distances(location, best.into_sorted_vec().into_iter().map(|(_, i)| i))
+    }
+
+    /// All sites within `meters` of `location` with their distance in meters, closest first.
+    pub fn within(&self, location: &Location, meters: f64) -> Vec<(&'a Site, f64)> {
+        let target = location.unit_vector();
+        let angle = (meters / EARTH_RADIUS_METERS).min(std::f64::consts::PI);
+        let chord = 2.0 * (angle / 2.0).sin();
+        let mut found = Vec::new();
+        self.search_within(&target, chord * chord, 0, self.nodes.len(), 0, &mut found);
+
+        let mut result = self.with_distances(location, found.into_iter());
+        result.sort_by(|a, b| a.1.total_cmp(&b.1));
+        result
+    }
+
+    fn with_distances(&self, location: &Location, indices: impl Iterator<Item = usize>) -> Vec<(&'a Site, f64)> {
+        indices
+            .map(|i| {
+                let site = self.nodes[i].1;
+                (site, location.haversine_distance(&site.location))
+            })
+            .collect()
+    }
+
+    fn search_nearest(&self, target: &[f64; 3], lo: usize, hi: usize, depth: usize, k: usize, best: &mut BinaryHeap<(N64, usize)>) {
+        if lo >= hi {
+            return;
+        }
+        let mid = (lo + hi) / 2;
+        let (point, _) = &self.nodes[mid];
+        best.push((n64(squared_distance(point, target)), mid));
+        if best.len() > k {
+            best.pop();
+        }
+
+        let axis = depth % 3;
+        let offset = target[axis] - point[axis];
+        let (near, far) = if offset < 0.0 { ((lo, mid), (mid + 1, hi)) } else { ((mid + 1, hi), (lo, mid)) };
+        self.search_nearest(target, near.0, near.1, depth + 1, k, best);
+        // the far side can only hold closer sites if the splitting plane is closer than the worst candidate
+        if best.len() < k || offset * offset < best.peek().unwrap().0.raw() {
+            self.search_nearest(target, far.0, far.1, depth + 1, k, best);
+        }
+    }
+
+    fn search_within(&self, target: &[f64; 3], max_squared: f64, lo: usize, hi: usize, depth: usize, found: &mut Vec<usize>) {
+        if lo >= hi {
+            return;
+        }
+        let mid = (lo + hi) / 2;
+        let (point, _) = &self.nodes[mid];
+        if squared_distance(point, target) <= max_squared {
+            found.push(mid);
+        }
+
+        let axis = depth % 3;
+        let offset = target[axis] - point[axis];
+        if offset < 0.0 || offset * offset <= max_squared {
+            self.search_within(target, max_squared, lo, mid, depth + 1, found);
+        }
+        if offset >= 0.0 || offset * offset <= max_squared {
+            self.search_within(target, max_squared, mid + 1, hi, depth + 1, found);
+        }
+    }
+}
+
+fn build(nodes: &mut [([f64; 3], &Site)], depth: usize) {
+    if nodes.len() <= 1 {
+        return;
+    }
+    let axis = depth % 3;
+    let mid = nodes.len() / 2;
+    nodes.select_nth_unstable_by(mid, |a, b| a.0[axis].total_cmp(&b.0[axis]));
+    let (left, right) = nodes.split_at_mut(mid);
+    build(left, depth + 1);
+    build(&mut right[1..], depth + 1);
+}
+
+fn squared_distance(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    (0..3).map(|i| (a[i] - b[i]) * (a[i] - b[i])).sum()
+}
@@ -0,0 +1,191 @@
+use crate::horizon::Horizon;
+use crate::segment::Segment;
+use crate::solution::ChargeVisit;
+use crate::vehicle::Vehicle;
+use crate::{Period, SiteId};
+
+use csv::Writer;
+use serde::Serialize;
+use std::io;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimelineKind {
+    /// A segment with a customer.
+    Trip,
+    /// A segment without a customer, in which the vehicle may charge.
+    Free,
+    /// A charging stop during the preceding free segment.
+    Charge,
+}
+
+/// One segment or charging stop of a vehicle.
+///
+/// Segments span their start and stop period. A charging stop spans the periods the vehicle charges,
+/// its state of charge is given on arrival at the site and on leaving it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TimelineEntry {
+    pub kind: TimelineKind,
+    pub segment: u32,
+    pub site: Option<SiteId>,
+    pub arrival_period: Period,
+    pub departure_period: Period,
+    pub charge_minutes: u32,
+    pub soc_before: f64,
+    pub soc_after: f64,
+    /// Distance driven, including the way to and from the site of a charging stop.
+    pub distance_km: f64,
+    /// Distance a charging stop adds to its segment.
+    pub detour_km: f64,
+}
+
+/// The course of a vehicle through its tour for a charge plan, as the column generation
+/// evaluates it: the vehicle drives to the site, charges every planned period and drives on.
+#[derive(Debug, Clone, Serialize)]
+pub struct VehicleTimeline {
+    pub scenario: String,
+    pub vehicle: u32,
+    pub original_id: u32,
+    pub entries: Vec<TimelineEntry>,
+}
+
+impl VehicleTimeline {
+    /// Fails if a visit refers to a segment that is not a free segment of the tour or to a site that
+    /// cannot be reached during it, or if the visits of a segment are not consecutive periods at one site.
+    pub fn new(scenario: &str, vehicle: &Vehicle, visits: &[ChargeVisit], horizon: &Horizon) -> Result<Self, String> {
+        if let Some(visit) = visits.iter().find(|visit| !vehicle.tour.iter().any(|segment| segment.id == visit.segment)) {
+            return Err(format!("vehicle {} does not drive segment {}", vehicle.id, visit.segment));
+        }
+
+        let mut soc = vehicle.battery_initial_soc();
+        let mut entries = Vec::with_capacity(vehicle.tour.len() + visits.len());
+        for segment in &vehicle.tour {
+            let mut charges: Vec<&ChargeVisit> = visits.iter().filter(|visit| visit.segment == segment.id).collect();
+            charges.sort_by_key(|visit| visit.period);
+
+            let soc_at_start = soc;
+            if charges.is_empty() {
+                soc = vehicle.get_new_soc_after_distance(soc, segment.distance);
+                entries.push(segment_entry(segment, soc_at_start, soc, segment.distance));
+                continue;
+            }
+
+            let site_id = charges[0].site;
+            let site = segment
+                .reachable_sites
+                .iter()
+                .find(|site| site.site.id == site_id)
+                .filter(|_| segment.is_free)
+                .ok_or_else(|| format!("vehicle {} can not charge at site s{} during segment {}", vehicle.id, site_id, segment.id))?;
+            let first = charges[0].period;
+            let last = charges[charges.len() - 1].period;
+            let consecutive = charges.iter().enumerate().all(|(i, visit)| visit.site == site_id && usize::from(visit.period) == usize::from(first) + i);
+            if !consecutive || first < site.arrival_time || last > site.departure_time {
+                return Err(format!("charges of vehicle {} during segment {} are not a single stop at site s{}", vehicle.id, segment.id, site_id));
+            }
+
+            let distance = site.distance_to + site.distance_from;
+            let detour_km = (f64::from(distance) - f64::from(segment.distance)) / 1000.0;
+            let segment_index = entries.len();
+            entries.push(segment_entry(segment, soc_at_start, 0.0, distance));
+
+            soc = vehicle.get_new_soc_after_distance(soc, site.distance_to);
+            let soc_on_arrival = soc;
+            for _ in &charges {
+                soc = vehicle.get_new_soc_after_charging(soc, horizon.minutes_per_period);
+            }
+            entries.push(TimelineEntry {
+                kind: TimelineKind::Charge,
+                segment: segment.id,
+                site: Some(site_id),
+                arrival_period: first,
+                departure_period: last + 1,
+                charge_minutes: charges.len() as u32 * u32::from(horizon.minutes_per_period),
+                soc_before: soc_on_arrival,
+                soc_after: soc,
+                distance_km: 0.0,
+                detour_km,
+            });
+            soc = vehicle.get_new_soc_after_distance(soc, site.distance_from);
+            entries[segment_index].soc_after = soc;
+        }
+
+        Ok(VehicleTimeline {
+            scenario: scenario.to_string(),
+            vehicle: vehicle.id,
+            original_id: vehicle.original_id,
+            entries,
+        })
+    }
+
+    /// Lowest state of charge reached, which the column generation keeps above the minimum of the battery.
+    pub fn min_soc(&self) -> f64 {
+        self.entries
+            .iter()
+            .flat_map(|entry| [entry.soc_before, entry.soc_after])
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    pub fn final_soc(&self) -> Option<f64> {
+        self.entries.last().map(|entry| entry.soc_after)
+    }
+
+    /// Writes the entries of all timelines as csv, one row per entry.
+    pub fn write_csv<W: io::Write>(timelines: &[VehicleTimeline], output: W) -> csv::Result<()> {
+        let mut wtr = Writer::from_writer(output);
+        for timeline in timelines {
+            for entry in &timeline.entries {
+                wtr.serialize(TimelineRow {
+                    scenario: &timeline.scenario,
+                    vehicle: timeline.vehicle,
+                    original_id: timeline.original_id,
+                    kind: entry.kind,
+                    segment: entry.segment,
+                    site: entry.site.map(|id| format!("s{}", id)),
+                    arrival_period: entry.arrival_period,
+                    departure_period: entry.departure_period,
+                    charge_minutes: entry.charge_minutes,
+                    soc_before: entry.soc_before,
+                    soc_after: entry.soc_after,
+                    distance_km: entry.distance_km,
+                    detour_km: entry.detour_km,
+                })?;
+            }
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+}
+
+/// A timeline entry along with its vehicle, as a row of the csv output.
+#[derive(Serialize)]
+struct TimelineRow<'t> {
+    scenario: &'t str,
+    vehicle: u32,
+    original_id: u32,
+    kind: TimelineKind,
+    segment: u32,
+    site: Option<String>,
+    arrival_period: Period,
+    departure_period: Period,
+    charge_minutes: u32,
+    soc_before: f64,
+    soc_after: f64,
+    distance_km: f64,
+    detour_km: f64,
+}
+
+fn segment_entry(segment: &Segment, soc_before: f64, soc_after: f64, distance: u32) -> TimelineEntry {
+    TimelineEntry {
+        kind: if segment.is_free { TimelineKind::Free } else { TimelineKind::Trip },
+        segment: segment.id,
+        site: None,
+        arrival_period: segment.start_time,
+        departure_period: segment.stop_time,
+        charge_minutes: 0,
+        soc_before,
+        soc_after,
+        distance_km: f64::from(distance) / 1000.0,
+        detour_km: 0.0,
+    }
+}
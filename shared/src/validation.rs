@@ -0,0 +1,295 @@
+use crate::battery::Battery;
+use crate::instance_error::{CsvContext, InstanceError};
+use crate::segment::read_segment_rows;
+use crate::site::Site;
+use crate::vehicle::read_vehicle_records;
+use crate::{Period, SiteId};
+
+use indexmap::IndexMap;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use serde_json::json;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// A trip of a tour starts before the previous trip of the tour.
+    TourNotOrdered,
+    /// A trip of a tour starts before the previous trip of the tour has ended.
+    TourOverlap,
+    /// A trip ends before it starts.
+    TripEndsBeforeStart,
+    /// A potential site is reached at or after the latest departure from it.
+    SiteVisitWithoutTime,
+    UnknownSite,
+    UnknownTrip,
+    /// A trip is longer than the vehicle serving it can drive on a full battery.
+    TripOutOfRange,
+    /// A trip id is used more than once within or across the trips files.
+    DuplicateTrip,
+    /// A vehicle names a battery profile that is not loaded, or names none while there are several.
+    UnknownBatteryProfile,
+}
+
+/// One finding of [`validate_instance`], located by file and line.
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationIssue {
+    pub kind: IssueKind,
+    pub path: String,
+    pub line: u64,
+    pub trip: Option<u32>,
+    pub vehicle: Option<u32>,
+    pub site: Option<SiteId>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ValidationReport {
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    pub fn count(&self, kind: IssueKind) -> usize {
+        self.issues.iter().filter(|issue| issue.kind == kind).count()
+    }
+
+    /// Machine readable form of the report, with the number of issues per kind.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut counts: BTreeMap<IssueKind, usize> = BTreeMap::new();
+        for issue in &self.issues {
+            *counts.entry(issue.kind).or_insert(0) += 1;
+        }
+        json!({
+            "valid": self.is_valid(),
+            "counts": counts,
+            "issues": self.issues,
+        })
+    }
+
+    fn push(&mut self, kind: IssueKind, path: &str, line: u64, message: String) -> &mut ValidationIssue {
+        self.issues.push(ValidationIssue {
+            kind,
+            path: path.to_string(),
+            line,
+            trip: None,
+            vehicle: None,
+            site: None,
+            message,
+        });
+        self.issues.last_mut().unwrap()
+    }
+}
+
+/// What the tour and range checks need to know about a trip.
+struct TripInfo<'p> {
+    path: &'p str,
+    line: u64,
+    start_time: Period,
+    stop_time: Period,
+    distance: u32,
+    is_free: bool,
+    // distances to and from the known potential sites
+    site_distances: Vec<(u32, u32)>,
+}
+
+/// Checks the scenarios, given as pairs of vehicles and trips files, for consistency.
+///
+/// Unlike the loaders, which stop at the first unresolved reference, all issues are
+/// collected. Files that cannot be read or parsed at all are still reported as errors.
+/// As in the solver, the tours of all scenarios are resolved against the trips of all scenarios.
+pub fn validate_instance(
+    sites: &IndexMap<SiteId, Site>,
+    batteries: &IndexMap<String, Battery>,
+    scenarios: &[(&str, &str)],
+) -> Result<ValidationReport, InstanceError> {
+    let mut report = ValidationReport::default();
+    let mut trips: IndexMap<u32, TripInfo> = IndexMap::new();
+
+    let trips_paths: Vec<&str> = scenarios.iter().map(|&(_, trips_path)| trips_path).collect();
+    for (file_index, &path) in trips_paths.iter().enumerate() {
+        // scenarios may share a trips file
+        if trips_paths[..file_index].contains(&path) {
+            continue;
+        }
+
+        read_segment_rows(path, |_ctx, _potential_sites_column, row| {
+            let line = CsvContext::line(&row.record);
+
+            if let Some(first) = trips.get(&row.id) {
+                report
+                    .push(
+                        IssueKind::DuplicateTrip,
+                        path,
+                        line,
+                        format!("trip t{} is already defined in {}:{}", row.id, first.path, first.line),
+                    )
+                    .trip = Some(row.id);
+            }
+
+            if row.stop_time < row.start_time {
+                report
+                    .push(
+                        IssueKind::TripEndsBeforeStart,
+                        path,
+                        line,
+                        format!(
+                            "trip t{} ends in period {} before it starts in period {}",
+                            row.id, row.stop_time, row.start_time
+                        ),
+                    )
+                    .trip = Some(row.id);
+            }
+
+            let mut site_distances = Vec::new();
+            for visit in &row.reachable_sites {
+                if !sites.contains_key(&visit.site_id) {
+                    let issue = report.push(
+                        IssueKind::UnknownSite,
+                        path,
+                        line,
+                        format!("trip t{} references unknown site s{}", row.id, visit.site_id),
+                    );
+                    issue.trip = Some(row.id);
+                    issue.site = Some(visit.site_id);
+                    continue;
+                }
+
+                if visit.arrival_time >= visit.departure_time {
+                    let issue = report.push(
+                        IssueKind::SiteVisitWithoutTime,
+                        path,
+                        line,
+                        format!(
+                            "trip t{} arrives at site s{} in period {} but has to leave in period {}",
+                            row.id, visit.site_id, visit.arrival_time, visit.departure_time
+                        ),
+                    );
+                    issue.trip = Some(row.id);
+                    issue.site = Some(visit.site_id);
+                }
+                site_distances.push((visit.distance_to, visit.distance_from));
+            }
+
+            trips.insert(
+                row.id,
+                TripInfo {
+                    path,
+                    line,
+                    start_time: row.start_time,
+                    stop_time: row.stop_time,
+                    distance: row.distance,
+                    is_free: row.is_free,
+                    site_distances,
+                },
+            );
+            Ok(())
+        })?;
+    }
+
+    for (scenario_index, &(path, _)) in scenarios.iter().enumerate() {
+        if scenarios[..scenario_index].iter().any(|&(other, _)| other == path) {
+            continue;
+        }
+
+        read_vehicle_records(path, |_ctx, _trips_column, _battery_column, record, row| {
+            let line = CsvContext::line(record);
+            let vehicle_id = row.id;
+
+            // the tours are still checked, only the range check needs the battery
+            let battery = row.battery(batteries);
+            if battery.is_none() {
+                let message = match &row.battery {
+                    Some(profile) => format!("vehicle v{} uses unknown battery profile {}", vehicle_id, profile),
+                    None => format!(
+                        "vehicle v{} gives no battery profile, but there are {} profiles",
+                        vehicle_id,
+                        batteries.len()
+                    ),
+                };
+                report.push(IssueKind::UnknownBatteryProfile, path, line, message).vehicle = Some(vehicle_id);
+            }
+
+            let mut previous: Option<(u32, &TripInfo)> = None;
+            for trip_id in row.trips {
+                let trip = match trips.get(&trip_id) {
+                    Some(trip) => trip,
+                    None => {
+                        let issue = report.push(
+                            IssueKind::UnknownTrip,
+                            path,
+                            line,
+                            format!("vehicle v{} references unknown trip t{}", vehicle_id, trip_id),
+                        );
+                        issue.vehicle = Some(vehicle_id);
+                        issue.trip = Some(trip_id);
+                        continue;
+                    }
+                };
+
+                if let Some((previous_id, previous_trip)) = previous {
+                    let issue = if trip.start_time < previous_trip.start_time {
+                        Some((
+                            IssueKind::TourNotOrdered,
+                            format!(
+                                "trip t{} of vehicle v{} starts in period {} before the previous trip t{} in period {}",
+                                trip_id, vehicle_id, trip.start_time, previous_id, previous_trip.start_time
+                            ),
+                        ))
+                    } else if trip.start_time <= previous_trip.stop_time {
+                        Some((
+                            IssueKind::TourOverlap,
+                            format!(
+                                "trip t{} of vehicle v{} starts in period {} before the previous trip t{} ends in period {}",
+                                trip_id, vehicle_id, trip.start_time, previous_id, previous_trip.stop_time
+                            ),
+                        ))
+                    } else {
+                        None
+                    };
+                    if let Some((kind, message)) = issue {
+                        let issue = report.push(kind, path, line, message);
+                        issue.vehicle = Some(vehicle_id);
+                        issue.trip = Some(trip_id);
+                    }
+                }
+
+                if let Some(battery) = battery {
+                    let in_range =
+                        |distance: u32| battery.get_new_soc_after_distance(battery.max_charge, distance) >= battery.min_charge;
+                    // a free trip may also be split by a detour to a site
+                    let drivable = in_range(trip.distance)
+                        || (trip.is_free
+                            && trip
+                                .site_distances
+                                .iter()
+                                .any(|&(to, from)| in_range(to) && in_range(from)));
+                    if !drivable {
+                        let issue = report.push(
+                            IssueKind::TripOutOfRange,
+                            path,
+                            line,
+                            format!(
+                                "trip t{} of vehicle v{} needs {:.1} km but a full battery lasts {:.1} km",
+                                trip_id,
+                                vehicle_id,
+                                f64::from(trip.distance) / 1000.0,
+                                (battery.max_charge - battery.min_charge) * battery.range_in_km
+                            ),
+                        );
+                        issue.vehicle = Some(vehicle_id);
+                        issue.trip = Some(trip_id);
+                    }
+                }
+
+                previous = Some((trip_id, trip));
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok(report)
+}
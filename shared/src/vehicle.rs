@@ -1,27 +1,161 @@
 use crate::battery::Battery;
 use crate::get_reader;
+use crate::instance_cache;
+use crate::instance_error::{Column, CsvContext, InstanceError};
 use crate::segment::Segment;
-use csv::Writer;
+use csv::{StringRecord, Writer};
 
 use indexmap::IndexMap;
-use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::cmp::Ordering;
 
 use std::hash::{Hash, Hasher};
 use std::io;
+use std::sync::Arc;
 
+/// One row of a vehicles file with its trips and battery profile not yet resolved.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct VehicleRow {
+    pub id: u32,
+    pub trips: Vec<u32>,
+    /// Name of the battery profile, `None` if the row gives none.
+    pub battery: Option<String>,
+}
+
+impl VehicleRow {
+    /// The battery profile of the row. Rows without a profile can only use the single profile
+    /// of an instance that has exactly one.
+    pub(crate) fn battery<'b>(&self, batteries: &'b IndexMap<String, Battery>) -> Option<&'b Battery> {
+        match &self.battery {
+            Some(profile) => batteries.get(profile),
+            None if batteries.len() == 1 => batteries.get_index(0).map(|(_, battery)| battery),
+            None => None,
+        }
+    }
+
+    /// Why the battery profile of the row in `record` does not resolve against `batteries`.
+    fn battery_error(
+        &self,
+        ctx: &CsvContext,
+        record: &StringRecord,
+        battery_column: Option<Column>,
+        batteries: &IndexMap<String, Battery>,
+    ) -> InstanceError {
+        match (&self.battery, battery_column) {
+            (Some(profile), Some(column)) => ctx.unknown(record, column, "battery profile", profile.clone()),
+            _ => InstanceError::config(
+                ctx.path(),
+                "battery",
+                format!(
+                    "line {} gives no battery profile, but there are several: {}",
+                    CsvContext::line(record),
+                    batteries.keys().cloned().collect::<Vec<String>>().join(", ")
+                ),
+            ),
+        }
+    }
+}
+
+/// Loading vehicles requires at least one battery profile.
+fn require_batteries(path: &str, batteries: &IndexMap<String, Battery>) -> Result<(), InstanceError> {
+    if batteries.is_empty() {
+        return Err(InstanceError::config(path, "battery", "at least one battery profile is required"));
+    }
+    Ok(())
+}
+
+/// Identity of the vehicle in row `index` of the vehicles file with checksum `source_sha256`.
+/// Derived from the file contents, so that repeated runs order and hash vehicles alike.
+pub(crate) fn vehicle_id(source_sha256: &str, index: usize) -> u32 {
+    let digest = Sha256::new()
+        .chain_update(source_sha256.as_bytes())
+        .chain_update((index as u64).to_le_bytes())
+        .finalize();
+    u32::from_le_bytes([digest[0], digest[1], digest[2], digest[3]])
+}
+
+/// Reads all rows of a vehicles file without resolving them. `on_row` receives the
+/// columns of the trips and the battery profile along with every row.
+pub(crate) fn read_vehicle_records<F>(path: &str, mut on_row: F) -> Result<(), InstanceError>
+where
+    F: FnMut(&CsvContext, Column, Option<Column>, &StringRecord, VehicleRow) -> Result<(), InstanceError>,
+{
+    let mut rdr = csv::Reader::from_reader(get_reader(path)?);
+    let ctx = CsvContext::new(path, &mut rdr)?;
+
+    // get the ids for the relevant columns!
+    let vehicle_id_column = ctx.column("id")?;
+    let trips_column = ctx.column("trips")?;
+    let battery_column = ctx.optional_column("battery");
+
+    for result in rdr.records() {
+        let record = ctx.record(result)?;
+
+        let vehicle_id = ctx.parse::<u32>(
+            &record,
+            vehicle_id_column,
+            ctx.get(&record, vehicle_id_column)?.trim_start_matches('v'),
+        )?;
+        let track_ids_string = ctx.get(&record, trips_column)?;
+
+        let patterns: &[_] = &['[', ']'];
+        let track_ids = track_ids_string
+            .trim_matches(patterns)
+            .split(',')
+            .filter(|x| x != &"")
+            .map(|x| ctx.parse::<u32>(&record, trips_column, x.trim_matches(' ').trim_matches('t')))
+            .collect::<Result<Vec<u32>, InstanceError>>()?;
+
+        let battery = match battery_column {
+            Some(column) if !ctx.get(&record, column)?.trim().is_empty() => {
+                Some(ctx.get(&record, column)?.trim().to_string())
+            }
+            _ => None,
+        };
 
+        let row = VehicleRow {
+            id: vehicle_id,
+            trips: track_ids,
+            battery,
+        };
+        on_row(&ctx, trips_column, battery_column, &record, row)?;
+    }
+
+    Ok(())
+}
+
+/// Reads all rows of a vehicles file. `on_row` receives the original vehicle id, the
+/// unresolved trip ids and the battery profile of every row, along with the column to
+/// report unknown trips against. Rows without a profile require `batteries` to hold a single one.
+fn read_vehicle_rows<F>(
+    path: &str,
+    batteries: &IndexMap<String, Battery>,
+    mut on_row: F,
+) -> Result<(), InstanceError>
+where
+    F: FnMut(&CsvContext, Column, &StringRecord, u32, Vec<u32>, &Battery) -> Result<(), InstanceError>,
+{
+    require_batteries(path, batteries)?;
+
+    read_vehicle_records(path, |ctx, trips_column, battery_column, record, row| {
+        let battery = row
+            .battery(batteries)
+            .ok_or_else(|| row.battery_error(ctx, record, battery_column, batteries))?;
+        on_row(ctx, trips_column, record, row.id, row.trips, battery)
+    })
+}
 
 #[derive(Debug, Clone)]
-pub struct Vehicle<'a> {
+pub struct Vehicle {
     pub index: usize,
     pub original_id: u32,
     pub id: u32,
     pub battery: Battery,
-    pub tour: Vec<&'a Segment<'a>>,
+    pub tour: Vec<Arc<Segment>>,
 }
 
-impl<'a> Vehicle<'a> {
+impl Vehicle {
     pub fn battery_initial_soc(&self) -> f64 {
         self.battery.initial_charge
     }
@@ -41,90 +175,89 @@ impl<'a> Vehicle<'a> {
     }
 
     pub fn get_new_soc_after_charging(&self, current_soc: f64, duration_minutes: u8) -> f64 {
-        let time_index = (((self.battery.soc_to_time[0])
-            .mul_add(current_soc, self.battery.soc_to_time[1]))
-        .mul_add(current_soc, self.battery.soc_to_time[2]))
-        .mul_add(current_soc, self.battery.soc_to_time[3]);
-
-        let new_index = time_index + (duration_minutes as f64);
-
-        let new_soc = (((self.battery.time_to_soc[0])
-            .mul_add(new_index, self.battery.time_to_soc[1]))
-        .mul_add(new_index, self.battery.time_to_soc[2]))
-        .mul_add(new_index, self.battery.time_to_soc[3]);
-
-        let new_soc = new_soc
-            .min(self.battery_max_soc())
-            .max(self.battery_min_soc());
-
-        debug_assert!(new_soc >= self.battery_min_soc());
-        debug_assert!(new_soc <= self.battery_max_soc());
-
-        new_soc
+        self.battery.get_new_soc_after_charging(current_soc, duration_minutes)
     }
 
+    /// Loads the vehicles of a file, their tours share the segments of `segments`.
     pub fn load(
-        segments: &'a IndexMap<u32, Segment<'a>>,
+        segments: &IndexMap<u32, Arc<Segment>>,
         path: &str,
-        battery: &Battery,
-    ) -> Vec<Vehicle<'a>> {
-        let mut vehicles = Vec::new();
-
-        let mut rdr = csv::Reader::from_reader(get_reader(path));
-
-        let header_row = rdr.headers().unwrap();
+        batteries: &IndexMap<String, Battery>,
+    ) -> Result<Vec<Vehicle>, InstanceError> {
+        require_batteries(path, batteries)?;
 
-        // get the ids for the relevant columns!
-        let vehicle_id_column = header_row.iter().position(|x| x == "id").unwrap();
-        let trips_column = header_row.iter().position(|x| x == "trips").unwrap();
+        let (rows, source_sha256): (Vec<VehicleRow>, String) = instance_cache::cached_with_sha256(path, "vehicles", || {
+            let mut rows = Vec::new();
+            read_vehicle_records(path, |_ctx, _trips_column, _battery_column, _record, row| {
+                rows.push(row);
+                Ok(())
+            })?;
+            Ok(rows)
+        })?;
 
-        let mut index_counter = 0;
+        let vehicles = rows
+            .iter()
+            .enumerate()
+            .map(|(index, row)| {
+                Some(Vehicle {
+                    original_id: row.id,
+                    id: vehicle_id(&source_sha256, index),
+                    index,
+                    tour: row
+                        .trips
+                        .iter()
+                        .map(|x| segments.get(x).cloned())
+                        .collect::<Option<Vec<Arc<Segment>>>>()?,
+                    battery: row.battery(batteries)?.clone(),
+                })
+            })
+            .collect::<Option<Vec<Vehicle>>>();
 
-        for result in rdr.records() {
-            let record = result.unwrap();
+        match vehicles {
+            Some(vehicles) => Ok(vehicles),
+            // read the file again to report the unknown reference with its line
+            None => Vehicle::read(segments, path, &source_sha256, batteries),
+        }
+    }
 
-            let vehicle_id = record
-                .get(vehicle_id_column)
-                .unwrap()
-                .trim_start_matches('v')
-                .parse::<u32>()
-                .unwrap();
-            let track_ids_string = record.get(trips_column).unwrap();
+    fn read(
+        segments: &IndexMap<u32, Arc<Segment>>,
+        path: &str,
+        source_sha256: &str,
+        batteries: &IndexMap<String, Battery>,
+    ) -> Result<Vec<Vehicle>, InstanceError> {
+        let mut vehicles = Vec::new();
 
-            let patterns: &[_] = &['[', ']'];
-            let track_ids: Vec<&Segment> = track_ids_string
-                .trim_matches(patterns)
-                .split(',')
-                .filter(|x| x != &"")
+        read_vehicle_rows(path, batteries, |ctx, trips_column, record, original_id, track_ids, battery| {
+            let tour = track_ids
+                .into_iter()
                 .map(|x| {
-                    x.trim_matches(' ')
-                        .trim_matches('t')
-                        .parse::<u32>()
-                        .unwrap()
+                    segments
+                        .get(&x)
+                        .cloned()
+                        .ok_or_else(|| ctx.unknown(record, trips_column, "segment", format!("t{}", x)))
                 })
-                .map(|x| segments.get(&x).unwrap())
-                .collect();
-
-            let vehicle = Vehicle {
-                original_id: vehicle_id,
-                id: rand::thread_rng().gen::<u32>(),
-                index: index_counter,
-                tour: track_ids,
+                .collect::<Result<Vec<Arc<Segment>>, InstanceError>>()?;
+
+            vehicles.push(Vehicle {
+                original_id,
+                id: vehicle_id(source_sha256, vehicles.len()),
+                index: vehicles.len(),
+                tour,
                 battery: battery.clone(),
-            };
+            });
+            Ok(())
+        })?;
 
-            vehicles.push(vehicle);
-            index_counter += 1;
-        }
-        vehicles
+        Ok(vehicles)
     }
 
-    pub fn output<T>(vehicles: &[Vehicle<'a>], output: T)
+    pub fn output<T>(vehicles: &[Vehicle], output: T)
     where
         T: io::Write,
     {
         let mut wtr = Writer::from_writer(output);
-        wtr.write_record(&["index", "id", "trips"]).unwrap();
+        wtr.write_record(&["index", "id", "trips", "battery"]).unwrap();
         for vehicle in vehicles {
             wtr.write_record(&[
                 format!("{}", vehicle.index),
@@ -138,6 +271,7 @@ impl<'a> Vehicle<'a> {
                         .collect::<Vec<String>>()
                         .join(",")
                 ),
+                vehicle.battery.profile.clone(),
             ])
             .unwrap();
         }
@@ -145,21 +279,21 @@ impl<'a> Vehicle<'a> {
     }
 }
 
-impl<'a> Ord for Vehicle<'a> {
+impl Ord for Vehicle {
     fn cmp(&self, other: &Vehicle) -> Ordering {
         self.id.cmp(&other.id)
     }
 }
 
-impl<'a> PartialOrd for Vehicle<'a> {
+impl PartialOrd for Vehicle {
     fn partial_cmp(&self, other: &Vehicle) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl<'a> Eq for Vehicle<'a> {}
+impl Eq for Vehicle {}
 
-impl<'a> Hash for Vehicle<'a> {
+impl Hash for Vehicle {
     fn hash<H>(&self, state: &mut H)
     where
         H: Hasher,
@@ -169,7 +303,7 @@ impl<'a> Hash for Vehicle<'a> {
     }
 }
 
-impl<'a> PartialEq for Vehicle<'a> {
+impl PartialEq for Vehicle {
     fn eq(&self, other: &Vehicle) -> bool {
         self.id == other.id
     }
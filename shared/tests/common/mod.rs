@@ -0,0 +1,28 @@
+//! Helpers shared by the integration tests.
+#![allow(dead_code)]
+
+use std::fs;
+use tempfile::TempDir;
+
+/// Writes `content` to `name` in `dir` and returns the path of the file.
+///
+/// The file and any cache written next to it are removed with `dir`.
+pub fn write_instance_file(dir: &TempDir, name: &str, content: &str) -> String {
+    let path = dir.path().join(name);
+    fs::write(&path, content).unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+/// Battery profile with a range of 100 km that charges fully in an hour.
+pub fn battery(soc_final: f64) -> String {
+    format!(
+        "SOC_min = 0.1
+SOC_max = 0.9
+SOC_initial = 0.5
+SOC_final = {}
+range_in_km = 100
+charging_curve = [[0.0, 0.0], [60.0, 1.0]]
+",
+        soc_final
+    )
+}
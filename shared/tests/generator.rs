@@ -0,0 +1,65 @@
+use shared::{generate_instance, validate_instance, GeneratorConfig, Horizon, InstanceManifest, Segment, Shift, Vehicle};
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_generated_instance() {
+    let config = GeneratorConfig {
+        num_sites: 8,
+        num_vehicles: 4,
+        ..GeneratorConfig::default()
+    };
+    let instance = TempDir::new().unwrap();
+    let dir = instance.path();
+    generate_instance(&config).unwrap().write_to_dir(dir).unwrap();
+
+    let manifest = InstanceManifest::load(dir.join("instance.toml").to_str().unwrap()).unwrap();
+    assert_eq!(manifest.horizon, Some(Horizon::default()));
+    let sites = manifest.load_sites().unwrap();
+    let batteries = manifest.load_batteries().unwrap();
+    assert_eq!(sites.len(), 8);
+
+    let report = validate_instance(&sites, &batteries, &manifest.scenario_paths()).unwrap();
+    assert!(report.is_valid(), "{:?}", report);
+
+    let scenario = manifest.scenario(Some("generated")).unwrap();
+    let segments = Segment::load(&sites, &scenario.trips.path).unwrap();
+    let vehicles = Vehicle::load(&segments, &scenario.vehicles.path, &batteries).unwrap();
+    assert_eq!(vehicles.len(), 4);
+    for vehicle in &vehicles {
+        // vehicles alternate between customer trips and free time, back to back within their shift
+        for pair in vehicle.tour.windows(2) {
+            assert_eq!(pair[0].stop_time + 1, pair[1].start_time);
+            assert_ne!(pair[0].is_free, pair[1].is_free);
+        }
+    }
+    assert!(segments.values().any(|segment| !segment.reachable_sites.is_empty()));
+    assert!(segments.values().filter(|segment| !segment.is_free).all(|segment| segment.reachable_sites.is_empty()));
+
+    // the same seed writes the same files
+    let again_instance = TempDir::new().unwrap();
+    let again = again_instance.path();
+    generate_instance(&config).unwrap().write_to_dir(again).unwrap();
+    for file in ["sites.csv", "trips.csv", "vehicles.csv"] {
+        assert_eq!(fs::read(dir.join(file)).unwrap(), fs::read(again.join(file)).unwrap(), "{}", file);
+    }
+}
+
+#[test]
+fn test_invalid_generator_config() {
+    let valid = GeneratorConfig::default();
+    assert!(valid.validate().is_ok());
+    let invalid = [
+        GeneratorConfig { trip_km: (15.0, 2.0), ..valid.clone() },
+        GeneratorConfig { idle_minutes: (0, 60), ..valid.clone() },
+        GeneratorConfig { idle_minutes: (60, 10), ..valid.clone() },
+        GeneratorConfig { shifts: Vec::new(), ..valid.clone() },
+        // starts after the end of the horizon
+        GeneratorConfig { shifts: vec![Shift { start_minute: 1500, length_minutes: 600 }], ..valid.clone() },
+        // shorter than a period
+        GeneratorConfig { shifts: vec![Shift { start_minute: 360, length_minutes: 2 }], ..valid.clone() },
+    ];
+    for config in &invalid {
+        assert!(generate_instance(config).is_err(), "{:?}", config);
+    }
+}
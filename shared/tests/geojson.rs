@@ -0,0 +1,71 @@
+mod common;
+
+use common::write_instance_file;
+use shared::{solution_geojson, ChargePlan, ChargeVisit, Horizon, OccupancySchedule, OpenSite, ScenarioResult, Segment, Site, SolutionReport, SolveStatistics};
+use tempfile::TempDir;
+
+#[test]
+fn test_solution_geojson() {
+    let dir = TempDir::new().unwrap();
+    let sites_path = write_instance_file(
+        &dir,
+        "geojson_sites.csv",
+        "id,capacity,levels,cost_2,cost_4,location
+s3,4,\"[2, 4]\",60,100,\"[52.5, 13.4]\"
+s5,4,\"[2, 4]\",60,100,\"[52.6, 13.5]\"
+",
+    );
+    let trips_path = write_instance_file(
+        &dir,
+        "geojson_trips.csv",
+        "id,isFree,startPeriod,endPeriod,osmDistance,startPoint,endPoint,potentialSites
+t1,True,13,30,5000.0,\"[52.6, 13.3]\",\"[52.5, 13.4]\",s3[15|4000|28|3000]
+",
+    );
+    let sites = Site::load(&sites_path).unwrap();
+    let segments = Segment::load(&sites, &trips_path).unwrap();
+
+    let report = SolutionReport {
+        cost: 60,
+        lower_bound: 60.0,
+        gap: 0.0,
+        open_sites: vec![OpenSite { id: 3, chargers: 2, cost: 60 }],
+        active_scenarios: 1,
+        statistics: SolveStatistics::default(),
+        scenarios: vec![ScenarioResult {
+            name: "seed".to_string(),
+            active: true,
+            feasible: true,
+            infeasible_vehicles: 0,
+            charge_plans: vec![ChargePlan {
+                vehicle: 8,
+                original_id: 0,
+                visits: (16..19).map(|period| ChargeVisit { segment: 1, site: 3, period }).collect(),
+            }],
+        }],
+    };
+    let horizon = Horizon::default();
+    let schedules = [OccupancySchedule::new(&report.scenarios[0], &report.open_sites, &horizon)];
+
+    let geojson = solution_geojson(&sites, &report, &schedules, None);
+    assert_eq!(geojson["type"], "FeatureCollection");
+    let features = geojson["features"].as_array().unwrap();
+    assert_eq!(features.len(), 2);
+    let s3 = &features[0];
+    assert_eq!(s3["geometry"]["coordinates"][0].as_f64().unwrap() as f32, 13.4);
+    assert_eq!(s3["properties"]["id"], "s3");
+    assert_eq!(s3["properties"]["open"], true);
+    assert_eq!((s3["properties"]["chargers"].as_u64(), s3["properties"]["cost"].as_u64()), (Some(2), Some(60)));
+    assert_eq!(s3["properties"]["stops"], 1);
+    assert_eq!(s3["properties"]["peak_utilisation"], 0.5);
+    let s5 = &features[1]["properties"];
+    assert_eq!((s5["open"].as_bool(), s5["chargers"].as_u64()), (Some(false), Some(0)));
+    assert!(s5.get("peak_utilisation").is_none());
+
+    let geojson = solution_geojson(&sites, &report, &schedules, Some(&segments));
+    let detour = &geojson["features"][2];
+    assert_eq!(detour["geometry"]["type"], "LineString");
+    assert_eq!(detour["geometry"]["coordinates"].as_array().unwrap().len(), 3);
+    assert_eq!(detour["properties"]["charged_periods"], 3);
+    assert_eq!(detour["properties"]["detour_km"], 2.0);
+}
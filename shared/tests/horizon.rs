@@ -0,0 +1,17 @@
+use shared::Horizon;
+
+#[test]
+fn test_horizon_periods() {
+    assert_eq!(Horizon::default(), Horizon::new(5, 24 * 60).unwrap());
+
+    let horizon = Horizon::new(15, 2 * 24 * 60).unwrap();
+    assert_eq!(horizon.periods, 192);
+    assert_eq!(horizon.capacity_period(&100), 100);
+    // periods after the horizon wrap around to its start
+    assert_eq!(horizon.capacity_period(&200), 8);
+    assert_eq!(horizon.periods_in(50), 3);
+
+    assert!(Horizon::new(0, 24 * 60).is_err());
+    assert!(Horizon::new(7, 24 * 60).is_err());
+    assert!(Horizon::new(1, 60 * 24 * 60).is_err());
+}
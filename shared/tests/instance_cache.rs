@@ -0,0 +1,79 @@
+mod common;
+
+use common::{battery, write_instance_file};
+use shared::{Battery, InstanceError, Segment, Site, Vehicle};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+#[test]
+fn test_loaders_use_instance_cache() {
+    let dir = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    let sites_path = write_instance_file(
+        &dir,
+        "cache_sites.csv",
+        "id,capacity,cost,location
+s0,4,10,\"[52.5, 13.4]\"
+s1,2,12,\"[52.6, 13.3]\"
+",
+    );
+    let battery_path = write_instance_file(&dir, "cache_battery.toml", &battery(0.5));
+    let trips_path = write_instance_file(
+        &dir,
+        "cache_trips.csv",
+        "id,isFree,startPeriod,endPeriod,osmDistance,startPoint,endPoint,potentialSites
+t0,True,10,12,1500.0,\"[52.5, 13.4]\",\"[52.6, 13.3]\",s0[12|100.0|13|200.0]
+t1,True,14,16,1500.0,\"[52.5, 13.4]\",\"[52.6, 13.3]\",s0[16|100.0|17|200.0]; s1[16|10.0|17|20.0]
+",
+    );
+    let vehicles_path = write_instance_file(&dir, "cache_vehicles.csv", "id,trips\nv0,\"[t0, t1]\"\n");
+    let batteries = Battery::load_profiles(&[&battery_path]).unwrap();
+
+    // caching is opt-in, nothing is written next to the instance files by default
+    Segment::load(&Site::load(&sites_path).unwrap(), &trips_path).unwrap();
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 4);
+
+    std::env::set_var("INSTANCE_CACHE_DIR", cache_dir.path());
+    for _ in 0..2 {
+        let sites = Site::load(&sites_path).unwrap();
+        let segments = Segment::load(&sites, &trips_path).unwrap();
+        let vehicles = Vehicle::load(&segments, &vehicles_path, &batteries).unwrap();
+
+        assert_eq!(sites[1].index, 1);
+        assert_eq!(segments[&1].reachable_sites[1].site.id, 1);
+        assert_eq!(segments[&1].reachable_sites[1].distance_from, 20);
+        assert_eq!(vehicles[0].tour.iter().map(|t| t.id).collect::<Vec<u32>>(), vec![0, 1]);
+    }
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 4);
+    let caches: Vec<String> = fs::read_dir(cache_dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    assert_eq!(caches.len(), 3);
+    assert!(caches.iter().any(|name| name.ends_with(".segments.cache")));
+
+    // a changed file is read again
+    fs::write(
+        &trips_path,
+        "id,isFree,startPeriod,endPeriod,osmDistance,startPoint,endPoint,potentialSites
+t0,True,10,12,2500.0,\"[52.5, 13.4]\",\"[52.6, 13.3]\",s0[12|100.0|13|200.0]
+t1,True,14,16,1500.0,\"[52.5, 13.4]\",\"[52.6, 13.3]\",s0[16|100.0|17|200.0]; s1[16|10.0|17|20.0]
+",
+    )
+    .unwrap();
+    let sites = Site::load(&sites_path).unwrap();
+    assert_eq!(Segment::load(&sites, &trips_path).unwrap()[&0].distance, 2500);
+
+    // references that no longer resolve are still reported with their line
+    fs::write(&sites_path, "id,capacity,cost,location\ns0,4,10,\"[52.5, 13.4]\"\n").unwrap();
+    let sites = Site::load(&sites_path).unwrap();
+    let err = Segment::load(&sites, &trips_path).unwrap_err();
+    assert!(matches!(err, InstanceError::UnknownReference { line: 3, kind: "site", .. }));
+
+    // INSTANCE_CACHE=on caches next to the instance files
+    std::env::remove_var("INSTANCE_CACHE_DIR");
+    std::env::set_var("INSTANCE_CACHE", "on");
+    Site::load(&sites_path).unwrap();
+    assert!(Path::new(&format!("{}.sites.cache", sites_path)).exists());
+}
@@ -0,0 +1,289 @@
+mod common;
+
+use common::write_instance_file;
+use indexmap::IndexMap;
+use shared::{Battery, InstanceError, Segment, Site, SiteLevel, Vehicle};
+use tempfile::TempDir;
+
+const SITES: &str = "id,capacity,cost,location
+s0,4,10,\"[52.5, 13.4]\"
+s1,2,12,\"[52.6, 13.3]\"
+";
+
+#[test]
+fn test_unknown_site_is_reported_with_line_and_column() {
+    let dir = TempDir::new().unwrap();
+    let sites_path = write_instance_file(&dir, "sites.csv", SITES);
+    let trips_path = write_instance_file(
+        &dir,
+        "trips.csv",
+        "id,isFree,startPeriod,endPeriod,osmDistance,startPoint,endPoint,potentialSites
+t0,True,10,12,1500.0,\"[52.5, 13.4]\",\"[52.6, 13.3]\",s0[12|100.0|13|200.0]
+t1,True,14,16,1500.0,\"[52.5, 13.4]\",\"[52.6, 13.3]\",s0[16|100.0|17|200.0]; s117[16|10.0|17|20.0]
+",
+    );
+
+    let sites = Site::load(&sites_path).unwrap();
+    let err = Segment::load(&sites, &trips_path).unwrap_err();
+
+    assert!(matches!(
+        err,
+        InstanceError::UnknownReference { line: 3, kind: "site", .. }
+    ));
+    assert_eq!(
+        err.to_string(),
+        format!("{}:3 potentialSites: unknown site s117", trips_path)
+    );
+}
+
+#[test]
+fn test_invalid_value_and_missing_file() {
+    let dir = TempDir::new().unwrap();
+    let sites_path = write_instance_file(
+        &dir,
+        "sites_invalid.csv",
+        "id,capacity,cost,location
+s0,4,10,\"[52.5, 13.4]\"
+s1,four,12,\"[52.6, 13.3]\"
+",
+    );
+
+    let err = Site::load(&sites_path).unwrap_err();
+    assert!(matches!(
+        &err,
+        InstanceError::InvalidValue { line: 3, column, value, .. } if column == "capacity" && value == "four"
+    ));
+
+    let err = Site::load("does/not/exist.csv").unwrap_err();
+    assert!(matches!(err, InstanceError::Io { .. }));
+    assert_eq!(err.path(), "does/not/exist.csv");
+}
+
+#[test]
+fn test_site_ids_above_255() {
+    let dir = TempDir::new().unwrap();
+    let sites_path = write_instance_file(
+        &dir,
+        "sites_wide.csv",
+        "id,capacity,cost,location
+s0,4,10,\"[52.5, 13.4]\"
+s1999,2,12,\"[52.6, 13.3]\"
+",
+    );
+    let trips_path = write_instance_file(
+        &dir,
+        "trips_wide.csv",
+        "id,isFree,startPeriod,endPeriod,osmDistance,startPoint,endPoint,potentialSites
+t0,True,10,12,1500.0,\"[52.5, 13.4]\",\"[52.6, 13.3]\",s1999[12|100.0|13|200.0]
+",
+    );
+
+    let sites = Site::load(&sites_path).unwrap();
+    let segments = Segment::load(&sites, &trips_path).unwrap();
+    assert_eq!(segments[&0].reachable_sites[0].site.id, 1999);
+    assert_eq!(segments[&0].reachable_sites[0].site.index, 1);
+}
+
+#[test]
+fn test_site_levels() {
+    let dir = TempDir::new().unwrap();
+    let sites_path = write_instance_file(
+        &dir,
+        "sites_levels.csv",
+        "id,capacity,cost,location,levels
+s0,4,100,\"[52.5, 13.4]\",
+s1,8,100,\"[52.6, 13.3]\",\"[1, 2, 4, 8]\"
+",
+    );
+
+    let sites = Site::load(&sites_path).unwrap();
+
+    // without levels the legacy half and full size apply
+    assert_eq!(
+        sites[&0].levels,
+        vec![
+            SiteLevel { chargers: 2, cost: 94 },
+            SiteLevel { chargers: 4, cost: 140 }
+        ]
+    );
+
+    let site = &sites[&1];
+    assert_eq!(site.levels.iter().map(|l| l.chargers).collect::<Vec<u8>>(), vec![1, 2, 4, 8]);
+    assert_eq!(site.level_cost(8), Some(180));
+    assert_eq!(site.level_cost(0), Some(0));
+    assert_eq!(site.next_level(2), Some(4));
+    assert_eq!(site.next_level(8), None);
+    assert_eq!(site.level_at_most(6), 4);
+    assert_eq!(site.level_cost(3), None);
+}
+
+#[test]
+fn test_site_levels_within_capacity() {
+    let dir = TempDir::new().unwrap();
+    let sites_path = write_instance_file(
+        &dir,
+        "sites_small.csv",
+        "id,capacity,cost,location,levels
+s0,2,100,\"[52.5, 13.4]\",
+",
+    );
+
+    // a half size site only offers the legacy half size
+    let sites = Site::load(&sites_path).unwrap();
+    assert_eq!(sites[&0].levels, vec![SiteLevel { chargers: 2, cost: 94 }]);
+
+    let sites_path = write_instance_file(
+        &dir,
+        "sites_too_large.csv",
+        "id,capacity,cost,location,levels
+s0,4,100,\"[52.5, 13.4]\",\"[2, 6]\"
+",
+    );
+    let err = Site::load(&sites_path).unwrap_err();
+    assert!(matches!(
+        &err,
+        InstanceError::InvalidValue { line: 2, column, .. } if column == "levels"
+    ));
+}
+
+#[test]
+fn test_explicit_level_costs() {
+    let dir = TempDir::new().unwrap();
+    let sites_path = write_instance_file(
+        &dir,
+        "sites_level_costs.csv",
+        "id,capacity,cost,location,levels,cost_2,cost_4,cost_6
+s0,4,100,\"[52.5, 13.4]\",,,150,
+s1,6,100,\"[52.6, 13.3]\",\"[2, 4, 6]\",90,130,170
+s2,6,100,\"[52.6, 13.3]\",\"[2, 6]\",,,
+",
+    );
+
+    let sites = Site::load(&sites_path).unwrap();
+
+    // explicit costs win, missing ones fall back to the legacy derivation
+    assert_eq!(sites[&0].level_cost(2), Some(94));
+    assert_eq!(sites[&0].level_cost(4), Some(150));
+    assert_eq!(sites[&1].level_cost(2), Some(90));
+    assert_eq!(sites[&1].level_cost(6), Some(170));
+    assert_eq!(sites[&2].level_cost(2), Some(120));
+    assert_eq!(sites[&2].level_cost(6), Some(160));
+
+    let sites_path = write_instance_file(
+        &dir,
+        "sites_level_costs_only.csv",
+        "id,capacity,location,cost_2,cost_4
+s0,4,\"[52.5, 13.4]\",90,130
+s1,4,\"[52.6, 13.3]\",90,
+",
+    );
+    let err = Site::load(&sites_path).unwrap_err();
+    assert!(matches!(
+        &err,
+        InstanceError::InvalidValue { line: 3, column, .. } if column == "cost_4"
+    ));
+
+    // a level without any cost column
+    let sites_path = write_instance_file(
+        &dir,
+        "sites_level_costs_missing.csv",
+        "id,capacity,location,levels,cost_2
+s0,4,\"[52.5, 13.4]\",\"[2, 4]\",90
+",
+    );
+    let err = Site::load(&sites_path).unwrap_err();
+    assert!(matches!(
+        &err,
+        InstanceError::MissingColumn { column, .. } if column == "cost_4"
+    ));
+}
+
+#[test]
+fn test_vehicle_battery_profiles() {
+    let dir = TempDir::new().unwrap();
+    let battery_path = write_instance_file(
+        &dir,
+        "fleet.toml",
+        "[small]
+SOC_min = 0.1
+SOC_max = 0.9
+SOC_initial = 0.5
+SOC_final = 0.5
+range_in_km = 150
+charging_curve = [[0.0, 0.0], [60.0, 1.0]]
+
+[large]
+SOC_min = 0.05
+SOC_max = 0.95
+SOC_initial = 0.8
+SOC_final = 0.5
+range_in_km = 400
+charging_curve = [[0.0, 0.0], [90.0, 1.0]]
+",
+    );
+    let sites_path = write_instance_file(&dir, "sites_fleet.csv", SITES);
+    let trips_path = write_instance_file(
+        &dir,
+        "trips_fleet.csv",
+        "id,isFree,startPeriod,endPeriod,osmDistance,startPoint,endPoint,potentialSites
+t0,True,10,12,1500.0,\"[52.5, 13.4]\",\"[52.6, 13.3]\",s0[12|100.0|13|200.0]
+",
+    );
+    let vehicles_path = write_instance_file(
+        &dir,
+        "vehicles_fleet.csv",
+        "id,trips,battery
+v0,[t0],small
+v1,[t0],small
+",
+    );
+
+    let batteries = Battery::load_profiles(&[&battery_path]).unwrap();
+    assert_eq!(batteries.keys().collect::<Vec<_>>(), vec!["large", "small"]);
+
+    let sites = Site::load(&sites_path).unwrap();
+    let segments = Segment::load(&sites, &trips_path).unwrap();
+    let vehicles = Vehicle::load(&segments, &vehicles_path, &batteries).unwrap();
+
+    assert_eq!(vehicles[0].battery.profile, "small");
+    assert_eq!(vehicles[0].battery.range_in_km, 150.0);
+    assert_eq!(vehicles[1].battery.profile, "small");
+
+    let vehicles_path = write_instance_file(
+        &dir,
+        "vehicles_fleet_unknown.csv",
+        "id,trips,battery
+v0,[t0],medium
+",
+    );
+    let err = Vehicle::load(&segments, &vehicles_path, &batteries).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!("{}:2 battery: unknown battery profile medium", vehicles_path)
+    );
+
+    // rows without a profile are ambiguous with several profiles
+    let vehicles_path = write_instance_file(
+        &dir,
+        "vehicles_fleet_default.csv",
+        "id,trips,battery
+v0,[t0],small
+v1,[t0],
+",
+    );
+    let err = Vehicle::load(&segments, &vehicles_path, &batteries).unwrap_err();
+    assert!(matches!(&err, InstanceError::InvalidConfig { key, .. } if key == "battery"));
+    assert_eq!(
+        err.to_string(),
+        format!("{} battery: line 3 gives no battery profile, but there are several: large, small", vehicles_path)
+    );
+
+    // with a single profile it is the default
+    let vehicles_path = write_instance_file(&dir, "vehicles_fleet_single.csv", "id,trips,battery\nv0,[t0],\n");
+    let single = Battery::load_profiles(&[&battery_path]).unwrap().into_iter().take(1).collect();
+    let vehicles = Vehicle::load(&segments, &vehicles_path, &single).unwrap();
+    assert_eq!(vehicles[0].battery.profile, "large");
+
+    let err = Vehicle::load(&segments, &vehicles_path, &IndexMap::new()).unwrap_err();
+    assert!(matches!(err, InstanceError::InvalidConfig { .. }));
+}
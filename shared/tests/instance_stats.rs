@@ -0,0 +1,64 @@
+mod common;
+
+use common::{battery, write_instance_file};
+use shared::{instance_stats, Battery, Horizon, Segment, Site, Vehicle};
+use tempfile::TempDir;
+
+#[test]
+fn test_instance_stats() {
+    let dir = TempDir::new().unwrap();
+    let sites_path = write_instance_file(
+        &dir,
+        "stats_sites.csv",
+        "id,capacity,cost,location
+s0,4,10,\"[52.5, 13.4]\"
+s1,4,10,\"[52.6, 13.4]\"
+",
+    );
+    let battery_path = write_instance_file(&dir, "stats_battery.toml", &battery(0.3));
+    // t4 is not driven by any vehicle
+    let trips_path = write_instance_file(
+        &dir,
+        "stats_trips.csv",
+        "id,isFree,startPeriod,endPeriod,osmDistance,startPoint,endPoint,potentialSites
+t0,False,10,12,5000.0,\"[52.5, 13.4]\",\"[52.6, 13.3]\",
+t1,True,13,20,1000.0,\"[52.6, 13.3]\",\"[52.5, 13.4]\",s0[14|100|18|100];s1[16|100|20|100]
+t2,False,21,24,15000.0,\"[52.5, 13.4]\",\"[52.6, 13.3]\",
+t3,True,10,15,1000.0,\"[52.6, 13.3]\",\"[52.5, 13.4]\",
+t4,True,10,15,1000.0,\"[52.6, 13.3]\",\"[52.5, 13.4]\",s0[10|100|15|100]
+",
+    );
+    let vehicles_path = write_instance_file(&dir, "stats_vehicles.csv", "id,trips\nv0,\"[t0, t1, t2]\"\nv1,[t3]\n");
+
+    let sites = Site::load(&sites_path).unwrap();
+    let batteries = Battery::load_profiles(&[battery_path.as_str()]).unwrap();
+    let segments = Segment::load(&sites, &trips_path).unwrap();
+    let vehicles = Vehicle::load(&segments, &vehicles_path, &batteries).unwrap();
+    let horizon = Horizon::new(5, 60).unwrap();
+
+    let stats = instance_stats("seed", &vehicles, &horizon);
+    assert_eq!(stats.fleet_size, 2);
+    assert_eq!((stats.segments, stats.free_segments), (4, 2));
+    assert!((stats.total_driven_km - 22.0).abs() < 1e-9);
+
+    // 21 km of the 20 km between the initial and final charge
+    let v0 = stats.vehicles.iter().find(|v| v.trips == 3).unwrap();
+    assert_eq!(v0.customer_trips, 2);
+    assert!((v0.energy_demand - 0.21).abs() < 1e-9);
+    assert!((v0.range_without_charging_km - 20.0).abs() < 1e-9);
+    assert!(!v0.feasible_without_charging);
+    assert_eq!(stats.vehicles_feasible_without_charging, 1);
+
+    assert_eq!(stats.reachable_sites_per_free_segment.get(&0), Some(&1));
+    assert_eq!(stats.reachable_sites_per_free_segment.get(&2), Some(&1));
+    // periods past the horizon of 12 periods wrap around to its start
+    let s0 = &stats.site_reachability[&0];
+    assert_eq!(s0.len(), 12);
+    assert_eq!(s0.iter().sum::<u32>(), 5);
+    assert_eq!(s0[2..=6], [1, 1, 1, 1, 1]);
+    assert_eq!(stats.site_reachability[&1][4..=8], [1, 1, 1, 1, 1]);
+
+    let json = stats.to_json();
+    assert_eq!(json["scenario"], "seed");
+    assert_eq!(json["reachable_sites_per_free_segment"]["2"], 1);
+}
@@ -0,0 +1,103 @@
+mod common;
+
+use common::{battery, write_instance_file};
+use shared::{Horizon, InstanceData, InstanceError, InstanceManifest, Segment, Vehicle};
+use sha2::{Digest, Sha256};
+use std::fs;
+use tempfile::TempDir;
+
+fn instance_dir() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    write_instance_file(
+        &dir,
+        "sites.csv",
+        "id,capacity,cost,location
+s0,4,10,\"[52.5, 13.4]\"
+",
+    );
+    write_instance_file(&dir, "battery.toml", &battery(0.5));
+    write_instance_file(
+        &dir,
+        "trips.csv",
+        "id,isFree,startPeriod,endPeriod,osmDistance,startPoint,endPoint,potentialSites
+t0,True,10,12,1500.0,\"[52.5, 13.4]\",\"[52.6, 13.3]\",s0[12|100.0|13|200.0]
+",
+    );
+    write_instance_file(&dir, "vehicles.csv", "id,trips\nv0,[t0]\n");
+    dir
+}
+
+#[test]
+fn test_instance_manifest() {
+    let instance = instance_dir();
+    let dir = instance.path();
+    let manifest_path = dir.join("instance.toml");
+    fs::write(
+        &manifest_path,
+        "sites = \"sites.csv\"
+battery = \"battery.toml\"
+period_length = 15
+
+[[scenarios]]
+name = \"seed\"
+vehicles = \"vehicles.csv\"
+trips = { path = \"trips.csv\", sha256 = \"0000\" }
+",
+    )
+    .unwrap();
+
+    // the trips file does not match its checksum
+    let err = InstanceManifest::load(manifest_path.to_str().unwrap()).unwrap_err();
+    assert!(matches!(err, InstanceError::InvalidConfig { .. }));
+    assert!(err.to_string().contains("sha256 checksum"));
+
+    let trips_sha256: String = Sha256::digest(fs::read(dir.join("trips.csv")).unwrap())
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect();
+    fs::write(
+        &manifest_path,
+        format!(
+            "sites = \"sites.csv\"
+battery = [\"battery.toml\"]
+period_length = 15
+
+[[scenarios]]
+name = \"seed\"
+vehicles = \"vehicles.csv\"
+trips = {{ path = \"trips.csv\", sha256 = \"{}\" }}
+
+[[scenarios]]
+vehicles = \"vehicles.csv\"
+trips = \"trips.csv\"
+",
+            trips_sha256
+        ),
+    )
+    .unwrap();
+
+    let manifest = InstanceManifest::load(manifest_path.to_str().unwrap()).unwrap();
+    assert_eq!(manifest.horizon, Some(Horizon::new(15, 24 * 60).unwrap()));
+    assert_eq!(manifest.scenarios.len(), 2);
+    assert_eq!(manifest.scenario(Some("seed")).unwrap().trips.path, dir.join("trips.csv").to_str().unwrap());
+    // unnamed scenarios are named after their position
+    assert_eq!(manifest.scenario(Some("1")).unwrap().name, "1");
+    assert!(manifest.scenario(Some("other")).is_none());
+
+    let sites = manifest.load_sites().unwrap();
+    let batteries = manifest.load_batteries().unwrap();
+    let scenario = manifest.scenario(None).unwrap();
+    let segments = Segment::load(&sites, &scenario.trips.path).unwrap();
+    let vehicles = Vehicle::load(&segments, &scenario.vehicles.path, &batteries).unwrap();
+    assert_eq!(vehicles[0].tour[0].id, 0);
+
+    // the loaded instance owns its data and skips the repeated scenario
+    fn assert_send_sync<T: Send + Sync + 'static>(_: &T) {}
+    let data = InstanceData::load(&manifest).unwrap();
+    assert_send_sync(&data);
+    assert_eq!(data.scenarios.len(), 1);
+    let data = std::thread::spawn(move || data).join().unwrap();
+    let tour = &data.scenario(Some("seed")).unwrap().vehicles[0].tour;
+    assert_eq!(tour[0].reachable_sites[0].site.resolve(&data.sites).capacity, 4);
+    assert!(std::sync::Arc::ptr_eq(&tour[0], &data.segments[&0]));
+}
@@ -0,0 +1,94 @@
+use shared::{ChargePlan, ChargeVisit, Horizon, OccupancySchedule, OpenSite, ScenarioResult};
+
+fn plan(vehicle: u32, segment: u32, site: u16, periods: std::ops::Range<u16>) -> ChargePlan {
+    ChargePlan {
+        vehicle,
+        original_id: vehicle,
+        visits: periods.map(|period| ChargeVisit { segment, site, period }).collect(),
+    }
+}
+
+#[test]
+fn test_occupancy_schedule() {
+    let horizon = Horizon::new(60, 12 * 60).unwrap();
+    let scenario = ScenarioResult {
+        name: "seed".to_string(),
+        active: true,
+        feasible: true,
+        infeasible_vehicles: 0,
+        charge_plans: vec![
+            plan(1, 10, 4, 2..5),
+            plan(2, 20, 4, 3..6),
+            // starts once vehicle 1 has left its charger
+            plan(3, 30, 4, 5..7),
+            // runs past the end of the horizon into its first periods
+            plan(4, 40, 9, 11..14),
+        ],
+    };
+    let open_sites = [OpenSite { id: 4, chargers: 2, cost: 10 }, OpenSite { id: 9, chargers: 1, cost: 10 }];
+
+    let schedule = OccupancySchedule::new(&scenario, &open_sites, &horizon);
+    let s4 = &schedule.sites[0];
+    assert_eq!((0..8).map(|period| s4.occupied(period)).collect::<Vec<usize>>(), vec![0, 0, 1, 2, 2, 2, 1, 0]);
+    assert_eq!(s4.vehicles[5], vec![2, 3]);
+    assert_eq!(s4.slots.iter().map(|slot| (slot.vehicle, slot.charger)).collect::<Vec<_>>(), vec![(1, 0), (2, 1), (3, 0)]);
+    assert_eq!(s4.peak(), 2);
+    assert!((s4.peak_utilisation() - 1.0).abs() < 1e-9);
+    assert!((s4.idle_share() - (1.0 - 8.0 / 24.0)).abs() < 1e-9);
+
+    let s9 = &schedule.sites[1];
+    assert_eq!((s9.occupied(11), s9.occupied(0), s9.occupied(1)), (1, 1, 1));
+    assert_eq!((s9.slots[0].start_period, s9.slots[0].end_period), (11, 14));
+
+    let schedules = [schedule];
+    let mut occupancy = Vec::new();
+    OccupancySchedule::write_occupancy_csv(&schedules, &mut occupancy).unwrap();
+    let occupancy = String::from_utf8(occupancy).unwrap();
+    assert_eq!(occupancy.lines().count(), 1 + 2 * 12);
+    assert!(occupancy.contains("seed,s4,5,2,2,2;3\n"));
+
+    let mut slots = Vec::new();
+    OccupancySchedule::write_slots_csv(&schedules, &mut slots).unwrap();
+    assert!(String::from_utf8(slots).unwrap().contains("seed,s4,1,2,20,3,6\n"));
+
+    let mut summary = Vec::new();
+    OccupancySchedule::write_summary_csv(&schedules, &mut summary).unwrap();
+    assert!(String::from_utf8(summary).unwrap().contains("seed,s9,1,1,1,1,"));
+
+    let svg = schedules[0].to_svg(&horizon);
+    assert!(svg.starts_with("<svg"));
+    // three stops at s4 and the stop at s9 split at the end of the horizon
+    assert_eq!(svg.matches("<rect").count(), 5);
+    assert_eq!(schedules[0].svg_file_name(), "occupancy_seed.svg");
+}
+
+#[test]
+fn test_wrapped_stops_share_chargers() {
+    let horizon = Horizon::new(60, 12 * 60).unwrap();
+    let scenario = ScenarioResult {
+        name: "seed".to_string(),
+        active: true,
+        feasible: true,
+        infeasible_vehicles: 0,
+        charge_plans: vec![
+            // wraps around into periods 0 and 1
+            plan(1, 10, 4, 11..14),
+            // overlaps the wrapped periods of vehicle 1
+            plan(2, 20, 4, 1..3),
+            // starts after the wrapped stop of vehicle 1 has left
+            plan(3, 30, 4, 2..4),
+        ],
+    };
+    let open_sites = [OpenSite { id: 4, chargers: 2, cost: 10 }];
+
+    let schedule = OccupancySchedule::new(&scenario, &open_sites, &horizon);
+    let s4 = &schedule.sites[0];
+    assert_eq!(s4.peak(), 2);
+    let mut chargers: Vec<(u32, usize)> = s4.slots.iter().map(|slot| (slot.vehicle, slot.charger)).collect();
+    chargers.sort_unstable();
+    // vehicle 1 keeps away from the charger of vehicle 2 in the periods it wraps into
+    assert_eq!(chargers, vec![(1, 1), (2, 0), (3, 1)]);
+
+    let schedule = OccupancySchedule { scenario: "../peak hour".to_string(), sites: Vec::new() };
+    assert_eq!(schedule.svg_file_name(), "occupancy____peak_hour.svg");
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use common::write_instance_file;
+use shared::{Horizon, Location, Reachability, Segment, Site, TravelMatrix};
+use tempfile::TempDir;
+
+#[test]
+fn test_reachable_sites_from_matrix() {
+    let dir = TempDir::new().unwrap();
+    let sites_path = write_instance_file(
+        &dir,
+        "reach_sites.csv",
+        "id,capacity,cost,location
+s0,4,10,\"[52.5, 13.4]\"
+s1,4,10,\"[52.55, 13.45]\"
+s2,4,10,\"[53.5, 13.4]\"
+",
+    );
+    // 10 minutes to s0 and 15 minutes back, s1 is only estimated
+    let matrix_path = write_instance_file(
+        &dir,
+        "reach_matrix.csv",
+        "from,to,duration,distance
+\"[52.52, 13.41]\",\"[52.5, 13.4]\",600,2500
+\"[52.5, 13.4]\",\"[52.53, 13.42]\",900,4000
+",
+    );
+    let trips_path = write_instance_file(
+        &dir,
+        "reach_trips.csv",
+        "id,isFree,startPeriod,endPeriod,osmDistance,startPoint,endPoint,potentialSites
+t0,True,100,120,1500.0,\"[52.52, 13.41]\",\"[52.53, 13.42]\",s7[1|1|2|1]
+t1,False,130,160,1500.0,\"[52.53, 13.42]\",\"[52.52, 13.41]\",s0[132|2500|150|4000]
+",
+    );
+
+    let sites = Site::load(&sites_path).unwrap();
+    let mut reachability = Reachability::new(&Horizon::default());
+    reachability.matrix = Some(TravelMatrix::load(&matrix_path).unwrap());
+
+    let start = Location::new(52.52, 13.41);
+    let stop = Location::new(52.53, 13.42);
+    let reachable = reachability.reachable_sites(&sites, &start, &stop, 100, 120, 1500);
+    // s2 is 100 km away and cannot be visited within 20 periods
+    assert_eq!(reachable.iter().map(|r| r.site.id).collect::<Vec<u16>>(), vec![0, 1]);
+    let s0 = &reachable[0];
+    assert_eq!((s0.arrival_time, s0.distance_to, s0.departure_time, s0.distance_from), (102, 2500, 117, 4000));
+
+    let s1 = &reachable[1];
+    let estimate = reachability.travel(&start, &sites[&1].location);
+    assert!((estimate.distance_meters - 1.3 * start.haversine_distance(&sites[&1].location)).abs() < 1e-6);
+    assert_eq!(s1.distance_to, estimate.distance_meters.round() as u32);
+
+    reachability.max_detour_meters = Some(5_000);
+    assert_eq!(reachability.reachable_sites(&sites, &start, &stop, 100, 120, 1500).len(), 1);
+    reachability.max_detour_meters = None;
+    // s0 leaves a window of 15 periods
+    reachability.min_charging_periods = 15;
+    let reachable = reachability.reachable_sites(&sites, &start, &stop, 100, 120, 1500);
+    assert_eq!(reachable.iter().map(|r| r.site.id).collect::<Vec<u16>>(), vec![0, 1]);
+    reachability.min_charging_periods = 16;
+    let reachable = reachability.reachable_sites(&sites, &start, &stop, 100, 120, 1500);
+    assert_eq!(reachable.iter().map(|r| r.site.id).collect::<Vec<u16>>(), vec![1]);
+
+    // arriving at s0 in period 102 and leaving in 103 leaves one period, but none when
+    // leaving in the period of arrival
+    let reaches_s0 = |reachability: &Reachability, stop_time| {
+        reachability
+            .reachable_sites(&sites, &start, &stop, 100, stop_time, 1500)
+            .iter()
+            .any(|r| r.site.id == 0)
+    };
+    reachability.min_charging_periods = 1;
+    assert!(reaches_s0(&reachability, 106));
+    assert!(!reaches_s0(&reachability, 105));
+    reachability.min_charging_periods = 2;
+    assert!(!reaches_s0(&reachability, 106));
+    assert!(reaches_s0(&reachability, 107));
+    reachability.min_charging_periods = 0;
+    assert!(!reaches_s0(&reachability, 105));
+    reachability.min_charging_periods = 1;
+
+    // visits wrap around the end of the horizon once, later arrivals are dropped
+    let end = Horizon::default().end();
+    assert!(!reachability.reachable_sites(&sites, &start, &stop, end + 10, end + 30, 1500).is_empty());
+    assert!(reachability.reachable_sites(&sites, &start, &stop, 2 * end - 2, 2 * end + 30, 1500).is_empty());
+
+    // the unknown site s7 of the input is replaced
+    let mut output = Vec::new();
+    reachability.rewrite_trips(&sites, &trips_path, &mut output).unwrap();
+    let updated_path = write_instance_file(&dir, "reach_trips_updated.csv", &String::from_utf8(output).unwrap());
+    let segments = Segment::load(&sites, &updated_path).unwrap();
+    assert_eq!(segments[&0].reachable_sites.len(), 2);
+    assert_eq!(segments[&0].reachable_sites[0].departure_time, 117);
+    // customer trips cannot be used to charge, even with time to reach a site
+    assert!(!reachability.reachable_sites(&sites, &stop, &start, 130, 160, 1500).is_empty());
+    assert!(segments[&1].reachable_sites.is_empty());
+    let customer_trip = String::from_utf8(std::fs::read(&updated_path).unwrap()).unwrap();
+    assert!(customer_trip.lines().nth(2).unwrap().ends_with(",\"[52.52, 13.41]\","));
+
+    let stale_path = write_instance_file(
+        &dir,
+        "reach_trips_stale.csv",
+        "id,isFree,startPeriod,endPeriod,osmDistance,startPoint,endPoint,potentialSites
+t0,True,100,120,1500.0,\"[52.52, 13.41]\",\"[52.53, 13.42]\",
+t1,False,130,160,1500.0,\"[52.53, 13.42]\",\"[52.52, 13.41]\",s0[132|2500|150|4000]
+",
+    );
+    let mut segments = Segment::load(&sites, &stale_path).unwrap();
+    assert_eq!(segments[&1].reachable_sites.len(), 1);
+    reachability.assign(&sites, &mut segments);
+    assert_eq!(segments[&0].reachable_sites.len(), 2);
+    assert!(segments[&1].reachable_sites.is_empty());
+}
@@ -1,4 +1,8 @@
-use shared::{Battery, Vehicle};
+mod common;
+
+use common::write_instance_file;
+use shared::{Battery, ChargingCurve, Vehicle};
+use tempfile::TempDir;
 
 #[test]
 fn test_a_default_battery() {
@@ -36,3 +40,29 @@ fn test_a_default_battery() {
     dbg!(new_soc);
     assert!(new_soc > 0.75 && new_soc < 0.81);
 }
+
+#[test]
+fn test_tabulated_charging_curve() {
+    let dir = TempDir::new().unwrap();
+    let path = write_instance_file(
+        &dir,
+        "battery_curve.toml",
+        "SOC_min = 0.05
+SOC_max = 0.95
+SOC_initial = 0.5
+SOC_final = 0.5
+range_in_km = 250
+charging_curve = [[0.0, 0.0], [30.0, 0.8], [60.0, 1.0]]
+",
+    );
+
+    let battery = Battery::load(&path).unwrap();
+    assert!(matches!(battery.charging_curve, ChargingCurve::PiecewiseLinear { .. }));
+
+    // constant current phase
+    assert!((battery.get_new_soc_after_charging(0.4, 15) - 0.8).abs() < 1e-9);
+    // crosses into the slower constant voltage phase
+    assert!((battery.get_new_soc_after_charging(0.7, 15) - 0.875).abs() < 1e-9);
+    // limited by the usable range
+    assert!((battery.get_new_soc_after_charging(0.9, 60) - 0.95).abs() < 1e-9);
+}
@@ -0,0 +1,47 @@
+use shared::{relative_gap, ChargePlan, ChargeVisit, OpenSite, ScenarioResult, SolutionReport, SolveStatistics};
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_solution_report() {
+    let report = SolutionReport {
+        cost: 200,
+        lower_bound: 150.0,
+        gap: relative_gap(200.0, 150.0),
+        open_sites: vec![OpenSite { id: 7, chargers: 4, cost: 200 }],
+        active_scenarios: 1,
+        statistics: SolveStatistics { iterations: 3, cuts: 2, ..SolveStatistics::default() },
+        scenarios: vec![
+            ScenarioResult {
+                name: "seed".to_string(),
+                active: true,
+                feasible: true,
+                infeasible_vehicles: 0,
+                charge_plans: vec![ChargePlan {
+                    vehicle: 11,
+                    original_id: 0,
+                    visits: vec![ChargeVisit { segment: 3, site: 7, period: 42 }],
+                }],
+            },
+            ScenarioResult {
+                name: "1".to_string(),
+                active: false,
+                feasible: false,
+                infeasible_vehicles: 2,
+                charge_plans: Vec::new(),
+            },
+        ],
+    };
+    assert!((report.gap - 0.25).abs() < 1e-9);
+    assert_eq!(relative_gap(0.0, 0.0), 0.0);
+
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("solution.json");
+    report.write(path.to_str().unwrap()).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(json, report.to_json());
+    assert_eq!(json["open_sites"][0]["id"], 7);
+    assert_eq!(json["statistics"]["iterations"], 3);
+    assert_eq!(json["scenarios"][0]["charge_plans"][0]["visits"][0]["period"], 42);
+    assert_eq!(json["scenarios"][1]["infeasible_vehicles"], 2);
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use common::write_instance_file;
+use shared::{BoundingBox, Location, Site, SiteSpatialIndex};
+use tempfile::TempDir;
+
+#[test]
+fn test_distances() {
+    let berlin = Location::new(52.52, 13.405);
+    let munich = Location::new(48.1351, 11.582);
+    assert!((berlin.haversine_distance(&munich) - 504_400.0).abs() < 1_000.0);
+    assert_eq!(berlin.haversine_distance(&berlin), 0.0);
+
+    // within a city the projection is within a few meters of the great circle
+    let alexanderplatz = Location::new(52.5219, 13.4132);
+    let zoo = Location::new(52.5079, 13.3376);
+    let great_circle = alexanderplatz.haversine_distance(&zoo);
+    assert!((great_circle - 5_370.0).abs() < 50.0);
+    assert!((alexanderplatz.projected_distance(&zoo) - great_circle).abs() < 1.0);
+
+    let bounds = BoundingBox::around(&[alexanderplatz.clone(), zoo.clone()]).unwrap();
+    assert!(bounds.contains(&bounds.center()));
+    // 900 m north of the box
+    let north = Location::new(52.53, 13.4);
+    assert!(!bounds.contains(&north));
+    assert!(bounds.expand(1_000.0).contains(&north));
+    assert!(!bounds.expand(800.0).contains(&north));
+    assert!(BoundingBox::around(&[]).is_none());
+}
+
+#[test]
+fn test_site_spatial_index() {
+    // a grid of sites around Berlin, 0.01 degrees apart
+    let mut sites_csv = String::from("id,capacity,cost,location\n");
+    for i in 0..15 {
+        for j in 0..15 {
+            sites_csv += &format!(
+                "s{},4,10,\"[{}, {}]\"\n",
+                i * 15 + j,
+                52.45 + 0.01 * i as f32,
+                13.30 + 0.01 * j as f32
+            );
+        }
+    }
+    let dir = TempDir::new().unwrap();
+    let sites = Site::load(&write_instance_file(&dir, "spatial_sites.csv", &sites_csv)).unwrap();
+    let index = SiteSpatialIndex::new(&sites);
+    assert_eq!(index.len(), 225);
+
+    let target = Location::new(52.5219, 13.4132);
+    let mut by_distance: Vec<(u16, f64)> = sites
+        .values()
+        .map(|site| (site.id, target.haversine_distance(&site.location)))
+        .collect();
+    by_distance.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+    let nearest = index.nearest(&target, 5);
+    assert_eq!(
+        nearest.iter().map(|(site, _)| site.id).collect::<Vec<u16>>(),
+        by_distance[..5].iter().map(|(id, _)| *id).collect::<Vec<u16>>()
+    );
+    assert_eq!(nearest[0].1, by_distance[0].1);
+    assert_eq!(index.nearest(&target, 1000).len(), 225);
+
+    let within = index.within(&target, 2_000.0);
+    let expected: Vec<u16> = by_distance
+        .iter()
+        .filter(|(_, d)| *d <= 2_000.0)
+        .map(|(id, _)| *id)
+        .collect();
+    assert!(!expected.is_empty());
+    assert_eq!(within.iter().map(|(site, _)| site.id).collect::<Vec<u16>>(), expected);
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use common::{battery, write_instance_file};
+use shared::{Battery, ChargeVisit, Horizon, Segment, Site, TimelineKind, Vehicle, VehicleTimeline};
+use tempfile::TempDir;
+
+#[test]
+fn test_vehicle_timeline() {
+    let dir = TempDir::new().unwrap();
+    let sites_path = write_instance_file(
+        &dir,
+        "timeline_sites.csv",
+        "id,capacity,cost,location
+s3,4,10,\"[52.5, 13.4]\"
+",
+    );
+    // a full battery lasts 100 km and charges in an hour
+    let battery_path = write_instance_file(&dir, "timeline_battery.toml", &battery(0.3));
+    let trips_path = write_instance_file(
+        &dir,
+        "timeline_trips.csv",
+        "id,isFree,startPeriod,endPeriod,osmDistance,startPoint,endPoint,potentialSites
+t0,False,10,12,20000.0,\"[52.5, 13.4]\",\"[52.6, 13.3]\",
+t1,True,13,30,5000.0,\"[52.6, 13.3]\",\"[52.5, 13.4]\",s3[15|4000|28|3000]
+t2,False,31,34,10000.0,\"[52.5, 13.4]\",\"[52.6, 13.3]\",
+",
+    );
+    let vehicles_path = write_instance_file(&dir, "timeline_vehicles.csv", "id,trips\nv0,\"[t0, t1, t2]\"\n");
+
+    let sites = Site::load(&sites_path).unwrap();
+    let batteries = Battery::load_profiles(&[battery_path.as_str()]).unwrap();
+    let segments = Segment::load(&sites, &trips_path).unwrap();
+    let vehicles = Vehicle::load(&segments, &vehicles_path, &batteries).unwrap();
+    let horizon = Horizon::default();
+
+    let visits: Vec<ChargeVisit> = (16..19).map(|period| ChargeVisit { segment: 1, site: 3, period }).collect();
+    let timeline = VehicleTimeline::new("seed", &vehicles[0], &visits, &horizon).unwrap();
+    let kinds: Vec<TimelineKind> = timeline.entries.iter().map(|entry| entry.kind).collect();
+    assert_eq!(kinds, vec![TimelineKind::Trip, TimelineKind::Free, TimelineKind::Charge, TimelineKind::Trip]);
+
+    let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+    let charge = &timeline.entries[2];
+    assert_eq!((charge.site, charge.arrival_period, charge.departure_period, charge.charge_minutes), (Some(3), 16, 19, 15));
+    // 0.5 - 0.2 for t0 - 0.04 to the site, then a quarter of an hour of charging
+    assert!(close(charge.soc_before, 0.26));
+    assert!(close(charge.soc_after, 0.51));
+    assert!(close(charge.detour_km, 2.0));
+    let free = &timeline.entries[1];
+    assert!(close(free.distance_km, 7.0));
+    assert!(close(free.soc_after, 0.48));
+    assert!(close(timeline.final_soc().unwrap(), 0.38));
+    assert!(close(timeline.min_soc(), 0.26));
+
+    // without charging the vehicle drives the free segment directly
+    let direct = VehicleTimeline::new("seed", &vehicles[0], &[], &horizon).unwrap();
+    assert_eq!(direct.entries.len(), 3);
+    assert!(close(direct.final_soc().unwrap(), 0.15));
+
+    let gap = [visits[0].clone(), visits[2].clone()];
+    assert!(VehicleTimeline::new("seed", &vehicles[0], &gap, &horizon).is_err());
+    let customer = [ChargeVisit { segment: 0, site: 3, period: 11 }];
+    assert!(VehicleTimeline::new("seed", &vehicles[0], &customer, &horizon).is_err());
+
+    let mut csv = Vec::new();
+    VehicleTimeline::write_csv(&[timeline], &mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    assert_eq!(csv.lines().count(), 5);
+    assert!(csv.lines().nth(3).unwrap().starts_with("seed,"));
+    assert!(csv.starts_with("scenario,vehicle,original_id,kind,segment,site,arrival_period,"));
+    assert!(csv.contains(",charge,1,s3,16,19,15,"));
+    assert!(csv.contains(",trip,0,,10,12,0,"));
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use common::{battery, write_instance_file};
+use shared::{validate_instance, Battery, IssueKind, Site};
+use tempfile::TempDir;
+
+#[test]
+fn test_validate_instance() {
+    let dir = TempDir::new().unwrap();
+    let sites_path = write_instance_file(
+        &dir,
+        "validate_sites.csv",
+        "id,capacity,cost,location
+s0,4,10,\"[52.5, 13.4]\"
+",
+    );
+    let battery_path = write_instance_file(&dir, "validate_battery.toml", &battery(0.5));
+    let trips_path = write_instance_file(
+        &dir,
+        "validate_trips.csv",
+        "id,isFree,startPeriod,endPeriod,osmDistance,startPoint,endPoint,potentialSites
+t0,False,10,12,1500.0,\"[52.5, 13.4]\",\"[52.6, 13.3]\",
+t1,True,12,16,1500.0,\"[52.5, 13.4]\",\"[52.6, 13.3]\",s0[14|100.0|14|200.0]; s9[13|100.0|15|200.0]
+t2,False,30,20,1500.0,\"[52.5, 13.4]\",\"[52.6, 13.3]\",
+t3,False,40,42,95000.0,\"[52.5, 13.4]\",\"[52.6, 13.3]\",
+",
+    );
+    let other_trips_path = write_instance_file(
+        &dir,
+        "validate_trips_other.csv",
+        "id,isFree,startPeriod,endPeriod,osmDistance,startPoint,endPoint,potentialSites
+t3,False,40,42,1500.0,\"[52.5, 13.4]\",\"[52.6, 13.3]\",
+",
+    );
+    let vehicles_path = write_instance_file(
+        &dir,
+        "validate_vehicles.csv",
+        "id,trips
+v0,\"[t0, t1]\"
+v1,\"[t3, t2, t7]\"
+",
+    );
+
+    let sites = Site::load(&sites_path).unwrap();
+    let batteries = Battery::load_profiles(&[&battery_path]).unwrap();
+
+    let report = validate_instance(&sites, &batteries, &[(&vehicles_path, &trips_path)]).unwrap();
+    assert_eq!(report.count(IssueKind::TourOverlap), 1);
+    assert_eq!(report.count(IssueKind::TourNotOrdered), 1);
+    assert_eq!(report.count(IssueKind::TripEndsBeforeStart), 1);
+    assert_eq!(report.count(IssueKind::SiteVisitWithoutTime), 1);
+    assert_eq!(report.count(IssueKind::UnknownSite), 1);
+    assert_eq!(report.count(IssueKind::UnknownTrip), 1);
+    // 95 km exceed the 80 km of a full battery
+    assert_eq!(report.count(IssueKind::TripOutOfRange), 1);
+    assert_eq!(report.issues.len(), 7);
+
+    let unknown_site = report
+        .issues
+        .iter()
+        .find(|issue| issue.kind == IssueKind::UnknownSite)
+        .unwrap();
+    assert_eq!((unknown_site.line, unknown_site.trip, unknown_site.site), (3, Some(1), Some(9)));
+
+    let json = report.to_json();
+    assert_eq!(json["valid"], false);
+    assert_eq!(json["counts"]["tour_overlap"], 1);
+    assert_eq!(json["issues"][0]["path"], trips_path.as_str());
+
+    let report = validate_instance(
+        &sites,
+        &batteries,
+        &[(&vehicles_path, &trips_path), (&vehicles_path, &other_trips_path)],
+    )
+    .unwrap();
+    assert_eq!(report.count(IssueKind::DuplicateTrip), 1);
+
+    // an unknown battery profile is reported while the tour is still checked
+    let fleet_path = write_instance_file(
+        &dir,
+        "validate_vehicles_fleet.csv",
+        "id,trips,battery
+v0,\"[t0, t1]\",validate_battery
+v2,\"[t3, t7]\",medium
+",
+    );
+    let report = validate_instance(&sites, &batteries, &[(&fleet_path, &trips_path)]).unwrap();
+    assert_eq!(report.count(IssueKind::UnknownBatteryProfile), 1);
+    assert_eq!(report.count(IssueKind::UnknownTrip), 1);
+    assert_eq!(report.count(IssueKind::TripOutOfRange), 0);
+    let unknown_battery = report
+        .issues
+        .iter()
+        .find(|issue| issue.kind == IssueKind::UnknownBatteryProfile)
+        .unwrap();
+    assert_eq!((unknown_battery.line, unknown_battery.vehicle), (3, Some(2)));
+    assert_eq!(report.to_json()["counts"]["unknown_battery_profile"], 1);
+}
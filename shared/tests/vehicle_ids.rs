@@ -0,0 +1,38 @@
+mod common;
+
+use common::{battery, write_instance_file};
+use shared::{Battery, Segment, Site, Vehicle};
+use tempfile::TempDir;
+
+#[test]
+fn test_vehicle_ids_are_deterministic() {
+    let dir = TempDir::new().unwrap();
+    let sites_path = write_instance_file(&dir, "ids_sites.csv", "id,capacity,cost,location\ns0,4,10,\"[52.5, 13.4]\"\n");
+    let battery_path = write_instance_file(&dir, "ids_battery.toml", &battery(0.5));
+    let trips_path = write_instance_file(
+        &dir,
+        "ids_trips.csv",
+        "id,isFree,startPeriod,endPeriod,osmDistance,startPoint,endPoint,potentialSites
+t0,False,10,12,1500.0,\"[52.5, 13.4]\",\"[52.6, 13.3]\",
+t1,False,14,16,1500.0,\"[52.5, 13.4]\",\"[52.6, 13.3]\",
+",
+    );
+    let vehicles_path = write_instance_file(&dir, "ids_vehicles.csv", "id,trips\nv0,[t0]\nv1,[t1]\n");
+    let other_vehicles_path = write_instance_file(&dir, "ids_vehicles_other.csv", "id,trips\nv0,[t1]\nv1,[t0]\n");
+
+    let sites = Site::load(&sites_path).unwrap();
+    let segments = Segment::load(&sites, &trips_path).unwrap();
+    let batteries = Battery::load_profiles(&[&battery_path]).unwrap();
+    let ids = |path: &str| {
+        Vehicle::load(&segments, path, &batteries)
+            .unwrap()
+            .iter()
+            .map(|v| v.id)
+            .collect::<Vec<u32>>()
+    };
+
+    let first = ids(&vehicles_path);
+    assert_eq!(first, ids(&vehicles_path));
+    assert_ne!(first[0], first[1]);
+    assert_ne!(first, ids(&other_vehicles_path));
+}